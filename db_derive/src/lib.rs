@@ -0,0 +1,203 @@
+//! `#[derive(DbData)]` for the models in `database::models`.
+//!
+//! ```ignore
+//! #[derive(DbData)]
+//! #[db(table = "patrol_logs", images)]
+//! pub struct DBPatrolLog {
+//!     pub id: uuid::Uuid,
+//!     pub heading: String,
+//!     #[db(skip)]
+//!     pub created_at: DateTime<Utc>,
+//! }
+//! ```
+//!
+//! Every field but `id` and the ones marked `#[db(skip)]` is a column written
+//! from the field of the same name on the params type, `DBPatrolLogParams`
+//! here, unless `params = OtherType` says otherwise. `images` takes the
+//! params' `img_params` as the item's images, `tags` takes the params' `tags`
+//! as the names of the item's tags, `prepare = some::path` runs
+//! `some::path(&mut params, &mut conn)` before each write and `soft_delete`
+//! marks a table whose rows go to the trash through a `deleted_at` column
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr, Path};
+
+#[proc_macro_derive(DbData, attributes(db))]
+pub fn derive_db_data(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct StructAttrs {
+    table: Option<LitStr>,
+    params: Option<Path>,
+    images: bool,
+    tags: bool,
+    prepare: Option<Path>,
+    soft_delete: bool,
+}
+
+fn struct_attrs(input: &DeriveInput) -> syn::Result<StructAttrs> {
+    let mut attrs = StructAttrs::default();
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("db")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                attrs.table = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("params") {
+                attrs.params = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("images") {
+                attrs.images = true;
+            } else if meta.path.is_ident("tags") {
+                attrs.tags = true;
+            } else if meta.path.is_ident("prepare") {
+                attrs.prepare = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("soft_delete") {
+                attrs.soft_delete = true;
+            } else {
+                return Err(meta.error(
+                    "expected `table`, `params`, `images`, `tags`, `prepare` or `soft_delete`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attrs)
+}
+
+fn is_skipped(field: &syn::Field) -> syn::Result<bool> {
+    let mut skip = false;
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("db")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `skip`"))
+            }
+        })?;
+    }
+    Ok(skip)
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let attrs = struct_attrs(&input)?;
+    let table = attrs.table.as_ref().ok_or_else(|| {
+        syn::Error::new_spanned(name, "missing #[db(table = \"...\")] on the struct")
+    })?;
+    let params = match &attrs.params {
+        Some(path) => quote!(#path),
+        None => {
+            let ident = format_ident!("{}Params", name);
+            quote!(#ident)
+        }
+    };
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(name, "DbData needs named fields")),
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "DbData can only be derived for structs",
+            ))
+        }
+    };
+    if !fields
+        .iter()
+        .any(|f| f.ident.as_ref().is_some_and(|i| i == "id"))
+    {
+        return Err(syn::Error::new_spanned(name, "DbData needs an `id` field"));
+    }
+    if attrs.soft_delete
+        && !fields
+            .iter()
+            .any(|f| f.ident.as_ref().is_some_and(|i| i == "deleted_at"))
+    {
+        return Err(syn::Error::new_spanned(
+            name,
+            "soft_delete needs a skipped `deleted_at` field",
+        ));
+    }
+
+    let mut columns: Vec<&Ident> = vec![];
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        if ident != "id" && !is_skipped(field)? {
+            columns.push(ident);
+        }
+    }
+    let column_names: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+
+    let take_images = if attrs.images {
+        quote! {
+            fn take_images(
+                params: &mut #params,
+            ) -> Option<Vec<crate::database::models::DBImageParams>> {
+                Some(params.img_params.drain(..).collect())
+            }
+        }
+    } else {
+        quote!()
+    };
+    let take_tags = if attrs.tags {
+        quote! {
+            fn take_tags(params: &mut #params) -> Option<Vec<String>> {
+                Some(std::mem::take(&mut params.tags))
+            }
+        }
+    } else {
+        quote!()
+    };
+    let prepare = match &attrs.prepare {
+        Some(path) => quote! {
+            async fn prepare(
+                params: &mut #params,
+                conn: &mut sqlx::PgConnection,
+            ) -> anyhow::Result<()> {
+                #path(params, conn).await
+            }
+        },
+        None => quote!(),
+    };
+    let soft_deletes = if attrs.soft_delete {
+        quote! {
+            fn soft_deletes() -> bool {
+                true
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    Ok(quote! {
+        impl crate::database::handles::DbData<#params> for #name {
+            fn id(&self) -> uuid::Uuid {
+                self.id
+            }
+            fn table_name() -> String {
+                #table.to_string()
+            }
+            fn fields() -> Vec<String> {
+                vec![#(#column_names.to_string()),*]
+            }
+            #take_images
+            #take_tags
+            #prepare
+            #soft_deletes
+            fn bind_tables(
+                params: #params,
+                query: crate::database::handles::QueryType<Self>,
+            ) -> crate::database::handles::QueryType<Self> {
+                query #(.bind(params.#columns))*
+            }
+        }
+    })
+}
@@ -0,0 +1,14 @@
+-- Add up migration script here
+CREATE TABLE
+    "sessions" (
+        id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+        admin_id UUID NOT NULL,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        expires_at TIMESTAMPTZ NOT NULL,
+        user_agent TEXT,
+        ip VARCHAR(64),
+        FOREIGN KEY (admin_id) REFERENCES admins(id) ON DELETE CASCADE
+    );
+
+CREATE INDEX sessions_admin_id_idx ON "sessions" (admin_id);
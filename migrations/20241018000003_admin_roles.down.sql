@@ -0,0 +1,2 @@
+ALTER TABLE "admins" DROP COLUMN IF EXISTS role;
+DROP TYPE IF EXISTS admin_role;
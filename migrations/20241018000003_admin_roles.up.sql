@@ -0,0 +1,6 @@
+-- Add up migration script here
+CREATE TYPE admin_role AS ENUM ('owner', 'editor', 'moderator', 'volunteer');
+
+-- every admin had full access before roles existed
+ALTER TABLE "admins" ADD COLUMN role admin_role NOT NULL DEFAULT 'owner';
+ALTER TABLE "admins" ALTER COLUMN role SET DEFAULT 'volunteer';
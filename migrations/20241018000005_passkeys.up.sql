@@ -0,0 +1,24 @@
+-- Add up migration script here
+CREATE TABLE
+    "passkeys" (
+        id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+        admin_id UUID NOT NULL,
+        name VARCHAR(100) NOT NULL,
+        credential_id TEXT NOT NULL UNIQUE,
+        passkey JSONB NOT NULL,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        last_used_at TIMESTAMPTZ,
+        FOREIGN KEY (admin_id) REFERENCES admins(id) ON DELETE CASCADE
+    );
+
+CREATE INDEX passkeys_admin_id_idx ON "passkeys" (admin_id);
+
+CREATE TABLE
+    "webauthn_challenges" (
+        id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+        admin_id UUID NOT NULL,
+        kind VARCHAR(20) NOT NULL,
+        state JSONB NOT NULL,
+        expires_at TIMESTAMPTZ NOT NULL,
+        FOREIGN KEY (admin_id) REFERENCES admins(id) ON DELETE CASCADE
+    );
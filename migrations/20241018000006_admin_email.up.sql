@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TABLE "admins" ADD COLUMN email VARCHAR(320) UNIQUE;
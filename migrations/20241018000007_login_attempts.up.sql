@@ -0,0 +1,14 @@
+-- Add up migration script here
+CREATE TABLE
+    "login_attempts" (
+        id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+        username VARCHAR(100) NOT NULL,
+        ip VARCHAR(64),
+        user_agent TEXT,
+        succeeded BOOLEAN NOT NULL,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    );
+
+CREATE INDEX login_attempts_username_idx ON "login_attempts" (username, created_at);
+
+CREATE INDEX login_attempts_ip_idx ON "login_attempts" (ip, created_at);
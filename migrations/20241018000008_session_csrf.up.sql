@@ -0,0 +1,4 @@
+-- Add up migration script here
+ALTER TABLE "sessions" ADD COLUMN csrf_token VARCHAR(64) NOT NULL DEFAULT md5(random()::text);
+
+ALTER TABLE "sessions" ALTER COLUMN csrf_token DROP DEFAULT;
@@ -0,0 +1,20 @@
+-- Add up migration script here
+CREATE TABLE
+    "audit_events" (
+        id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+        actor_id UUID,
+        actor_name VARCHAR(100) NOT NULL,
+        action VARCHAR(20) NOT NULL,
+        item_type VARCHAR(50),
+        item_id UUID,
+        snapshot JSONB,
+        ip VARCHAR(64),
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        FOREIGN KEY (actor_id) REFERENCES admins(id) ON DELETE SET NULL
+    );
+
+CREATE INDEX audit_events_created_at_idx ON "audit_events" (created_at);
+
+CREATE INDEX audit_events_actor_name_idx ON "audit_events" (actor_name, created_at);
+
+CREATE INDEX audit_events_item_idx ON "audit_events" (item_type, item_id);
@@ -0,0 +1,22 @@
+ALTER TABLE "dedications" ADD COLUMN IF NOT EXISTS img_ids UUID[] NOT NULL DEFAULT '{}';
+ALTER TABLE "patrol_logs" ADD COLUMN IF NOT EXISTS img_ids UUID[] NOT NULL DEFAULT '{}';
+ALTER TABLE "support_resources" ADD COLUMN IF NOT EXISTS img_ids UUID[] NOT NULL DEFAULT '{}';
+
+UPDATE "dedications" d SET img_ids = ARRAY(
+    SELECT image_id FROM "entity_images"
+    WHERE entity_type = 'dedications' AND entity_id = d.id ORDER BY position
+);
+UPDATE "patrol_logs" p SET img_ids = ARRAY(
+    SELECT image_id FROM "entity_images"
+    WHERE entity_type = 'patrol_logs' AND entity_id = p.id ORDER BY position
+);
+UPDATE "support_resources" s SET img_ids = ARRAY(
+    SELECT image_id FROM "entity_images"
+    WHERE entity_type = 'support_resources' AND entity_id = s.id ORDER BY position
+);
+
+ALTER TABLE "dedications" ALTER COLUMN img_ids DROP DEFAULT;
+ALTER TABLE "patrol_logs" ALTER COLUMN img_ids DROP DEFAULT;
+ALTER TABLE "support_resources" ALTER COLUMN img_ids DROP DEFAULT;
+
+DROP TABLE IF EXISTS "entity_images";
@@ -0,0 +1,53 @@
+-- Add up migration script here
+-- one row per image shown on an item, an image can be shared between items.
+-- entity_id can point at any of the tables below, so each table gets a
+-- generated column that is only set for its own rows and carries the real
+-- foreign key, deleting the item removes its links
+CREATE TABLE
+    "entity_images" (
+        entity_type VARCHAR(50) NOT NULL CHECK (
+            entity_type IN ('dedications', 'patrol_logs', 'support_resources')
+        ),
+        entity_id UUID NOT NULL,
+        image_id UUID NOT NULL,
+        position INT NOT NULL,
+        dedication_id UUID GENERATED ALWAYS AS (
+            CASE WHEN entity_type = 'dedications' THEN entity_id END
+        ) STORED,
+        patrol_log_id UUID GENERATED ALWAYS AS (
+            CASE WHEN entity_type = 'patrol_logs' THEN entity_id END
+        ) STORED,
+        support_resource_id UUID GENERATED ALWAYS AS (
+            CASE WHEN entity_type = 'support_resources' THEN entity_id END
+        ) STORED,
+        PRIMARY KEY (entity_type, entity_id, image_id),
+        FOREIGN KEY (image_id) REFERENCES images(id) ON DELETE CASCADE,
+        FOREIGN KEY (dedication_id) REFERENCES dedications(id) ON DELETE CASCADE,
+        FOREIGN KEY (patrol_log_id) REFERENCES patrol_logs(id) ON DELETE CASCADE,
+        FOREIGN KEY (support_resource_id) REFERENCES support_resources(id) ON DELETE CASCADE
+    );
+
+CREATE INDEX entity_images_position_idx ON "entity_images" (entity_type, entity_id, position);
+
+CREATE INDEX entity_images_image_idx ON "entity_images" (image_id);
+
+-- arrays could point at images that no longer exist, those are dropped
+INSERT INTO "entity_images" (entity_type, entity_id, image_id, position)
+SELECT DISTINCT ON (e.entity_type, e.entity_id, e.image_id)
+    e.entity_type, e.entity_id, e.image_id, e.position
+FROM (
+    SELECT 'dedications' AS entity_type, d.id AS entity_id, u.image_id, u.position::INT
+    FROM "dedications" d, unnest(d.img_ids) WITH ORDINALITY AS u(image_id, position)
+    UNION ALL
+    SELECT 'patrol_logs', p.id, u.image_id, u.position::INT
+    FROM "patrol_logs" p, unnest(p.img_ids) WITH ORDINALITY AS u(image_id, position)
+    UNION ALL
+    SELECT 'support_resources', s.id, u.image_id, u.position::INT
+    FROM "support_resources" s, unnest(s.img_ids) WITH ORDINALITY AS u(image_id, position)
+) e
+JOIN "images" i ON i.id = e.image_id
+ORDER BY e.entity_type, e.entity_id, e.image_id, e.position;
+
+ALTER TABLE "dedications" DROP COLUMN img_ids;
+ALTER TABLE "patrol_logs" DROP COLUMN img_ids;
+ALTER TABLE "support_resources" DROP COLUMN img_ids;
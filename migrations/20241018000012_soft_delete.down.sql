@@ -0,0 +1,9 @@
+-- Add down migration script here
+-- anything still in the trash shows up again rather than being lost
+ALTER TABLE "dedications" DROP COLUMN deleted_at;
+
+ALTER TABLE "patrol_logs" DROP COLUMN deleted_at;
+
+ALTER TABLE "testimonials" DROP COLUMN deleted_at;
+
+ALTER TABLE "support_resources" DROP COLUMN deleted_at;
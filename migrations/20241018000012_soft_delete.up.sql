@@ -0,0 +1,17 @@
+-- Add up migration script here
+ALTER TABLE "dedications" ADD COLUMN deleted_at TIMESTAMPTZ;
+
+ALTER TABLE "patrol_logs" ADD COLUMN deleted_at TIMESTAMPTZ;
+
+ALTER TABLE "testimonials" ADD COLUMN deleted_at TIMESTAMPTZ;
+
+ALTER TABLE "support_resources" ADD COLUMN deleted_at TIMESTAMPTZ;
+
+-- the trash and the purge task only ever look at deleted rows
+CREATE INDEX dedications_deleted_at_idx ON "dedications" (deleted_at) WHERE deleted_at IS NOT NULL;
+
+CREATE INDEX patrol_logs_deleted_at_idx ON "patrol_logs" (deleted_at) WHERE deleted_at IS NOT NULL;
+
+CREATE INDEX testimonials_deleted_at_idx ON "testimonials" (deleted_at) WHERE deleted_at IS NOT NULL;
+
+CREATE INDEX support_resources_deleted_at_idx ON "support_resources" (deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,46 @@
+-- Add up migration script here
+CREATE TABLE
+    "revisions" (
+        id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+        entity_type VARCHAR(50) NOT NULL,
+        entity_id UUID NOT NULL,
+        snapshot JSONB NOT NULL,
+        image_ids UUID[] NOT NULL DEFAULT '{}',
+        author_id UUID,
+        author_name VARCHAR(100) NOT NULL,
+        -- NOW() is fixed for a whole transaction, so revisions recorded in one
+        -- would tie and come back in any order
+        created_at TIMESTAMPTZ NOT NULL DEFAULT clock_timestamp(),
+        FOREIGN KEY (author_id) REFERENCES admins(id) ON DELETE SET NULL
+    );
+
+CREATE INDEX revisions_entity_idx ON "revisions" (entity_type, entity_id, created_at);
+
+-- images are only deleted once no revision can bring them back
+CREATE INDEX revisions_image_ids_idx ON "revisions" USING GIN (image_ids);
+
+-- what's there now is the first revision of everything
+INSERT INTO "revisions" (entity_type, entity_id, snapshot, image_ids, author_name)
+SELECT 'dedications', d.id, to_jsonb(d.*), ARRAY(
+    SELECT image_id FROM "entity_images"
+    WHERE entity_type = 'dedications' AND entity_id = d.id ORDER BY position
+), 'unknown'
+FROM "dedications" d;
+
+INSERT INTO "revisions" (entity_type, entity_id, snapshot, image_ids, author_name)
+SELECT 'patrol_logs', p.id, to_jsonb(p.*), ARRAY(
+    SELECT image_id FROM "entity_images"
+    WHERE entity_type = 'patrol_logs' AND entity_id = p.id ORDER BY position
+), 'unknown'
+FROM "patrol_logs" p;
+
+INSERT INTO "revisions" (entity_type, entity_id, snapshot, image_ids, author_name)
+SELECT 'support_resources', s.id, to_jsonb(s.*), ARRAY(
+    SELECT image_id FROM "entity_images"
+    WHERE entity_type = 'support_resources' AND entity_id = s.id ORDER BY position
+), 'unknown'
+FROM "support_resources" s;
+
+INSERT INTO "revisions" (entity_type, entity_id, snapshot, author_name)
+SELECT 'testimonials', t.id, to_jsonb(t.*), 'unknown'
+FROM "testimonials" t;
@@ -0,0 +1,11 @@
+-- Add down migration script here
+-- drafts and scheduled items go live rather than being lost
+ALTER TABLE "dedications" DROP COLUMN status, DROP COLUMN publish_at;
+
+ALTER TABLE "patrol_logs" DROP COLUMN status, DROP COLUMN publish_at;
+
+ALTER TABLE "testimonials" DROP COLUMN status, DROP COLUMN publish_at;
+
+ALTER TABLE "support_resources" DROP COLUMN status, DROP COLUMN publish_at;
+
+DROP TYPE IF EXISTS content_status;
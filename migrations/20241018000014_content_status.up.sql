@@ -0,0 +1,28 @@
+-- Add up migration script here
+CREATE TYPE content_status AS ENUM ('draft', 'scheduled', 'published');
+
+-- everything uploaded so far is already live
+ALTER TABLE "dedications" ADD COLUMN status content_status NOT NULL DEFAULT 'published',
+    ADD COLUMN publish_at TIMESTAMPTZ,
+    ADD CONSTRAINT dedications_scheduled_check CHECK (status <> 'scheduled' OR publish_at IS NOT NULL);
+
+ALTER TABLE "patrol_logs" ADD COLUMN status content_status NOT NULL DEFAULT 'published',
+    ADD COLUMN publish_at TIMESTAMPTZ,
+    ADD CONSTRAINT patrol_logs_scheduled_check CHECK (status <> 'scheduled' OR publish_at IS NOT NULL);
+
+ALTER TABLE "testimonials" ADD COLUMN status content_status NOT NULL DEFAULT 'published',
+    ADD COLUMN publish_at TIMESTAMPTZ,
+    ADD CONSTRAINT testimonials_scheduled_check CHECK (status <> 'scheduled' OR publish_at IS NOT NULL);
+
+ALTER TABLE "support_resources" ADD COLUMN status content_status NOT NULL DEFAULT 'published',
+    ADD COLUMN publish_at TIMESTAMPTZ,
+    ADD CONSTRAINT support_resources_scheduled_check CHECK (status <> 'scheduled' OR publish_at IS NOT NULL);
+
+-- the scheduler only ever looks at scheduled rows
+CREATE INDEX dedications_publish_at_idx ON "dedications" (publish_at) WHERE status = 'scheduled';
+
+CREATE INDEX patrol_logs_publish_at_idx ON "patrol_logs" (publish_at) WHERE status = 'scheduled';
+
+CREATE INDEX testimonials_publish_at_idx ON "testimonials" (publish_at) WHERE status = 'scheduled';
+
+CREATE INDEX support_resources_publish_at_idx ON "support_resources" (publish_at) WHERE status = 'scheduled';
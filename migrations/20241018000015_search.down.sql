@@ -0,0 +1,10 @@
+-- Add down migration script here
+ALTER TABLE "patrol_logs" DROP COLUMN search;
+
+ALTER TABLE "dedications" DROP COLUMN search;
+
+ALTER TABLE "testimonials" DROP COLUMN search;
+
+ALTER TABLE "support_resources" DROP COLUMN search;
+
+DROP FUNCTION IF EXISTS search_text(text[]);
@@ -0,0 +1,36 @@
+-- Add up migration script here
+-- array_to_string is only stable, generated columns need an immutable one
+CREATE FUNCTION search_text(text[]) RETURNS text
+    LANGUAGE sql IMMUTABLE PARALLEL SAFE
+    AS $$ SELECT array_to_string($1, ' ') $$;
+
+-- what names an item weighs more than what's said about it
+ALTER TABLE "patrol_logs" ADD COLUMN search tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', heading), 'A')
+    || setweight(to_tsvector('english', description), 'B')
+) STORED;
+
+ALTER TABLE "dedications" ADD COLUMN search tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', search_text(names)), 'A')
+    || setweight(to_tsvector('english', bio), 'B')
+) STORED;
+
+ALTER TABLE "testimonials" ADD COLUMN search tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', firstname || ' ' || lastname), 'A')
+    || setweight(to_tsvector('english', content), 'B')
+    || setweight(to_tsvector('english', coalesce(bio, '')), 'C')
+) STORED;
+
+ALTER TABLE "support_resources" ADD COLUMN search tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('english', name), 'A')
+    || setweight(to_tsvector('english', search_text(missions)), 'B')
+    || setweight(to_tsvector('english', description), 'B')
+) STORED;
+
+CREATE INDEX patrol_logs_search_idx ON "patrol_logs" USING GIN (search);
+
+CREATE INDEX dedications_search_idx ON "dedications" USING GIN (search);
+
+CREATE INDEX testimonials_search_idx ON "testimonials" USING GIN (search);
+
+CREATE INDEX support_resources_search_idx ON "support_resources" USING GIN (search);
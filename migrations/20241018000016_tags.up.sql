@@ -0,0 +1,31 @@
+-- Add up migration script here
+CREATE TABLE
+    "tags" (
+        id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+        name VARCHAR(100) NOT NULL,
+        -- how the tag shows up in urls, and what keeps "PTSD" and "ptsd" one tag
+        slug VARCHAR(100) NOT NULL UNIQUE
+    );
+
+-- one row per tag on an item. Like entity_images, each table gets a generated
+-- column that carries the real foreign key, deleting the item removes its tags
+CREATE TABLE
+    "entity_tags" (
+        entity_type VARCHAR(50) NOT NULL CHECK (
+            entity_type IN ('patrol_logs', 'support_resources')
+        ),
+        entity_id UUID NOT NULL,
+        tag_id UUID NOT NULL,
+        patrol_log_id UUID GENERATED ALWAYS AS (
+            CASE WHEN entity_type = 'patrol_logs' THEN entity_id END
+        ) STORED,
+        support_resource_id UUID GENERATED ALWAYS AS (
+            CASE WHEN entity_type = 'support_resources' THEN entity_id END
+        ) STORED,
+        PRIMARY KEY (entity_type, entity_id, tag_id),
+        FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE,
+        FOREIGN KEY (patrol_log_id) REFERENCES patrol_logs(id) ON DELETE CASCADE,
+        FOREIGN KEY (support_resource_id) REFERENCES support_resources(id) ON DELETE CASCADE
+    );
+
+CREATE INDEX entity_tags_tag_idx ON "entity_tags" (tag_id, entity_type);
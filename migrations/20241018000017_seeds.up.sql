@@ -0,0 +1,12 @@
+-- Add up migration script here
+-- seed file entries already loaded, so restarts don't load them again and
+-- content an admin deleted stays deleted
+CREATE TABLE
+    "seeds" (
+        entity_type VARCHAR(50) NOT NULL,
+        key VARCHAR(255) NOT NULL,
+        -- not a foreign key, the row can be gone by now
+        entity_id UUID NOT NULL,
+        seeded_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+        PRIMARY KEY (entity_type, key)
+    );
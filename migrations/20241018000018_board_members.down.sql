@@ -0,0 +1,16 @@
+-- Add down migration script here
+DELETE FROM "entity_images" WHERE entity_type = 'board_members';
+
+ALTER TABLE "entity_images" DROP COLUMN board_member_id;
+
+ALTER TABLE "entity_images" DROP CONSTRAINT entity_images_entity_type_check;
+
+ALTER TABLE "entity_images" ADD CONSTRAINT entity_images_entity_type_check CHECK (
+    entity_type IN ('dedications', 'patrol_logs', 'support_resources')
+);
+
+DELETE FROM "revisions" WHERE entity_type = 'board_members';
+
+DELETE FROM "seeds" WHERE entity_type = 'board_members';
+
+DROP TABLE IF EXISTS "board_members";
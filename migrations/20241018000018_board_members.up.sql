@@ -0,0 +1,27 @@
+-- Add up migration script here
+CREATE TABLE
+    "board_members" (
+        id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+        name VARCHAR(100) NOT NULL,
+        role VARCHAR(100) NOT NULL,
+        bio TEXT NOT NULL,
+        -- where About Us shows the member, lowest first. New members go after
+        -- everyone else, reordering numbers them from 1 again
+        position INT GENERATED BY DEFAULT AS IDENTITY,
+        -- inactive members are kept but left off the page
+        active BOOLEAN NOT NULL DEFAULT TRUE,
+        deleted_at TIMESTAMPTZ
+    );
+
+CREATE INDEX board_members_deleted_at_idx ON "board_members" (deleted_at) WHERE deleted_at IS NOT NULL;
+
+-- the photo goes through entity_images like the images of everything else
+ALTER TABLE "entity_images" DROP CONSTRAINT entity_images_entity_type_check;
+
+ALTER TABLE "entity_images" ADD CONSTRAINT entity_images_entity_type_check CHECK (
+    entity_type IN ('dedications', 'patrol_logs', 'support_resources', 'board_members')
+);
+
+ALTER TABLE "entity_images" ADD COLUMN board_member_id UUID GENERATED ALWAYS AS (
+    CASE WHEN entity_type = 'board_members' THEN entity_id END
+) STORED REFERENCES board_members(id) ON DELETE CASCADE;
@@ -0,0 +1,16 @@
+-- Add down migration script here
+DELETE FROM "entity_images" WHERE entity_type = 'landing_slides';
+
+ALTER TABLE "entity_images" DROP COLUMN landing_slide_id;
+
+ALTER TABLE "entity_images" DROP CONSTRAINT entity_images_entity_type_check;
+
+ALTER TABLE "entity_images" ADD CONSTRAINT entity_images_entity_type_check CHECK (
+    entity_type IN ('dedications', 'patrol_logs', 'support_resources', 'board_members')
+);
+
+DELETE FROM "revisions" WHERE entity_type = 'landing_slides';
+
+DELETE FROM "seeds" WHERE entity_type = 'landing_slides';
+
+DROP TABLE IF EXISTS "landing_slides";
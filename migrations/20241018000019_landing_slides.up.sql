@@ -0,0 +1,30 @@
+-- Add up migration script here
+CREATE TABLE
+    "landing_slides" (
+        id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+        alt TEXT NOT NULL,
+        subtitle TEXT NOT NULL,
+        -- where the landing carousel shows the slide, lowest first, numbered
+        -- the same way as board_members
+        position INT GENERATED BY DEFAULT AS IDENTITY,
+        active BOOLEAN NOT NULL DEFAULT TRUE,
+        deleted_at TIMESTAMPTZ
+    );
+
+CREATE INDEX landing_slides_deleted_at_idx ON "landing_slides" (deleted_at) WHERE deleted_at IS NOT NULL;
+
+ALTER TABLE "entity_images" DROP CONSTRAINT entity_images_entity_type_check;
+
+ALTER TABLE "entity_images" ADD CONSTRAINT entity_images_entity_type_check CHECK (
+    entity_type IN (
+        'dedications',
+        'patrol_logs',
+        'support_resources',
+        'board_members',
+        'landing_slides'
+    )
+);
+
+ALTER TABLE "entity_images" ADD COLUMN landing_slide_id UUID GENERATED ALWAYS AS (
+    CASE WHEN entity_type = 'landing_slides' THEN entity_id END
+) STORED REFERENCES landing_slides(id) ON DELETE CASCADE;
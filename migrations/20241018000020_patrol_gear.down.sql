@@ -0,0 +1,24 @@
+-- Add down migration script here
+DELETE FROM "entity_images" WHERE entity_type = 'patrol_gear';
+
+ALTER TABLE "entity_images" DROP COLUMN patrol_gear_id;
+
+ALTER TABLE "entity_images" DROP CONSTRAINT entity_images_entity_type_check;
+
+ALTER TABLE "entity_images" ADD CONSTRAINT entity_images_entity_type_check CHECK (
+    entity_type IN (
+        'dedications',
+        'patrol_logs',
+        'support_resources',
+        'board_members',
+        'landing_slides'
+    )
+);
+
+DELETE FROM "revisions" WHERE entity_type = 'patrol_gear';
+
+DELETE FROM "seeds" WHERE entity_type = 'patrol_gear';
+
+DROP TABLE IF EXISTS "patrol_gear";
+
+DROP TYPE IF EXISTS gear_category;
@@ -0,0 +1,37 @@
+-- Add up migration script here
+CREATE TYPE gear_category AS ENUM ('tops', 'hats', 'misc');
+
+CREATE TABLE
+    "patrol_gear" (
+        id UUID NOT NULL PRIMARY KEY DEFAULT (uuid_generate_v4()),
+        name VARCHAR(100) NOT NULL,
+        category gear_category NOT NULL,
+        -- suggested donation in whole dollars
+        price INT NOT NULL CHECK (price >= 0),
+        description TEXT NOT NULL,
+        -- sizes and colors with how many are in stock, an empty list means
+        -- there's one kind and its stock isn't tracked
+        variants JSONB NOT NULL DEFAULT '[]',
+        position INT GENERATED BY DEFAULT AS IDENTITY,
+        active BOOLEAN NOT NULL DEFAULT TRUE,
+        deleted_at TIMESTAMPTZ
+    );
+
+CREATE INDEX patrol_gear_deleted_at_idx ON "patrol_gear" (deleted_at) WHERE deleted_at IS NOT NULL;
+
+ALTER TABLE "entity_images" DROP CONSTRAINT entity_images_entity_type_check;
+
+ALTER TABLE "entity_images" ADD CONSTRAINT entity_images_entity_type_check CHECK (
+    entity_type IN (
+        'dedications',
+        'patrol_logs',
+        'support_resources',
+        'board_members',
+        'landing_slides',
+        'patrol_gear'
+    )
+);
+
+ALTER TABLE "entity_images" ADD COLUMN patrol_gear_id UUID GENERATED ALWAYS AS (
+    CASE WHEN entity_type = 'patrol_gear' THEN entity_id END
+) STORED REFERENCES patrol_gear(id) ON DELETE CASCADE;
@@ -0,0 +1,34 @@
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// Marks a bearer value as an API token rather than a session token
+pub const PREFIX: &str = "sf_";
+/// Longest expiry that can be picked when creating a token
+pub const MAX_EXPIRY_DAYS: i64 = 365;
+
+/// A new token, only ever shown to the admin once
+pub fn generate() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}{}", PREFIX, hex)
+}
+
+/// What gets stored and looked up, the token itself is never kept
+pub fn hash(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.trim().as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate, hash, PREFIX};
+
+    #[test]
+    fn tokens_are_prefixed_and_hashed() {
+        let token = generate();
+        assert!(token.starts_with(PREFIX));
+        assert_eq!(token.len(), PREFIX.len() + 64);
+        assert_eq!(hash(&token), hash(&format!(" {} ", token)));
+        assert_ne!(hash(&token), hash(&generate()));
+    }
+}
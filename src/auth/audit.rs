@@ -0,0 +1,104 @@
+use serde::Serialize;
+use sqlx::{types::Json, Pool, Postgres};
+use tracing::warn;
+use uuid::Uuid;
+
+use super::client::ClientInfo;
+use crate::database::{
+    handles::DbData,
+    models::{DBAdmin, DBAuditEvent, DBAuditEventParams},
+};
+
+/// Item type sessions are recorded under for logins and logouts
+pub const SESSION_ITEM: &str = "session";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Upload,
+    Edit,
+    Delete,
+    Restore,
+    Purge,
+    Rollback,
+    Reorder,
+    Login,
+    Logout,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Upload => "upload",
+            Self::Edit => "edit",
+            Self::Delete => "delete",
+            Self::Restore => "restore",
+            Self::Purge => "purge",
+            Self::Rollback => "rollback",
+            Self::Reorder => "reorder",
+            Self::Login => "login",
+            Self::Logout => "logout",
+        }
+    }
+}
+
+/// What an event was about
+#[derive(Debug, Clone)]
+pub struct Subject {
+    pub item_type: String,
+    pub item_id: Uuid,
+    pub snapshot: Option<serde_json::Value>,
+}
+
+impl Subject {
+    /// The affected row as it was right after an upload, edit, restore or
+    /// rollback, or right before a delete or purge
+    pub fn row(item_type: &str, item_id: Uuid, row: &impl Serialize) -> Self {
+        Self {
+            item_type: item_type.to_string(),
+            item_id,
+            snapshot: serde_json::to_value(row)
+                .map_err(|err| warn!("failed to snapshot {} {}: {:?}", item_type, item_id, err))
+                .ok(),
+        }
+    }
+
+    pub fn session(id: Uuid) -> Self {
+        Self {
+            item_type: SESSION_ITEM.to_string(),
+            item_id: id,
+            snapshot: None,
+        }
+    }
+}
+
+/// Writes an event to the audit log. A failure to record is logged rather than
+/// failing the change it describes
+pub async fn record(
+    pool: &Pool<Postgres>,
+    actor: &DBAdmin,
+    client: &ClientInfo,
+    action: AuditAction,
+    subject: Option<Subject>,
+) {
+    let (item_type, item_id, snapshot) = match subject {
+        Some(s) => (Some(s.item_type), Some(s.item_id), s.snapshot.map(Json)),
+        None => (None, None, None),
+    };
+    let params = DBAuditEventParams {
+        actor_id: Some(actor.id),
+        actor_name: actor.username.clone(),
+        action: action.as_str().to_string(),
+        item_type,
+        item_id,
+        snapshot,
+        ip: client.ip.clone(),
+    };
+    if let Err(err) = DBAuditEvent::insert_one(params, pool).await {
+        warn!(
+            "failed to record {} by {}: {:?}",
+            action.as_str(),
+            actor.username,
+            err
+        );
+    }
+}
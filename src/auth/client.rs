@@ -0,0 +1,112 @@
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRef, FromRequestParts},
+    http::{header, request::Parts},
+};
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+};
+
+use crate::state::SharedState;
+
+const FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Who is on the other end of a request, as far as we can tell
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// The address a request came from. `X-Forwarded-For` is only followed while
+/// the hop that wrote it is a trusted proxy, so the first untrusted hop from
+/// the right is the client and anything left of it may be forged
+fn client_ip(peer: Option<IpAddr>, forwarded: &str, trusted: &[IpAddr]) -> Option<IpAddr> {
+    let mut ip = peer?;
+    if !trusted.contains(&ip) {
+        return Some(ip);
+    }
+    for hop in forwarded.rsplit(',').map(str::trim) {
+        match hop.parse::<IpAddr>() {
+            Ok(hop) => {
+                ip = hop;
+                if !trusted.contains(&ip) {
+                    break;
+                }
+            }
+            // nothing past a hop we can't read can be vouched for
+            Err(_) => break,
+        }
+    }
+    Some(ip)
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    SharedState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let state = SharedState::from_ref(state);
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let forwarded = parts
+            .headers
+            .get_all(FORWARDED_FOR)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .collect::<Vec<_>>()
+            .join(",");
+        let ip = client_ip(peer, &forwarded, &state.read().await.env.trusted_proxies)
+            .map(|ip| ip.to_string());
+
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+
+        Ok(Self { ip, user_agent })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::client_ip;
+    use std::net::IpAddr;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn forwarded_for_ignored_from_untrusted_peer() {
+        let trusted = [ip("10.0.0.1")];
+        assert_eq!(
+            client_ip(Some(ip("203.0.113.7")), "1.2.3.4", &trusted),
+            Some(ip("203.0.113.7"))
+        );
+        assert_eq!(client_ip(None, "1.2.3.4", &trusted), None);
+    }
+
+    #[test]
+    fn forwarded_for_takes_rightmost_untrusted_hop() {
+        let trusted = [ip("10.0.0.1"), ip("10.0.0.2")];
+        let peer = Some(ip("10.0.0.1"));
+        assert_eq!(
+            client_ip(peer, "1.2.3.4, 203.0.113.7, 10.0.0.2", &trusted),
+            Some(ip("203.0.113.7"))
+        );
+        assert_eq!(
+            client_ip(peer, "not an ip, 10.0.0.2", &trusted),
+            Some(ip("10.0.0.2"))
+        );
+        assert_eq!(client_ip(peer, "", &trusted), Some(ip("10.0.0.1")));
+    }
+}
@@ -0,0 +1,38 @@
+use rand::{rngs::OsRng, RngCore};
+
+/// Header htmx sends the session's token in, set through `hx-headers` on the
+/// admin forms
+pub const HEADER_NAME: &str = "x-csrf-token";
+
+/// Fresh token for a new session, hex encoded
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Compares without bailing on the first differing byte, so the time taken
+/// says nothing about how much of the token was right
+pub fn tokens_match(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_token, tokens_match};
+
+    #[test]
+    fn only_the_exact_token_matches() {
+        let token = generate_token();
+        assert_eq!(token.len(), 64);
+        assert!(tokens_match(&token, &token.clone()));
+        assert!(!tokens_match(&token, &token[..63]));
+        assert!(!tokens_match(&token, &generate_token()));
+        assert!(!tokens_match(&token, ""));
+    }
+}
@@ -1,5 +1,6 @@
 use reqwest::StatusCode;
 
+use super::permissions::Permission;
 use crate::error::{error_chain_fmt, InternalError};
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 
@@ -11,6 +12,14 @@ pub enum AuthError {
     JsonWebToken(#[from] jsonwebtoken::errors::Error),
     Uuid(#[from] uuid::Error),
     NotLoggedIn,
+    InvalidSecondFactor,
+    PasskeyRejected,
+    /// seconds until another login attempt is allowed
+    TooManyAttempts(i64),
+    Forbidden(Permission),
+    CsrfMismatch,
+    SessionRequired,
+    UnknownItem,
 }
 
 impl Debug for AuthError {
@@ -26,6 +35,13 @@ impl Display for AuthError {
             Self::JsonWebToken(err) => err.to_string(),
             Self::Uuid(err) => err.to_string(),
             Self::NotLoggedIn => "Not Logged In".to_string(),
+            Self::InvalidSecondFactor => "Invalid Second Factor".to_string(),
+            Self::PasskeyRejected => "Passkey Rejected".to_string(),
+            Self::TooManyAttempts(seconds) => format!("Too Many Attempts: {}s", seconds),
+            Self::Forbidden(permission) => format!("Missing Permission: {}", permission),
+            Self::CsrfMismatch => "CSRF Token Mismatch".to_string(),
+            Self::SessionRequired => "Session Required".to_string(),
+            Self::UnknownItem => "Unknown Item".to_string(),
         };
         writeln!(f, "{}", display)
     }
@@ -34,7 +50,14 @@ impl Display for AuthError {
 impl InternalError for AuthError {
     fn status_code(&self) -> StatusCode {
         match self {
-            Self::NotLoggedIn => StatusCode::UNAUTHORIZED,
+            Self::NotLoggedIn | Self::InvalidSecondFactor | Self::PasskeyRejected => {
+                StatusCode::UNAUTHORIZED
+            }
+            Self::Forbidden(_) | Self::CsrfMismatch | Self::SessionRequired => {
+                StatusCode::FORBIDDEN
+            }
+            Self::TooManyAttempts(_) => StatusCode::TOO_MANY_REQUESTS,
+            Self::UnknownItem => StatusCode::NOT_FOUND,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -42,6 +65,22 @@ impl InternalError for AuthError {
     fn public_message(&self) -> String {
         match self {
             Self::NotLoggedIn => "You are not logged in".to_string(),
+            Self::InvalidSecondFactor => "That authentication code is not valid".to_string(),
+            Self::PasskeyRejected => "That passkey could not be verified".to_string(),
+            Self::TooManyAttempts(seconds) => format!(
+                "Too many failed login attempts, try again in {} seconds",
+                seconds
+            ),
+            Self::Forbidden(permission) => {
+                format!("You do not have the {} permission", permission)
+            }
+            Self::CsrfMismatch => {
+                "Your session's form token is missing or stale, reload the page".to_string()
+            }
+            Self::SessionRequired => {
+                "API tokens can only be used to upload and delete content".to_string()
+            }
+            Self::UnknownItem => "That item type does not exist".to_string(),
             Self::JsonWebToken(err) => format!("A web token error occurred: {:?}", err),
             Self::Undefined(err) => format!("An undefined error occurred: {:?}", err),
             Self::Uuid(err) => format!("A uuid error occurred: {:?}", err),
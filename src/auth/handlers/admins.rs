@@ -0,0 +1,225 @@
+use axum::{
+    extract::{Path, State},
+    Extension, Form,
+};
+use reqwest::StatusCode;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        error::AuthError,
+        middleware::AdminExtension,
+        model::{ChangeRoleSchema, CreateAdminSchema},
+        password,
+        permissions::Role,
+    },
+    database::{
+        handles::DbData,
+        models::{DBAdmin, DBAdminParams},
+    },
+    error::{DataApiReturn, DataResponse, InternalError},
+    state::SharedState,
+};
+
+const MIN_PASSWORD_LEN: usize = 12;
+
+#[tracing::instrument(name = "create admin handler", skip_all)]
+pub async fn create_admin_handler(
+    State(data): State<SharedState>,
+    Form(body): Form<CreateAdminSchema>,
+) -> Result<DataApiReturn, DataApiReturn> {
+    let username = body.username.trim().to_string();
+    if username.is_empty() {
+        return Err(DataResponse::error(
+            "username cannot be empty",
+            Some(StatusCode::BAD_REQUEST),
+        ));
+    }
+    if body.password.len() < MIN_PASSWORD_LEN {
+        return Err(DataResponse::error(
+            format!("password must be at least {} characters", MIN_PASSWORD_LEN),
+            Some(StatusCode::BAD_REQUEST),
+        ));
+    }
+
+    let email = body
+        .email
+        .map(|e| e.trim().to_lowercase())
+        .filter(|e| !e.is_empty());
+    if email.as_ref().is_some_and(|e| !e.contains('@')) {
+        return Err(DataResponse::error(
+            "that is not a valid email address",
+            Some(StatusCode::BAD_REQUEST),
+        ));
+    }
+
+    let r = data.read().await;
+    let existing = DBAdmin::get_by_username(&r.db, &username)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    if existing.is_some() {
+        return Err(DataResponse::error(
+            format!("{} is already an admin", username),
+            Some(StatusCode::CONFLICT),
+        ));
+    }
+    if let Some(email) = email.as_ref() {
+        let existing = DBAdmin::get_by_email(&r.db, email)
+            .await
+            .map_err(|err| AuthError::from(err).into_data_api_return())?;
+        if existing.is_some() {
+            return Err(DataResponse::error(
+                format!("{} already belongs to an admin", email),
+                Some(StatusCode::CONFLICT),
+            ));
+        }
+    }
+
+    let params = DBAdminParams {
+        username,
+        password_hash: password::hash_password(&body.password)
+            .map_err(|err| AuthError::from(err).into_data_api_return())?,
+        disabled: false,
+        role: body.role,
+        email,
+    };
+    let admin = DBAdmin::insert_one(params, &r.db)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    Ok(DataResponse::success(format!(
+        "succesfully created admin {}",
+        admin.username
+    )))
+}
+
+/// Guards against an admin locking themselves, or everyone, out of the site
+async fn ensure_can_remove_access(
+    data: &SharedState,
+    acting: &DBAdmin,
+    target_id: Uuid,
+) -> Result<DBAdmin, DataApiReturn> {
+    if acting.id == target_id {
+        return Err(DataResponse::error(
+            "you cannot disable or remove your own account",
+            Some(StatusCode::BAD_REQUEST),
+        ));
+    }
+    let r = data.read().await;
+    let target = DBAdmin::get_single_by(&r.db, target_id)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?
+        .ok_or(DataResponse::error(
+            "no admin with that id",
+            Some(StatusCode::NOT_FOUND),
+        ))?;
+    let active = DBAdmin::count_active(&r.db)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    if !target.disabled && active <= 1 {
+        return Err(DataResponse::error(
+            "cannot remove the last active admin",
+            Some(StatusCode::BAD_REQUEST),
+        ));
+    }
+    Ok(target)
+}
+
+#[tracing::instrument(name = "disable admin handler", skip(data, admin_ext))]
+pub async fn disable_admin_handler(
+    Path(id): Path<Uuid>,
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+) -> Result<DataApiReturn, DataApiReturn> {
+    ensure_can_remove_access(&data, &admin_ext.admin, id).await?;
+    let admin = DBAdmin::set_disabled(&data.read().await.db, id, true)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    warn!(
+        "{} disabled admin {}",
+        admin_ext.admin.username, admin.username
+    );
+    Ok(DataResponse::success(format!(
+        "succesfully disabled admin {}",
+        admin.username
+    )))
+}
+
+#[tracing::instrument(name = "enable admin handler", skip(data))]
+pub async fn enable_admin_handler(
+    Path(id): Path<Uuid>,
+    State(data): State<SharedState>,
+) -> Result<DataApiReturn, DataApiReturn> {
+    let admin = DBAdmin::set_disabled(&data.read().await.db, id, false)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    Ok(DataResponse::success(format!(
+        "succesfully enabled admin {}",
+        admin.username
+    )))
+}
+
+#[tracing::instrument(name = "remove admin handler", skip(data, admin_ext))]
+pub async fn remove_admin_handler(
+    Path(id): Path<Uuid>,
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+) -> Result<DataApiReturn, DataApiReturn> {
+    ensure_can_remove_access(&data, &admin_ext.admin, id).await?;
+    let admin = DBAdmin::delete_one_with_id(id, &data.read().await.db)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    warn!(
+        "{} removed admin {}",
+        admin_ext.admin.username, admin.username
+    );
+    Ok(DataResponse::success(format!(
+        "succesfully removed admin {}",
+        admin.username
+    )))
+}
+
+#[tracing::instrument(name = "change admin role handler", skip(data, admin_ext))]
+pub async fn change_role_handler(
+    Path(id): Path<Uuid>,
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    Form(body): Form<ChangeRoleSchema>,
+) -> Result<DataApiReturn, DataApiReturn> {
+    if admin_ext.admin.id == id {
+        return Err(DataResponse::error(
+            "you cannot change your own role",
+            Some(StatusCode::BAD_REQUEST),
+        ));
+    }
+    let r = data.read().await;
+    let target = DBAdmin::get_single_by(&r.db, id)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?
+        .ok_or(DataResponse::error(
+            "no admin with that id",
+            Some(StatusCode::NOT_FOUND),
+        ))?;
+    if target.role == Role::Owner && body.role != Role::Owner && !target.disabled {
+        let owners = DBAdmin::count_active_owners(&r.db)
+            .await
+            .map_err(|err| AuthError::from(err).into_data_api_return())?;
+        if owners <= 1 {
+            return Err(DataResponse::error(
+                "cannot demote the last active owner",
+                Some(StatusCode::BAD_REQUEST),
+            ));
+        }
+    }
+    let admin = DBAdmin::set_role(&r.db, id, body.role)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    warn!(
+        "{} changed the role of {} to {}",
+        admin_ext.admin.username, admin.username, admin.role
+    );
+    Ok(DataResponse::success(format!(
+        "{} is now {}",
+        admin.username, admin.role
+    )))
+}
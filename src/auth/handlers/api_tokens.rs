@@ -0,0 +1,309 @@
+use axum::{
+    extract::{Path, State},
+    Extension, Form, Json,
+};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        api_token::{self, MAX_EXPIRY_DAYS},
+        error::AuthError,
+        middleware::AdminExtension,
+        model::CreateApiTokenSchema,
+        permissions::Scope,
+    },
+    database::{
+        handles::DbData,
+        models::{DBApiToken, DBApiTokenParams},
+    },
+    error::{DataApiReturn, DataResponse, InternalError},
+    state::SharedState,
+};
+
+const MAX_NAME_LEN: usize = 100;
+
+/// Creates a token for the acting admin. The token is only ever in this
+/// response, afterwards just its hash is kept
+#[tracing::instrument(name = "create api token", skip_all)]
+pub async fn create_api_token_handler(
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    Form(body): Form<CreateApiTokenSchema>,
+) -> Result<Json<Value>, DataApiReturn> {
+    let name = body.name.trim().to_string();
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(DataResponse::error(
+            format!("name must be between 1 and {} characters", MAX_NAME_LEN),
+            Some(StatusCode::BAD_REQUEST),
+        ));
+    }
+    if !(1..=MAX_EXPIRY_DAYS).contains(&body.expires_in_days) {
+        return Err(DataResponse::error(
+            format!("tokens must expire within {} days", MAX_EXPIRY_DAYS),
+            Some(StatusCode::BAD_REQUEST),
+        ));
+    }
+
+    let scopes = body
+        .scopes
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            Scope::try_from(s)
+                .map(|scope| scope.to_string())
+                .map_err(|err| DataResponse::error(err, Some(StatusCode::BAD_REQUEST)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if scopes.is_empty() {
+        return Err(DataResponse::error(
+            "a token needs at least one scope",
+            Some(StatusCode::BAD_REQUEST),
+        ));
+    }
+
+    let token = api_token::generate();
+    let created = DBApiToken::insert_one(
+        DBApiTokenParams {
+            admin_id: admin_ext.admin.id,
+            name,
+            token_hash: api_token::hash(&token),
+            scopes,
+            expires_at: chrono::Utc::now() + chrono::Duration::days(body.expires_in_days),
+        },
+        &data.read().await.db,
+    )
+    .await
+    .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    warn!(
+        "{} created api token {} with scopes {:?}",
+        admin_ext.admin.username, created.name, created.scopes
+    );
+
+    Ok(Json(json!({
+        "status": "success",
+        "id": created.id,
+        "token": token,
+    })))
+}
+
+#[tracing::instrument(name = "revoke api token", skip(data, admin_ext))]
+pub async fn revoke_api_token_handler(
+    Path(id): Path<Uuid>,
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+) -> Result<DataApiReturn, DataApiReturn> {
+    let removed = DBApiToken::delete_for_admin(&data.read().await.db, id, admin_ext.admin.id)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?
+        .ok_or(DataResponse::error(
+            "no api token with that id",
+            Some(StatusCode::NOT_FOUND),
+        ))?;
+    warn!(
+        "{} revoked api token {}",
+        admin_ext.admin.username, removed.name
+    );
+    Ok(DataResponse::success(format!(
+        "succesfully revoked api token {}",
+        removed.name
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header, Method, Request, StatusCode},
+        Router,
+    };
+    use serde_json::Value;
+    use sqlx::postgres::PgPoolOptions;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    use crate::{
+        auth::{csrf, password, permissions::Role},
+        database::{
+            handles::DbData,
+            models::{AuditFilter, DBAdmin, DBAdminParams, DBAuditEvent},
+        },
+        routes::create_router,
+        state::{AppState, Config},
+    };
+
+    const FORM: &str = "application/x-www-form-urlencoded";
+
+    async fn send(
+        app: &Router,
+        method: Method,
+        uri: &str,
+        headers: &[(&str, &str)],
+        body: String,
+    ) -> (StatusCode, Value) {
+        let mut req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, FORM);
+        for (name, value) in headers {
+            req = req.header(*name, *value);
+        }
+        let res = app
+            .clone()
+            .oneshot(req.body(Body::from(body)).unwrap())
+            .await
+            .unwrap();
+        let status = res.status();
+        let bytes = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        (
+            status,
+            serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+        )
+    }
+
+    #[tokio::test]
+    async fn scoped_tokens_upload_until_revoked() {
+        dotenv::dotenv().ok();
+        let database_url = std::env::var("DB_URL").expect("DATABASE_URL must be set");
+        let pool = PgPoolOptions::new()
+            .max_connections(2)
+            .connect(&database_url)
+            .await
+            .unwrap();
+        let username = format!("api-token-test-{}", uuid::Uuid::new_v4());
+        let admin = DBAdmin::insert_one(
+            DBAdminParams {
+                username: username.clone(),
+                password_hash: password::hash_password("correct horse battery").unwrap(),
+                disabled: false,
+                role: Role::Volunteer,
+                email: None,
+            },
+            &pool,
+        )
+        .await
+        .unwrap();
+        let app = create_router(Arc::new(RwLock::new(AppState {
+            db: pool.clone(),
+            env: Config {
+                database_url,
+                jwt_secret: "api token test secret".to_string(),
+                webauthn_origin: "http://localhost:3000".to_string(),
+                oidc: None,
+                trash_retention: chrono::Duration::days(30),
+                trusted_proxies: vec![],
+            },
+        })));
+
+        let (status, login) = send(
+            &app,
+            Method::POST,
+            "/data/auth/login",
+            &[],
+            format!("username={}&password=correct+horse+battery", username),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let cookie = format!("token={}", login["token"].as_str().unwrap());
+        let session = [
+            (header::COOKIE.as_str(), cookie.as_str()),
+            (csrf::HEADER_NAME, login["csrf_token"].as_str().unwrap()),
+        ];
+
+        let (status, _) = send(
+            &app,
+            Method::POST,
+            "/data/auth/api_tokens",
+            &session,
+            "name=bad&scopes=manage:admins&expires_in_days=30".to_string(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, created) = send(
+            &app,
+            Method::POST,
+            "/data/auth/api_tokens",
+            &session,
+            "name=importer&scopes=upload:debriefs,+delete:*&expires_in_days=30".to_string(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let bearer = format!("Bearer {}", created["token"].as_str().unwrap());
+        let with_token = [(header::AUTHORIZATION.as_str(), bearer.as_str())];
+
+        let debrief = format!("firstname={}&lastname=Test&content=Scripted", username);
+        let (status, _) = send(
+            &app,
+            Method::POST,
+            "/data/auth/upload_form/debriefs",
+            &with_token,
+            debrief.clone(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        // the upload is audited under the token's admin, with the new row
+        let events = DBAuditEvent::search(
+            &pool,
+            &AuditFilter {
+                actor_name: Some(username.clone()),
+                ..Default::default()
+            },
+            10,
+        )
+        .await
+        .unwrap();
+        let actions: Vec<_> = events.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions, ["upload", "login"]);
+        let snapshot = events[0].snapshot.as_ref().expect("upload has a snapshot");
+        assert_eq!(events[0].item_type.as_deref(), Some("debriefs"));
+        assert_eq!(snapshot.0["firstname"], username.as_str());
+
+        // scoped for deletes, but a volunteer can't delete debriefs
+        let (status, _) = send(
+            &app,
+            Method::DELETE,
+            &format!("/data/auth/delete/debriefs/{}", uuid::Uuid::new_v4()),
+            &with_token,
+            String::new(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // tokens can't manage the account they belong to
+        for uri in ["/data/auth/api_tokens", "/data/auth/logout"] {
+            let (status, _) = send(&app, Method::POST, uri, &with_token, String::new()).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{uri}");
+        }
+
+        let (status, _) = send(
+            &app,
+            Method::DELETE,
+            &format!("/data/auth/api_tokens/{}", created["id"].as_str().unwrap()),
+            &session,
+            String::new(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = send(
+            &app,
+            Method::POST,
+            "/data/auth/upload_form/debriefs",
+            &with_token,
+            debrief,
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        sqlx::query("DELETE FROM testimonials WHERE firstname = $1;")
+            .bind(&username)
+            .execute(&pool)
+            .await
+            .unwrap();
+        admin.delete_one(&pool).await.unwrap();
+    }
+}
@@ -1,95 +1,95 @@
-use anyhow::anyhow;
 use axum::{
     extract::{Path, State},
     response::{IntoResponse, Response},
+    Extension,
 };
+use reqwest::StatusCode;
 use tracing::warn;
 use uuid::Uuid;
 
 use crate::{
-    auth::handlers::upload::attachments::FileAttachment,
+    auth::{
+        audit::{self, AuditAction, Subject},
+        client::ClientInfo,
+        middleware::AdminExtension,
+    },
     database::{
         handles::DbData,
-        models::{DBDedication, DBImage, DBPatrolLog, DBResource, DBTestimonial},
+        models::{
+            DBBoardMember, DBDedication, DBLandingSlide, DBPatrolGear, DBPatrolLog, DBResource,
+            DBTestimonial,
+        },
     },
-    error::{DataApiReturn, InternalError},
+    error::{DataApiReturn, DataResponse, InternalError},
     state::SharedState,
 };
 
 use super::{
     error::UploadError,
-    upload::{UploadFormItemType, UploadItemType, UploadMultipartItemType},
+    upload::{GeneralItem, UploadFormItemType, UploadMultipartItemType},
 };
 
-#[derive(Debug)]
-enum GeneralItem {
-    Form(UploadFormItemType),
-    Multi(UploadMultipartItemType),
-}
-
-impl TryFrom<&str> for GeneralItem {
-    type Error = anyhow::Error;
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        if let Some(item) = UploadFormItemType::try_from_str(value).ok() {
-            return Ok(Self::Form(item));
-        } else if let Some(item) = UploadMultipartItemType::try_from_str(value).ok() {
-            return Ok(Self::Multi(item));
-        }
-
-        Err(anyhow!("{} is not a supported item", value))
-    }
-}
-
-#[tracing::instrument(name = "deletion handler", skip(data))]
+/// Moves an item to the trash. Its images and files stay until it's purged,
+/// see [`super::trash`]
+#[tracing::instrument(name = "deletion handler", skip(data, admin_ext, client))]
 pub async fn delete_item_handler(
     Path((item_str, id)): Path<(String, Uuid)>,
     State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    client: ClientInfo,
 ) -> Result<impl IntoResponse, DataApiReturn> {
     let item = GeneralItem::try_from(item_str.as_str()).expect("failed to get item");
     let success_message = format!(
-        "succesfully deleted {}",
-        item_str[..item_str.len() - 1].to_string()
+        "moved {} to the trash",
+        item_str.strip_suffix('s').unwrap_or(&item_str)
     );
     warn!("got item: {:?}", item);
 
     let r = data.read().await;
-    let pool = &r.db;
-    match item {
-        GeneralItem::Form(i) => match i {
-            UploadFormItemType::Debriefs => {
-                DBTestimonial::delete_one_with_id(id, pool)
-                    .await
-                    .map_err(|err| UploadError::from(err).into_data_api_return())?;
-            }
-        },
-        GeneralItem::Multi(i) => {
-            let (img_ids, subdir) = match i {
-                UploadMultipartItemType::Support => {
-                    let ret = DBResource::delete_one_with_id(id, pool)
-                        .await
-                        .map_err(|err| UploadError::from(err).into_data_api_return())?;
-                    (ret.img_ids, ret.name)
-                }
-                UploadMultipartItemType::PatrolLog => {
-                    let ret = DBPatrolLog::delete_one_with_id(id, pool)
-                        .await
-                        .map_err(|err| UploadError::from(err).into_data_api_return())?;
-                    (ret.img_ids, ret.heading)
-                }
-                UploadMultipartItemType::Dedications => {
-                    let ret = DBDedication::delete_one_with_id(id, pool)
-                        .await
-                        .map_err(|err| UploadError::from(err).into_data_api_return())?;
-                    (ret.img_ids, ret.names.join("-"))
-                }
-            };
-            DBImage::delete_many_by_ids(img_ids, pool)
+    let subject = match item {
+        GeneralItem::Form(UploadFormItemType::Debriefs) => DBTestimonial::trash_one(id, &r.db)
+            .await
+            .map(|row| row.map(|row| Subject::row(&item_str, id, &row))),
+        GeneralItem::Multi(UploadMultipartItemType::Support) => DBResource::trash_one(id, &r.db)
+            .await
+            .map(|row| row.map(|row| Subject::row(&item_str, id, &row))),
+        GeneralItem::Multi(UploadMultipartItemType::PatrolLog) => DBPatrolLog::trash_one(id, &r.db)
+            .await
+            .map(|row| row.map(|row| Subject::row(&item_str, id, &row))),
+        GeneralItem::Multi(UploadMultipartItemType::Dedications) => {
+            DBDedication::trash_one(id, &r.db)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::BoardMembers) => {
+            DBBoardMember::trash_one(id, &r.db)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::LandingSlides) => {
+            DBLandingSlide::trash_one(id, &r.db)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::PatrolGear) => {
+            DBPatrolGear::trash_one(id, &r.db)
                 .await
-                .map_err(|err| UploadError::from(err).into_data_api_return())?;
-            FileAttachment::remove_from_filesys(Some(&subdir), &i)
-                .map_err(|err| UploadError::from(err).into_data_api_return())?;
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
         }
     }
+    .map_err(|err| UploadError::from(err).into_data_api_return())?
+    .ok_or(DataResponse::error(
+        format!("no {} with that id", item_str),
+        Some(StatusCode::NOT_FOUND),
+    ))?;
+    audit::record(
+        &r.db,
+        &admin_ext.admin,
+        &client,
+        AuditAction::Delete,
+        Some(subject),
+    )
+    .await;
 
     let response = Response::new(success_message);
     Ok(response)
@@ -0,0 +1,143 @@
+use axum::{
+    extract::{FromRequest, Multipart, Path, Request, State},
+    response::{IntoResponse, Response},
+    Extension, Form,
+};
+use reqwest::StatusCode;
+use serde_json::Value;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        audit::{self, AuditAction, Subject},
+        client::ClientInfo,
+        middleware::AdminExtension,
+    },
+    database::{
+        handles::DbData,
+        models::{
+            DBBoardMember, DBDedication, DBLandingSlide, DBPatrolGear, DBPatrolLog, DBResource,
+            DBTestimonial,
+        },
+    },
+    error::{DataApiReturn, DataResponse, InternalError},
+    state::SharedState,
+};
+
+use super::{
+    error::UploadError,
+    upload::{GeneralItem, StagedFiles, UploadItem, UploadItemType},
+};
+
+/// Replaces an item with the fields of the same form used to upload it. Images
+/// sent along are added to the item's existing ones
+#[tracing::instrument(name = "edit handler", skip(data, admin_ext, client, request))]
+pub async fn edit_item_handler(
+    Path((item_str, id)): Path<(String, Uuid)>,
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    client: ClientInfo,
+    request: Request,
+) -> Result<impl IntoResponse, DataApiReturn> {
+    let item = GeneralItem::try_from(item_str.as_str())
+        .map_err(|err| DataResponse::error(err.to_string(), Some(StatusCode::NOT_FOUND)))?;
+    let success_message = format!("succesfully edited {}", item_str);
+    debug!("got item: {:?}", item);
+
+    let mut staged = StagedFiles::new();
+    let uploadable = match item {
+        GeneralItem::Form(i) => {
+            let Form(form) = Form::<Value>::from_request(request, &data)
+                .await
+                .map_err(|err| DataResponse::error(err.body_text(), Some(err.status())))?;
+            i.into_item(form, &mut staged).await
+        }
+        GeneralItem::Multi(i) => {
+            let multipart = Multipart::from_request(request, &data)
+                .await
+                .map_err(|err| DataResponse::error(err.body_text(), Some(err.status())))?;
+            i.into_item(multipart, &mut staged).await
+        }
+    }
+    .map_err(|err| UploadError::from(err).into_data_api_return())?;
+
+    let r = data.read().await;
+    let mut tx =
+        r.db.begin()
+            .await
+            .map_err(|err| UploadError::from(err).into_data_api_return())?;
+    debug!("updating {} with: {:?}", id, uploadable);
+    let subject = match uploadable {
+        UploadItem::Debrief(params) => {
+            DBTestimonial::update_revised(params, &admin_ext.admin, &mut tx, id)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        UploadItem::PatrolLog(params) => {
+            DBPatrolLog::update_revised(params, &admin_ext.admin, &mut tx, id)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        UploadItem::Dedication(params) => {
+            DBDedication::update_revised(params, &admin_ext.admin, &mut tx, id)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        UploadItem::Support(params) => {
+            DBResource::update_revised(params, &admin_ext.admin, &mut tx, id)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        // members show one photo, a new one replaces it. Taken off before the
+        // update so its revision has the new one
+        UploadItem::BoardMember(params) => async {
+            if !params.img_params.is_empty() {
+                DBBoardMember::detach_all_images(id, &mut *tx).await?;
+            }
+            DBBoardMember::update_revised(params, &admin_ext.admin, &mut *tx, id).await
+        }
+        .await
+        .map(|row| row.map(|row| Subject::row(&item_str, id, &row))),
+        // the same goes for the image of a slide
+        UploadItem::LandingSlide(params) => async {
+            if !params.img_params.is_empty() {
+                DBLandingSlide::detach_all_images(id, &mut *tx).await?;
+            }
+            DBLandingSlide::update_revised(params, &admin_ext.admin, &mut *tx, id).await
+        }
+        .await
+        .map(|row| row.map(|row| Subject::row(&item_str, id, &row))),
+        UploadItem::PatrolGear(params) => {
+            DBPatrolGear::update_revised(params, &admin_ext.admin, &mut tx, id)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        other => {
+            let msg = format!("{:?} is not a supported edit type", other);
+            warn!("{}", msg);
+            return Err(DataResponse::error(msg, Some(StatusCode::BAD_REQUEST)));
+        }
+    }
+    .map_err(|err| UploadError::from(err).into_data_api_return())?
+    .ok_or(DataResponse::error(
+        format!("no {} with that id", item_str),
+        Some(StatusCode::NOT_FOUND),
+    ))?;
+
+    staged
+        .commit(tx)
+        .await
+        .map_err(|err| UploadError::from(err).into_data_api_return())?;
+    audit::record(
+        &r.db,
+        &admin_ext.admin,
+        &client,
+        AuditAction::Edit,
+        Some(subject),
+    )
+    .await;
+
+    let response = Response::new(success_message);
+    Ok(response)
+}
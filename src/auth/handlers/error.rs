@@ -8,13 +8,22 @@ pub type UploadResult<T> = Result<T, UploadError>;
 #[derive(thiserror::Error)]
 pub enum UploadError {
     #[error(transparent)]
-    Undefined(#[from] anyhow::Error),
+    Undefined(anyhow::Error),
     Chrono(#[from] chrono::ParseError),
     Serde(#[from] serde_json::error::Error),
     MultiPart(#[from] MultipartError),
+    Sqlx(#[from] sqlx::Error),
     UserFacing(String),
 }
 
+/// Parsers return `anyhow` errors, this gets back the [`UploadError`] one of
+/// them may have started out as
+impl From<anyhow::Error> for UploadError {
+    fn from(err: anyhow::Error) -> Self {
+        err.downcast().unwrap_or_else(Self::Undefined)
+    }
+}
+
 impl Debug for UploadError {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         error_chain_fmt(self, f)
@@ -27,6 +36,7 @@ impl Display for UploadError {
             Self::Undefined(err) => err.to_string(),
             Self::Serde(err) => err.to_string(),
             Self::MultiPart(err) => err.to_string(),
+            Self::Sqlx(err) => err.to_string(),
             Self::UserFacing(err) => err.to_string(),
             Self::Chrono(err) => err.to_string(),
         };
@@ -37,6 +47,7 @@ impl Display for UploadError {
 impl InternalError for UploadError {
     fn status_code(&self) -> StatusCode {
         match self {
+            Self::UserFacing(_) => StatusCode::BAD_REQUEST,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -46,6 +57,7 @@ impl InternalError for UploadError {
             Self::UserFacing(err) => err.to_string(),
             Self::Undefined(err) => format!("An undefined error occurred: {:?}", err),
             Self::MultiPart(err) => format!("A multipart error occurred: {:?}", err),
+            Self::Sqlx(err) => format!("A database error occurred: {:?}", err),
             Self::Serde(err) => format!("A serde error occurred: {:?}", err),
             Self::Chrono(err) => format!("A chrono error occurred: {:?}", err),
         }
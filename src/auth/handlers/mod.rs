@@ -1,9 +1,30 @@
+pub mod admins;
+pub mod api_tokens;
 pub mod delete;
+pub mod edit;
 mod error;
+pub mod oidc;
+pub mod reorder;
+pub mod revisions;
+pub mod sessions;
+pub mod totp;
+pub mod trash;
 pub mod upload;
-use super::model::{LoginAdminSchema, TokenClaims};
+pub mod webauthn;
+use super::model::{LoginAdminSchema, PendingLoginClaims, TokenClaims};
 use crate::{
-    auth::{error::AuthError, ADMIN_CREDENTIALS},
+    auth::{
+        audit::{self, AuditAction, Subject},
+        client::ClientInfo,
+        csrf,
+        error::AuthError,
+        middleware::AdminExtension,
+        password, throttle,
+    },
+    database::{
+        handles::DbData,
+        models::{DBAdmin, DBSession, DBSessionParams},
+    },
     error::{DataApiReturn, InternalError},
     state::SharedState,
     AppState,
@@ -13,33 +34,140 @@ use axum::{
     extract::{Multipart, Path, State},
     http::{header, Response},
     response::IntoResponse,
-    Form,
+    Extension, Form,
 };
 use axum_extra::extract::cookie::{Cookie, SameSite};
 use jsonwebtoken::{encode, EncodingKey, Header};
 use serde_json::json;
 use std::sync::Arc;
-use tracing::info;
+use tracing::warn;
 use uuid::Uuid;
 
+pub const SESSION_LENGTH_MINUTES: i64 = 60;
+/// How long an admin has to enter their second factor after their password
+pub const PENDING_LOGIN_MINUTES: i64 = 5;
+const PENDING_LOGIN_COOKIE: &str = "pending_login";
+const PENDING_LOGIN_PURPOSE: &str = "totp";
+
+#[tracing::instrument(name = "login admin handler", skip_all)]
 pub async fn login_admin_handler(
     State(data): State<SharedState>,
+    client: ClientInfo,
     Form(body): Form<LoginAdminSchema>,
-) -> Result<impl IntoResponse, DataApiReturn> {
-    info!("Login request Body {:?}", body);
+) -> Result<Response<String>, DataApiReturn> {
+    let username = body.username.trim();
+    let r = data.read().await;
+    let attempt = throttle::check(&r.db, username, &client)
+        .await
+        .map_err(|err| err.into_data_api_return())?;
+
+    let admin = DBAdmin::get_by_username(&r.db, username)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+
+    let admin = match admin {
+        Some(admin) if password::verify_password(&body.password, &admin.password_hash) => admin,
+        other => {
+            if other.is_none() {
+                password::burn_verification(&body.password);
+            }
+            throttle::record(&r.db, attempt, false).await;
+            return Err(AuthError::NotLoggedIn.into_data_api_return());
+        }
+    };
 
-    if ADMIN_CREDENTIALS.password != body.password {
-        data.write().await.admin_session_id = None;
+    if admin.disabled {
+        warn!("disabled admin {} attempted to log in", admin.username);
+        throttle::record(&r.db, attempt, false).await;
         return Err(AuthError::NotLoggedIn.into_data_api_return());
     }
+    drop(r);
+
+    // the attempt only counts as a success once the second factor is in too
+    if admin.totp_enabled {
+        throttle::forget(&data.read().await.db, attempt).await;
+        return pending_login_response(&data, &admin).await;
+    }
+
+    throttle::record(&data.read().await.db, attempt, true).await;
+    start_session(&data, &admin, client).await
+}
+
+/// Sets a short lived cookie that [`totp::login_totp_handler`] exchanges for a
+/// session once the admin proves their second factor
+async fn pending_login_response(
+    data: &SharedState,
+    admin: &DBAdmin,
+) -> Result<Response<String>, DataApiReturn> {
+    let now = chrono::Utc::now();
+    let claims = PendingLoginClaims {
+        sub: admin.id.to_string(),
+        purpose: PENDING_LOGIN_PURPOSE.to_string(),
+        iat: now.timestamp() as usize,
+        exp: (now + chrono::Duration::minutes(PENDING_LOGIN_MINUTES)).timestamp() as usize,
+    };
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(data.read().await.env.jwt_secret.as_ref()),
+    )
+    .map_err(|err| AuthError::from(err).into_data_api_return())?;
+
+    let cookie = Cookie::build((PENDING_LOGIN_COOKIE, token))
+        .path("/data/auth/login")
+        .max_age(time::Duration::minutes(PENDING_LOGIN_MINUTES))
+        .same_site(SameSite::Lax)
+        .http_only(true);
+
+    let mut response = Response::new(json!({"status": "totp_required"}).to_string());
+    response
+        .headers_mut()
+        .insert(header::SET_COOKIE, cookie.to_string().parse().unwrap());
+    Ok(response)
+}
 
+/// Records a new session for the admin and hands back its token, both in the
+/// body and as the `token` cookie. The body also carries the session's CSRF
+/// token for pages that log in without reloading
+pub(super) async fn start_session(
+    data: &SharedState,
+    admin: &DBAdmin,
+    client: ClientInfo,
+) -> Result<Response<String>, DataApiReturn> {
     let now = chrono::Utc::now();
+    let expires_at = now + chrono::Duration::minutes(SESSION_LENGTH_MINUTES);
     let iat = now.timestamp() as usize;
-    let exp = (now + chrono::Duration::minutes(60)).timestamp() as usize;
-    let uuid = Uuid::new_v4();
-    data.write().await.admin_session_id = Some(uuid);
+    let exp = expires_at.timestamp() as usize;
+
+    let r = data.read().await;
+    if let Err(err) = DBSession::delete_expired(&r.db).await {
+        warn!("failed to clean up expired sessions: {:?}", err);
+    }
+    let session = DBSession::insert_one(
+        DBSessionParams {
+            admin_id: admin.id,
+            expires_at,
+            user_agent: client.user_agent.clone(),
+            ip: client.ip.clone(),
+            csrf_token: csrf::generate_token(),
+        },
+        &r.db,
+    )
+    .await
+    .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    audit::record(
+        &r.db,
+        admin,
+        &client,
+        AuditAction::Login,
+        Some(Subject::session(session.id)),
+    )
+    .await;
+    drop(r);
+
     let claims: TokenClaims = TokenClaims {
-        sub: uuid.to_string(),
+        sub: admin.id.to_string(),
+        sid: session.id.to_string(),
         exp,
         iat,
     };
@@ -53,19 +181,20 @@ pub async fn login_admin_handler(
 
     let cookie = Cookie::build(("token", token.to_owned()))
         .path("/")
-        .max_age(time::Duration::hours(1))
+        .max_age(time::Duration::minutes(SESSION_LENGTH_MINUTES))
         .same_site(SameSite::Lax)
         .http_only(true);
 
-    let mut response = Response::new(json!({"status": "success", "token": token}).to_string());
+    let mut response = Response::new(
+        json!({"status": "success", "token": token, "csrf_token": session.csrf_token}).to_string(),
+    );
     response
         .headers_mut()
         .insert(header::SET_COOKIE, cookie.to_string().parse().unwrap());
     Ok(response)
 }
 
-#[tracing::instrument(name = "logout user")]
-pub async fn logout_handler() -> Result<impl IntoResponse, DataApiReturn> {
+pub(super) fn logged_out_response() -> Response<String> {
     let cookie = Cookie::build(("token", ""))
         .path("/")
         .max_age(time::Duration::hours(-1))
@@ -76,5 +205,30 @@ pub async fn logout_handler() -> Result<impl IntoResponse, DataApiReturn> {
     response
         .headers_mut()
         .insert(header::SET_COOKIE, cookie.to_string().parse().unwrap());
-    Ok(response)
+    response
+}
+
+#[tracing::instrument(name = "logout user", skip_all)]
+pub async fn logout_handler(
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    client: ClientInfo,
+) -> Result<impl IntoResponse, DataApiReturn> {
+    let session = admin_ext
+        .session()
+        .map_err(|err| err.into_data_api_return())?;
+    let r = data.read().await;
+    session
+        .delete_one(&r.db)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    audit::record(
+        &r.db,
+        &admin_ext.admin,
+        &client,
+        AuditAction::Logout,
+        Some(Subject::session(session.id)),
+    )
+    .await;
+    Ok(logged_out_response())
 }
@@ -0,0 +1,421 @@
+use axum::{
+    extract::{Query, State},
+    http::{header, Response, StatusCode},
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use tracing::warn;
+
+use crate::{
+    auth::{
+        client::ClientInfo,
+        error::AuthError,
+        model::{OidcCallbackQuery, OidcLoginClaims},
+        oidc, password,
+        permissions::Role,
+    },
+    database::{
+        handles::DbData,
+        models::{DBAdmin, DBAdminParams},
+    },
+    error::{DataApiReturn, DataResponse, InternalError},
+    state::{AppState, OidcConfig, SharedState},
+};
+
+use super::start_session;
+
+const OIDC_LOGIN_COOKIE: &str = "oidc_login";
+const OIDC_COOKIE_PATH: &str = "/data/auth/oidc";
+/// How long the admin has to finish signing in with the provider
+const OIDC_LOGIN_MINUTES: i64 = 10;
+const AFTER_LOGIN_PATH: &str = "/admin/status";
+
+fn oidc_config(data: &AppState) -> Result<OidcConfig, DataApiReturn> {
+    data.env.oidc.clone().ok_or(DataResponse::error(
+        "single sign-on is not configured",
+        Some(StatusCode::NOT_FOUND),
+    ))
+}
+
+/// Sends the browser to the provider, remembering the state, nonce and PKCE
+/// verifier in a signed cookie for the callback
+#[tracing::instrument(name = "oidc login handler", skip_all)]
+pub async fn oidc_login_handler(
+    State(data): State<SharedState>,
+) -> Result<Response<String>, DataApiReturn> {
+    let r = data.read().await;
+    let config = oidc_config(&r)?;
+    let discovery = oidc::discover(&config)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+
+    let now = chrono::Utc::now();
+    let claims = OidcLoginClaims {
+        state: oidc::random_token(),
+        nonce: oidc::random_token(),
+        verifier: oidc::random_token(),
+        iat: now.timestamp() as usize,
+        exp: (now + chrono::Duration::minutes(OIDC_LOGIN_MINUTES)).timestamp() as usize,
+    };
+    let location = oidc::authorization_url(
+        &config,
+        &discovery,
+        &claims.state,
+        &claims.nonce,
+        &claims.verifier,
+    )
+    .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(r.env.jwt_secret.as_ref()),
+    )
+    .map_err(|err| AuthError::from(err).into_data_api_return())?;
+
+    let cookie = Cookie::build((OIDC_LOGIN_COOKIE, token))
+        .path(OIDC_COOKIE_PATH)
+        .max_age(time::Duration::minutes(OIDC_LOGIN_MINUTES))
+        .same_site(SameSite::Lax)
+        .http_only(true);
+
+    Ok(Response::builder()
+        .status(StatusCode::SEE_OTHER)
+        .header(header::LOCATION, location)
+        .header(header::SET_COOKIE, cookie.to_string())
+        .body(String::new())
+        .unwrap())
+}
+
+/// Finds the admin for a verified email, making a volunteer account if the
+/// email is allowed but has never signed in before
+async fn admin_for_email(
+    pool: &sqlx::Pool<sqlx::Postgres>,
+    config: &OidcConfig,
+    email: &str,
+) -> anyhow::Result<Option<DBAdmin>> {
+    if let Some(admin) = DBAdmin::get_by_email(pool, email).await? {
+        return Ok(Some(admin));
+    }
+    if !oidc::is_allowed(config, email) {
+        return Ok(None);
+    }
+    let email = email.to_lowercase();
+    if DBAdmin::get_by_username(pool, &email).await?.is_some() {
+        warn!("an admin is already called {} but has no email set", email);
+        return Ok(None);
+    }
+    let admin = DBAdmin::insert_one(
+        DBAdminParams {
+            username: email.clone(),
+            // never shown to anyone, these accounts sign in through the provider
+            password_hash: password::hash_password(&oidc::random_token())?,
+            disabled: false,
+            role: Role::Volunteer,
+            email: Some(email),
+        },
+        pool,
+    )
+    .await?;
+    warn!("created admin {} from single sign-on", admin.username);
+    Ok(Some(admin))
+}
+
+/// The provider enforces its own second factor, so local TOTP isn't asked for here
+#[tracing::instrument(name = "oidc callback handler", skip_all)]
+pub async fn oidc_callback_handler(
+    State(data): State<SharedState>,
+    client: ClientInfo,
+    cookie_jar: CookieJar,
+    Query(query): Query<OidcCallbackQuery>,
+) -> Result<Response<String>, DataApiReturn> {
+    let r = data.read().await;
+    let config = oidc_config(&r)?;
+    if let Some(error) = query.error {
+        warn!("single sign-on provider returned an error: {}", error);
+        return Err(AuthError::NotLoggedIn.into_data_api_return());
+    }
+
+    let pending = cookie_jar
+        .get(OIDC_LOGIN_COOKIE)
+        .ok_or(AuthError::NotLoggedIn.into_data_api_return())?;
+    let claims = decode::<OidcLoginClaims>(
+        pending.value(),
+        &DecodingKey::from_secret(r.env.jwt_secret.as_ref()),
+        &Validation::default(),
+    )
+    .map_err(|_| AuthError::NotLoggedIn.into_data_api_return())?
+    .claims;
+    let (Some(code), Some(state)) = (query.code, query.state) else {
+        return Err(AuthError::NotLoggedIn.into_data_api_return());
+    };
+    if state != claims.state {
+        warn!("single sign-on state did not match");
+        return Err(AuthError::NotLoggedIn.into_data_api_return());
+    }
+
+    let discovery = oidc::discover(&config)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    let identity = oidc::exchange_code(&config, &discovery, &code, &claims.verifier, &claims.nonce)
+        .await
+        .map_err(|err| {
+            warn!("single sign-on token exchange failed: {:?}", err);
+            AuthError::NotLoggedIn.into_data_api_return()
+        })?;
+    let email = match identity.email {
+        Some(email) if identity.email_verified => email,
+        _ => {
+            warn!("identity {} has no verified email", identity.sub);
+            return Err(AuthError::NotLoggedIn.into_data_api_return());
+        }
+    };
+
+    let admin = admin_for_email(&r.db, &config, &email)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?
+        .ok_or_else(|| {
+            warn!("{} is not allowed to sign in", email);
+            AuthError::NotLoggedIn.into_data_api_return()
+        })?;
+    drop(r);
+    if admin.disabled {
+        warn!("disabled admin {} attempted to sign in", admin.username);
+        return Err(AuthError::NotLoggedIn.into_data_api_return());
+    }
+
+    let mut response = start_session(&data, &admin, client).await?;
+    let cleared = Cookie::build((OIDC_LOGIN_COOKIE, ""))
+        .path(OIDC_COOKIE_PATH)
+        .max_age(time::Duration::hours(-1))
+        .same_site(SameSite::Lax)
+        .http_only(true);
+    *response.status_mut() = StatusCode::SEE_OTHER;
+    response
+        .headers_mut()
+        .insert(header::LOCATION, AFTER_LOGIN_PATH.parse().unwrap());
+    response
+        .headers_mut()
+        .append(header::SET_COOKIE, cleared.to_string().parse().unwrap());
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{
+        body::Body,
+        extract::{Query, State},
+        http::{header, Request, StatusCode},
+        response::{IntoResponse, Redirect},
+        routing::{get, post},
+        Form, Json, Router,
+    };
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+    use sqlx::postgres::PgPoolOptions;
+    use std::collections::HashMap;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    use crate::{
+        auth::{oidc::pkce_challenge, permissions::Role},
+        database::{handles::DbData, models::DBAdmin},
+        routes::create_router,
+        state::{AppState, Config, OidcConfig},
+    };
+
+    const CLIENT_ID: &str = "semperflies";
+    const SIGNING_KEY: &[u8] = b"mock issuer signing key";
+    const REDIRECT_URL: &str = "http://localhost:3000/data/auth/oidc/callback";
+
+    #[derive(Clone, Default)]
+    struct MockIssuer {
+        base: String,
+        email: String,
+        /// nonce and PKCE challenge from the last authorization request
+        pending: Arc<Mutex<Option<(String, String)>>>,
+    }
+
+    async fn authorize(
+        State(mock): State<MockIssuer>,
+        Query(params): Query<HashMap<String, String>>,
+    ) -> impl IntoResponse {
+        *mock.pending.lock().unwrap() =
+            Some((params["nonce"].clone(), params["code_challenge"].clone()));
+        Redirect::to(&format!(
+            "{}?code=mock-code&state={}",
+            params["redirect_uri"], params["state"]
+        ))
+    }
+
+    async fn token(
+        State(mock): State<MockIssuer>,
+        Form(params): Form<HashMap<String, String>>,
+    ) -> impl IntoResponse {
+        let (nonce, challenge) = mock.pending.lock().unwrap().clone().unwrap();
+        if params["code"] != "mock-code" || pkce_challenge(&params["code_verifier"]) != challenge {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "invalid_grant"})),
+            );
+        }
+        let now = chrono::Utc::now().timestamp();
+        let header = Header {
+            kid: Some("mock".to_string()),
+            ..Default::default()
+        };
+        let id_token = encode(
+            &header,
+            &json!({
+                "iss": mock.base,
+                "aud": CLIENT_ID,
+                "sub": "mock-subject",
+                "email": mock.email,
+                "email_verified": true,
+                "nonce": nonce,
+                "iat": now,
+                "exp": now + 300,
+            }),
+            &EncodingKey::from_secret(SIGNING_KEY),
+        )
+        .unwrap();
+        (
+            StatusCode::OK,
+            Json(json!({"access_token": "mock", "token_type": "Bearer", "id_token": id_token})),
+        )
+    }
+
+    async fn start_mock_issuer(email: String) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let mock = MockIssuer {
+            base: base.clone(),
+            email,
+            ..Default::default()
+        };
+        let discovery = json!({
+            "issuer": base,
+            "authorization_endpoint": format!("{}/authorize", base),
+            "token_endpoint": format!("{}/token", base),
+            "jwks_uri": format!("{}/jwks", base),
+        });
+        let jwks = json!({"keys": [{
+            "kty": "oct",
+            "kid": "mock",
+            "alg": "HS256",
+            "k": URL_SAFE_NO_PAD.encode(SIGNING_KEY),
+        }]});
+        let app = Router::new()
+            .route(
+                "/.well-known/openid-configuration",
+                get(move || async move { Json(discovery) }),
+            )
+            .route("/jwks", get(move || async move { Json(jwks) }))
+            .route("/authorize", get(authorize))
+            .route("/token", post(token))
+            .with_state(mock);
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        base
+    }
+
+    fn set_cookie(res: &axum::response::Response, name: &str) -> Option<String> {
+        res.headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .find(|v| v.starts_with(&format!("{}=", name)))
+            .map(|v| v.split(';').next().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn sign_in_with_mock_issuer() {
+        dotenv::dotenv().ok();
+        let database_url = std::env::var("DB_URL").expect("DATABASE_URL must be set");
+        let pool = PgPoolOptions::new()
+            .max_connections(2)
+            .connect(&database_url)
+            .await
+            .unwrap();
+        let email = format!("chesty-{}@semperflies.test", uuid::Uuid::new_v4());
+        let issuer = start_mock_issuer(email.clone()).await;
+
+        let app = create_router(Arc::new(RwLock::new(AppState {
+            db: pool.clone(),
+            env: Config {
+                database_url,
+                jwt_secret: "oidc test secret".to_string(),
+                webauthn_origin: "http://localhost:3000".to_string(),
+                oidc: Some(OidcConfig {
+                    issuer,
+                    client_id: CLIENT_ID.to_string(),
+                    client_secret: "mock client secret".to_string(),
+                    redirect_url: REDIRECT_URL.to_string(),
+                    allowed_domains: vec!["semperflies.test".to_string()],
+                    allowed_emails: vec![],
+                }),
+                trash_retention: chrono::Duration::days(30),
+                trusted_proxies: vec![],
+            },
+        })));
+
+        let res = app
+            .clone()
+            .oneshot(
+                Request::get("/data/auth/oidc/login")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        let login_cookie = set_cookie(&res, "oidc_login").unwrap();
+        let authorize_url = res.headers()[header::LOCATION]
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let provider = client.get(&authorize_url).send().await.unwrap();
+        let callback = provider.headers()["location"].to_str().unwrap().to_string();
+        let callback = callback.strip_prefix("http://localhost:3000").unwrap();
+
+        let forged = callback.replace("state=", "state=forged");
+        let res = app
+            .clone()
+            .oneshot(
+                Request::get(forged)
+                    .header(header::COOKIE, &login_cookie)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let res = app
+            .clone()
+            .oneshot(
+                Request::get(callback)
+                    .header(header::COOKIE, &login_cookie)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(res.headers()[header::LOCATION], "/admin/status");
+        assert!(set_cookie(&res, "token").is_some());
+
+        let admin = DBAdmin::get_by_email(&pool, &email)
+            .await
+            .unwrap()
+            .expect("an account is made for an allowed domain");
+        assert_eq!(admin.role, Role::Volunteer);
+        admin.delete_one(&pool).await.unwrap();
+    }
+}
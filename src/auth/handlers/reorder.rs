@@ -0,0 +1,86 @@
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Extension, Form,
+};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        audit::{self, AuditAction, Subject},
+        client::ClientInfo,
+        middleware::AdminExtension,
+    },
+    database::{
+        handles::DbData,
+        models::{DBBoardMember, DBLandingSlide, DBPatrolGear},
+    },
+    error::{DataApiReturn, DataResponse, InternalError},
+    state::SharedState,
+};
+
+use super::{
+    error::UploadError,
+    upload::{GeneralItem, UploadMultipartItemType},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct ReorderForm {
+    /// counting from 1, past the end moves it last
+    position: usize,
+}
+
+/// Moves an item to another place in the order it's shown in, only board
+/// members and landing slides have one
+#[tracing::instrument(name = "reorder handler", skip(data, admin_ext, client))]
+pub async fn reorder_item_handler(
+    Path((item_str, id)): Path<(String, Uuid)>,
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    client: ClientInfo,
+    Form(form): Form<ReorderForm>,
+) -> Result<impl IntoResponse, DataApiReturn> {
+    let item = GeneralItem::try_from(item_str.as_str())
+        .map_err(|err| DataResponse::error(err.to_string(), Some(StatusCode::NOT_FOUND)))?;
+    let r = data.read().await;
+    let subject = match item {
+        GeneralItem::Multi(UploadMultipartItemType::BoardMembers) => {
+            DBBoardMember::move_to(id, form.position, &r.db)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::LandingSlides) => {
+            DBLandingSlide::move_to(id, form.position, &r.db)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::PatrolGear) => {
+            DBPatrolGear::move_to(id, form.position, &r.db)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        other => {
+            return Err(DataResponse::error(
+                format!("{} can't be reordered", other.as_str()),
+                Some(StatusCode::BAD_REQUEST),
+            ))
+        }
+    }
+    .map_err(|err| UploadError::from(err).into_data_api_return())?
+    .ok_or(DataResponse::error(
+        format!("no {} with that id", item_str),
+        Some(StatusCode::NOT_FOUND),
+    ))?;
+    audit::record(
+        &r.db,
+        &admin_ext.admin,
+        &client,
+        AuditAction::Reorder,
+        Some(subject),
+    )
+    .await;
+
+    Ok(DataResponse::success(format!("moved {}", item_str)))
+}
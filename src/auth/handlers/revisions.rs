@@ -0,0 +1,96 @@
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Extension,
+};
+use reqwest::StatusCode;
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        audit::{self, AuditAction, Subject},
+        client::ClientInfo,
+        middleware::AdminExtension,
+    },
+    database::{
+        handles::DbData,
+        models::{
+            DBBoardMember, DBDedication, DBLandingSlide, DBPatrolGear, DBPatrolLog, DBResource,
+            DBTestimonial,
+        },
+    },
+    error::{DataApiReturn, DataResponse, InternalError},
+    state::SharedState,
+};
+
+use super::{
+    error::UploadError,
+    upload::{GeneralItem, UploadFormItemType, UploadMultipartItemType},
+};
+
+/// Puts an item back the way one of its revisions has it. The rollback is
+/// itself recorded as the newest revision, so it can be undone the same way
+#[tracing::instrument(name = "rollback handler", skip(data, admin_ext, client))]
+pub async fn rollback_item_handler(
+    Path((item_str, id, revision_id)): Path<(String, Uuid, Uuid)>,
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    client: ClientInfo,
+) -> Result<impl IntoResponse, DataApiReturn> {
+    let item = GeneralItem::try_from(item_str.as_str())
+        .map_err(|err| DataResponse::error(err.to_string(), Some(StatusCode::NOT_FOUND)))?;
+    let admin = &admin_ext.admin;
+    let r = data.read().await;
+    let mut tx =
+        r.db.begin()
+            .await
+            .map_err(|err| UploadError::from(err).into_data_api_return())?;
+    let subject = match item {
+        GeneralItem::Form(UploadFormItemType::Debriefs) => {
+            DBTestimonial::roll_back(id, revision_id, admin, &mut tx)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::Support) => {
+            DBResource::roll_back(id, revision_id, admin, &mut tx)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::PatrolLog) => {
+            DBPatrolLog::roll_back(id, revision_id, admin, &mut tx)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::Dedications) => {
+            DBDedication::roll_back(id, revision_id, admin, &mut tx)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::BoardMembers) => {
+            DBBoardMember::roll_back(id, revision_id, admin, &mut tx)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::LandingSlides) => {
+            DBLandingSlide::roll_back(id, revision_id, admin, &mut tx)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::PatrolGear) => {
+            DBPatrolGear::roll_back(id, revision_id, admin, &mut tx)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+    }
+    .map_err(|err| UploadError::from(err).into_data_api_return())?
+    .ok_or(DataResponse::error(
+        format!("no {} revision with that id", item_str),
+        Some(StatusCode::NOT_FOUND),
+    ))?;
+    tx.commit()
+        .await
+        .map_err(|err| UploadError::from(err).into_data_api_return())?;
+    audit::record(&r.db, admin, &client, AuditAction::Rollback, Some(subject)).await;
+
+    Ok(DataResponse::success(format!("rolled back {}", item_str)))
+}
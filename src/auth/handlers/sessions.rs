@@ -0,0 +1,74 @@
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Extension,
+};
+use reqwest::StatusCode;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        audit::{self, AuditAction, Subject},
+        client::ClientInfo,
+        error::AuthError,
+        middleware::AdminExtension,
+    },
+    database::models::DBSession,
+    error::{DataApiReturn, DataResponse, InternalError},
+    state::SharedState,
+};
+
+use super::logged_out_response;
+
+#[tracing::instrument(name = "logout everywhere", skip_all)]
+pub async fn logout_everywhere_handler(
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    client: ClientInfo,
+) -> Result<impl IntoResponse, DataApiReturn> {
+    let r = data.read().await;
+    let removed = DBSession::delete_all_for_admin(&r.db, admin_ext.admin.id)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    for session in removed.iter() {
+        audit::record(
+            &r.db,
+            &admin_ext.admin,
+            &client,
+            AuditAction::Logout,
+            Some(Subject::session(session.id)),
+        )
+        .await;
+    }
+    warn!(
+        "{} signed out of {} sessions",
+        admin_ext.admin.username,
+        removed.len()
+    );
+    Ok(logged_out_response())
+}
+
+#[tracing::instrument(name = "revoke session", skip(data, admin_ext))]
+pub async fn revoke_session_handler(
+    Path(id): Path<Uuid>,
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+) -> Result<impl IntoResponse, DataApiReturn> {
+    let current = admin_ext
+        .session()
+        .map_err(|err| err.into_data_api_return())?
+        .id;
+    DBSession::delete_for_admin(&data.read().await.db, id, admin_ext.admin.id)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?
+        .ok_or(DataResponse::error(
+            "no session with that id",
+            Some(StatusCode::NOT_FOUND),
+        ))?;
+
+    if id == current {
+        return Ok(logged_out_response().into_response());
+    }
+    Ok(DataResponse::success("succesfully revoked session").into_response())
+}
@@ -0,0 +1,272 @@
+use axum::{
+    extract::State,
+    http::{header, Response},
+    Extension, Form, Json,
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use jsonwebtoken::{decode, DecodingKey, Validation};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use sqlx::{Pool, Postgres};
+use std::str::FromStr;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        client::ClientInfo,
+        error::AuthError,
+        middleware::AdminExtension,
+        model::{PendingLoginClaims, SecondFactorSchema},
+        throttle, totp,
+    },
+    database::{
+        handles::DbData,
+        models::{DBAdmin, DBRecoveryCode, DBRecoveryCodeParams},
+    },
+    error::{DataApiReturn, DataResponse, InternalError},
+    state::SharedState,
+};
+
+use super::{start_session, PENDING_LOGIN_COOKIE, PENDING_LOGIN_PURPOSE};
+
+fn unix_now() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
+
+/// Accepts a current authenticator code, refusing one that has already been used,
+/// or else spends a matching recovery code
+async fn verify_second_factor(
+    pool: &Pool<Postgres>,
+    admin: &DBAdmin,
+    code: &str,
+) -> anyhow::Result<bool> {
+    let Some(secret) = admin.totp_secret.as_ref() else {
+        return Ok(false);
+    };
+    if let Some(step) = totp::matching_step(secret, &admin.username, code, unix_now())? {
+        return DBAdmin::claim_totp_step(pool, admin.id, step).await;
+    }
+    let used = DBRecoveryCode::consume(pool, admin.id, &totp::hash_recovery_code(code)).await?;
+    if used.is_some() {
+        warn!("{} logged in with a recovery code", admin.username);
+    }
+    Ok(used.is_some())
+}
+
+/// Throws away the admin's recovery codes and hands back a fresh set, which is
+/// the only time they are ever shown
+async fn replace_recovery_codes(
+    pool: &Pool<Postgres>,
+    admin_id: Uuid,
+) -> anyhow::Result<Vec<String>> {
+    DBRecoveryCode::delete_all_for_admin(pool, admin_id).await?;
+    let codes = totp::generate_recovery_codes();
+    for code in codes.iter() {
+        DBRecoveryCode::insert_one(
+            DBRecoveryCodeParams {
+                admin_id,
+                code_hash: totp::hash_recovery_code(code),
+            },
+            pool,
+        )
+        .await?;
+    }
+    Ok(codes)
+}
+
+#[tracing::instrument(name = "login totp handler", skip_all)]
+pub async fn login_totp_handler(
+    State(data): State<SharedState>,
+    client: ClientInfo,
+    cookie_jar: CookieJar,
+    Form(body): Form<SecondFactorSchema>,
+) -> Result<Response<String>, DataApiReturn> {
+    let r = data.read().await;
+    let pending = cookie_jar
+        .get(PENDING_LOGIN_COOKIE)
+        .ok_or(AuthError::NotLoggedIn.into_data_api_return())?;
+    let claims = decode::<PendingLoginClaims>(
+        pending.value(),
+        &DecodingKey::from_secret(r.env.jwt_secret.as_ref()),
+        &Validation::default(),
+    )
+    .map_err(|_| AuthError::NotLoggedIn.into_data_api_return())?
+    .claims;
+    if claims.purpose != PENDING_LOGIN_PURPOSE {
+        return Err(AuthError::NotLoggedIn.into_data_api_return());
+    }
+    let admin_id =
+        Uuid::from_str(&claims.sub).map_err(|err| AuthError::from(err).into_data_api_return())?;
+
+    let admin = DBAdmin::get_single_by(&r.db, admin_id)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?
+        .filter(|admin| !admin.disabled && admin.totp_enabled)
+        .ok_or(AuthError::NotLoggedIn.into_data_api_return())?;
+    let attempt = throttle::check(&r.db, &admin.username, &client)
+        .await
+        .map_err(|err| err.into_data_api_return())?;
+
+    let verified = verify_second_factor(&r.db, &admin, &body.code)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    throttle::record(&r.db, attempt, verified).await;
+    drop(r);
+    if !verified {
+        warn!("{} entered an invalid second factor", admin.username);
+        return Err(AuthError::InvalidSecondFactor.into_data_api_return());
+    }
+
+    let mut response = start_session(&data, &admin, client).await?;
+    let cleared = Cookie::build((PENDING_LOGIN_COOKIE, ""))
+        .path("/data/auth/login")
+        .max_age(time::Duration::hours(-1))
+        .same_site(SameSite::Lax)
+        .http_only(true);
+    response
+        .headers_mut()
+        .append(header::SET_COOKIE, cleared.to_string().parse().unwrap());
+    Ok(response)
+}
+
+/// Generates a new secret for the logged in admin. It isn't required at login
+/// until confirmed with [`confirm_totp_handler`]
+#[tracing::instrument(name = "setup totp handler", skip_all)]
+pub async fn setup_totp_handler(
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+) -> Result<Json<Value>, DataApiReturn> {
+    if admin_ext.admin.totp_enabled {
+        return Err(DataResponse::error(
+            "two-factor authentication is already enabled",
+            Some(StatusCode::BAD_REQUEST),
+        ));
+    }
+    let secret = totp::generate_secret();
+    let (otpauth_url, qr_code) = totp::provisioning(&secret, &admin_ext.admin.username)
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    DBAdmin::set_totp(
+        &data.read().await.db,
+        admin_ext.admin.id,
+        Some(secret.clone()),
+        false,
+    )
+    .await
+    .map_err(|err| AuthError::from(err).into_data_api_return())?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "secret": secret,
+        "otpauth_url": otpauth_url,
+        "qr_code": qr_code,
+    })))
+}
+
+#[tracing::instrument(name = "confirm totp handler", skip_all)]
+pub async fn confirm_totp_handler(
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    Form(body): Form<SecondFactorSchema>,
+) -> Result<Json<Value>, DataApiReturn> {
+    let admin = admin_ext.admin;
+    let secret = match (&admin.totp_secret, admin.totp_enabled) {
+        (Some(secret), false) => secret,
+        (_, true) => {
+            return Err(DataResponse::error(
+                "two-factor authentication is already enabled",
+                Some(StatusCode::BAD_REQUEST),
+            ))
+        }
+        (None, false) => {
+            return Err(DataResponse::error(
+                "start two-factor setup first",
+                Some(StatusCode::BAD_REQUEST),
+            ))
+        }
+    };
+
+    let step = totp::matching_step(secret, &admin.username, &body.code, unix_now())
+        .map_err(|err| AuthError::from(err).into_data_api_return())?
+        .ok_or(AuthError::InvalidSecondFactor.into_data_api_return())?;
+
+    let r = data.read().await;
+    DBAdmin::set_totp(&r.db, admin.id, Some(secret.clone()), true)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    DBAdmin::claim_totp_step(&r.db, admin.id, step)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    let recovery_codes = replace_recovery_codes(&r.db, admin.id)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    warn!("{} enabled two-factor authentication", admin.username);
+
+    Ok(Json(json!({
+        "status": "success",
+        "recovery_codes": recovery_codes,
+    })))
+}
+
+#[tracing::instrument(name = "regenerate recovery codes handler", skip_all)]
+pub async fn regenerate_recovery_codes_handler(
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    Form(body): Form<SecondFactorSchema>,
+) -> Result<Json<Value>, DataApiReturn> {
+    let admin = admin_ext.admin;
+    if !admin.totp_enabled {
+        return Err(DataResponse::error(
+            "two-factor authentication is not enabled",
+            Some(StatusCode::BAD_REQUEST),
+        ));
+    }
+    let r = data.read().await;
+    let verified = verify_second_factor(&r.db, &admin, &body.code)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    if !verified {
+        return Err(AuthError::InvalidSecondFactor.into_data_api_return());
+    }
+    let recovery_codes = replace_recovery_codes(&r.db, admin.id)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "recovery_codes": recovery_codes,
+    })))
+}
+
+#[tracing::instrument(name = "disable totp handler", skip_all)]
+pub async fn disable_totp_handler(
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    Form(body): Form<SecondFactorSchema>,
+) -> Result<DataApiReturn, DataApiReturn> {
+    let admin = admin_ext.admin;
+    if !admin.totp_enabled {
+        return Err(DataResponse::error(
+            "two-factor authentication is not enabled",
+            Some(StatusCode::BAD_REQUEST),
+        ));
+    }
+    let r = data.read().await;
+    let verified = verify_second_factor(&r.db, &admin, &body.code)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    if !verified {
+        return Err(AuthError::InvalidSecondFactor.into_data_api_return());
+    }
+    DBAdmin::set_totp(&r.db, admin.id, None, false)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    DBRecoveryCode::delete_all_for_admin(&r.db, admin.id)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    warn!("{} disabled two-factor authentication", admin.username);
+
+    Ok(DataResponse::success(
+        "two-factor authentication has been disabled",
+    ))
+}
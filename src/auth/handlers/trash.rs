@@ -0,0 +1,243 @@
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Extension,
+};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres, Transaction};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    auth::{
+        audit::{self, AuditAction, Subject},
+        client::ClientInfo,
+        middleware::AdminExtension,
+    },
+    database::{
+        handles::DbData,
+        models::{
+            DBBoardMember, DBDedication, DBImage, DBLandingSlide, DBPatrolGear, DBPatrolLog,
+            DBResource, DBTestimonial,
+        },
+    },
+    error::{DataApiReturn, DataResponse, InternalError},
+    state::SharedState,
+};
+
+use super::{
+    error::UploadError,
+    upload::{FileAttachment, GeneralItem, UploadFormItemType, UploadMultipartItemType},
+};
+
+/// How often the trash is checked for items past the retention period
+const PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Takes an item back out of the trash
+#[tracing::instrument(name = "restore handler", skip(data, admin_ext, client))]
+pub async fn restore_item_handler(
+    Path((item_str, id)): Path<(String, Uuid)>,
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    client: ClientInfo,
+) -> Result<impl IntoResponse, DataApiReturn> {
+    let item = GeneralItem::try_from(item_str.as_str())
+        .map_err(|err| DataResponse::error(err.to_string(), Some(StatusCode::NOT_FOUND)))?;
+    let r = data.read().await;
+    let subject = match item {
+        GeneralItem::Form(UploadFormItemType::Debriefs) => DBTestimonial::restore_one(id, &r.db)
+            .await
+            .map(|row| row.map(|row| Subject::row(&item_str, id, &row))),
+        GeneralItem::Multi(UploadMultipartItemType::Support) => DBResource::restore_one(id, &r.db)
+            .await
+            .map(|row| row.map(|row| Subject::row(&item_str, id, &row))),
+        GeneralItem::Multi(UploadMultipartItemType::PatrolLog) => {
+            DBPatrolLog::restore_one(id, &r.db)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::Dedications) => {
+            DBDedication::restore_one(id, &r.db)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::BoardMembers) => {
+            DBBoardMember::restore_one(id, &r.db)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::LandingSlides) => {
+            DBLandingSlide::restore_one(id, &r.db)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::PatrolGear) => {
+            DBPatrolGear::restore_one(id, &r.db)
+                .await
+                .map(|row| row.map(|row| Subject::row(&item_str, id, &row)))
+        }
+    }
+    .map_err(|err| UploadError::from(err).into_data_api_return())?
+    .ok_or(DataResponse::error(
+        format!("no {} with that id in the trash", item_str),
+        Some(StatusCode::NOT_FOUND),
+    ))?;
+    audit::record(
+        &r.db,
+        &admin_ext.admin,
+        &client,
+        AuditAction::Restore,
+        Some(subject),
+    )
+    .await;
+
+    Ok(DataResponse::success(format!("restored {}", item_str)))
+}
+
+/// Deletes an item in the trash for good, along with its images
+#[tracing::instrument(name = "purge handler", skip(data, admin_ext, client))]
+pub async fn purge_item_handler(
+    Path((item_str, id)): Path<(String, Uuid)>,
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    client: ClientInfo,
+) -> Result<impl IntoResponse, DataApiReturn> {
+    let item = GeneralItem::try_from(item_str.as_str())
+        .map_err(|err| DataResponse::error(err.to_string(), Some(StatusCode::NOT_FOUND)))?;
+    let r = data.read().await;
+    let subject = purge_item(&r.db, item, id)
+        .await
+        .map_err(|err| UploadError::from(err).into_data_api_return())?
+        .ok_or(DataResponse::error(
+            format!("no {} with that id in the trash", item_str),
+            Some(StatusCode::NOT_FOUND),
+        ))?;
+    audit::record(
+        &r.db,
+        &admin_ext.admin,
+        &client,
+        AuditAction::Purge,
+        Some(subject),
+    )
+    .await;
+
+    Ok(DataResponse::success(format!("purged {}", item_str)))
+}
+
+/// Purges everything that's been in the trash for longer than `retention`,
+/// then again every [`PURGE_INTERVAL`]
+pub async fn purge_expired_task(pool: Pool<Postgres>, retention: chrono::Duration) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        for item in GeneralItem::all() {
+            if let Err(err) = purge_expired(&pool, item, retention).await {
+                error!("failed to purge expired {}: {:?}", item.as_str(), err);
+            }
+        }
+    }
+}
+
+async fn purge_expired(
+    pool: &Pool<Postgres>,
+    item: GeneralItem,
+    retention: chrono::Duration,
+) -> anyhow::Result<()> {
+    let expired = match item {
+        GeneralItem::Form(UploadFormItemType::Debriefs) => {
+            DBTestimonial::trashed_before(retention, pool).await?
+        }
+        GeneralItem::Multi(UploadMultipartItemType::Support) => {
+            DBResource::trashed_before(retention, pool).await?
+        }
+        GeneralItem::Multi(UploadMultipartItemType::PatrolLog) => {
+            DBPatrolLog::trashed_before(retention, pool).await?
+        }
+        GeneralItem::Multi(UploadMultipartItemType::Dedications) => {
+            DBDedication::trashed_before(retention, pool).await?
+        }
+        GeneralItem::Multi(UploadMultipartItemType::BoardMembers) => {
+            DBBoardMember::trashed_before(retention, pool).await?
+        }
+        GeneralItem::Multi(UploadMultipartItemType::LandingSlides) => {
+            DBLandingSlide::trashed_before(retention, pool).await?
+        }
+        GeneralItem::Multi(UploadMultipartItemType::PatrolGear) => {
+            DBPatrolGear::trashed_before(retention, pool).await?
+        }
+    };
+    for id in expired {
+        if purge_item(pool, item, id).await?.is_some() {
+            info!("purged {} {} after the retention period", item.as_str(), id);
+        }
+    }
+    Ok(())
+}
+
+/// Deletes a trashed item, the images nothing else shows and then their files
+async fn purge_item(
+    pool: &Pool<Postgres>,
+    item: GeneralItem,
+    id: Uuid,
+) -> anyhow::Result<Option<Subject>> {
+    let mut tx = pool.begin().await?;
+    let purged = match item {
+        GeneralItem::Form(UploadFormItemType::Debriefs) => {
+            purge_row::<DBTestimonial, _>(item, id, &mut tx).await?
+        }
+        GeneralItem::Multi(UploadMultipartItemType::Support) => {
+            purge_row::<DBResource, _>(item, id, &mut tx).await?
+        }
+        GeneralItem::Multi(UploadMultipartItemType::PatrolLog) => {
+            purge_row::<DBPatrolLog, _>(item, id, &mut tx).await?
+        }
+        GeneralItem::Multi(UploadMultipartItemType::Dedications) => {
+            purge_row::<DBDedication, _>(item, id, &mut tx).await?
+        }
+        GeneralItem::Multi(UploadMultipartItemType::BoardMembers) => {
+            purge_row::<DBBoardMember, _>(item, id, &mut tx).await?
+        }
+        GeneralItem::Multi(UploadMultipartItemType::LandingSlides) => {
+            purge_row::<DBLandingSlide, _>(item, id, &mut tx).await?
+        }
+        GeneralItem::Multi(UploadMultipartItemType::PatrolGear) => {
+            purge_row::<DBPatrolGear, _>(item, id, &mut tx).await?
+        }
+    };
+    let Some((subject, images)) = purged else {
+        return Ok(None);
+    };
+    tx.commit().await?;
+    // files can't be rolled back, so they only go once the rows are gone
+    if let Err(err) = FileAttachment::remove_images(&images) {
+        warn!(
+            "failed to remove the files of {} {}: {:?}",
+            item.as_str(),
+            id,
+            err
+        );
+    }
+    Ok(Some(subject))
+}
+
+async fn purge_row<D, P>(
+    item: GeneralItem,
+    id: Uuid,
+    tx: &mut Transaction<'_, Postgres>,
+) -> anyhow::Result<Option<(Subject, Vec<DBImage>)>>
+where
+    P: std::fmt::Debug + Serialize + for<'de> Deserialize<'de> + Send + Unpin,
+    D: DbData<P>,
+{
+    // the attachments go with the row, so they're looked up first
+    let attached = D::images(id, &mut *tx).await?;
+    let Some(row) = D::purge_one(id, &mut *tx).await? else {
+        return Ok(None);
+    };
+    let mut img_ids: Vec<Uuid> = attached.into_iter().map(|img| img.id).collect();
+    img_ids.extend(D::delete_revisions(id, &mut *tx).await?);
+    // images shared with another item stay
+    let images = DBImage::delete_unattached(img_ids, &mut *tx).await?;
+    Ok(Some((Subject::row(item.as_str(), id, &row), images)))
+}
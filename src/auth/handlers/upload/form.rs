@@ -1,5 +1,10 @@
 use crate::{
-    auth::handlers::error::UploadError,
+    auth::{
+        audit::{self, AuditAction, Subject},
+        client::ClientInfo,
+        handlers::error::UploadError,
+        middleware::AdminExtension,
+    },
     database::{
         handles::DbData,
         models::{
@@ -19,48 +24,56 @@ use axum::{
     extract::{Path, State},
     http::Response,
     response::IntoResponse,
-    Form,
+    Extension, Form,
 };
 use reqwest::StatusCode;
 use serde_json::Value;
 use tracing::warn;
 
-use super::{UploadItem, UploadItemType};
+use super::{publishing_from_fields, StagedFiles, UploadItem, UploadItemType};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UploadFormItemType {
     // Support,
     Debriefs,
 }
 
-#[tracing::instrument(name = "upload form handler", skip(data))]
+#[tracing::instrument(name = "upload form handler", skip(data, admin_ext, client))]
 pub async fn upload_form_handler(
     Path(item_str): Path<String>,
     State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    client: ClientInfo,
     Form(form): Form<Value>,
 ) -> DataApiReturn {
     let item =
         UploadFormItemType::try_from_str(item_str.as_str()).expect("failed to get upload item");
     let success_message = format!("succesfully uploaded {}", item_str);
     warn!("got item: {:?}", item);
-    match item.into_item(form).await {
+    let mut staged = StagedFiles::new();
+    match item.into_item(form, &mut staged).await {
         Ok(uploadable) => {
             let r = data.read().await;
+            let mut tx = match r.db.begin().await {
+                Ok(tx) => tx,
+                Err(err) => return UploadError::from(err).into_data_api_return(),
+            };
             warn!("inserting: {:?}", uploadable);
 
-            if let Err(e) = match uploadable {
-                UploadItem::Address(add) => match DBAddress::insert_one(add, &r.db).await {
-                    Ok(_) => Ok(()),
-                    Err(e) => Err(UploadError::from(e)),
-                },
-                UploadItem::Support(res) => match DBResource::insert_one(res, &r.db).await {
-                    Ok(_) => Ok(()),
-                    Err(e) => Err(UploadError::from(e)),
-                },
-                UploadItem::Debrief(test) => match DBTestimonial::insert_one(test, &r.db).await {
-                    Ok(_) => Ok(()),
-                    Err(e) => Err(UploadError::from(e)),
-                },
+            let inserted = match uploadable {
+                UploadItem::Address(add) => DBAddress::insert_one(add, &mut tx)
+                    .await
+                    .map(|row| Subject::row(&item_str, row.id(), &row)),
+                UploadItem::Support(res) => {
+                    DBResource::insert_revised(res, &admin_ext.admin, &mut tx)
+                        .await
+                        .map(|row| Subject::row(&item_str, row.id(), &row))
+                }
+                UploadItem::Debrief(test) => {
+                    DBTestimonial::insert_revised(test, &admin_ext.admin, &mut tx)
+                        .await
+                        .map(|row| Subject::row(&item_str, row.id(), &row))
+                }
                 other => {
                     let msg = format!(
                         "{:?} is not a supported upload type for a form upload",
@@ -69,8 +82,26 @@ pub async fn upload_form_handler(
                     warn!("{}", msg);
                     return DataResponse::success(msg.as_str());
                 }
-            } {
-                return DataResponse::error(format!("Failed to upload item: {e:?}"), None);
+            };
+            let committed = match inserted {
+                Ok(subject) => staged.commit(tx).await.map(|_| subject),
+                Err(e) => Err(e),
+            };
+            match committed {
+                Ok(subject) => {
+                    audit::record(
+                        &r.db,
+                        &admin_ext.admin,
+                        &client,
+                        AuditAction::Upload,
+                        Some(subject),
+                    )
+                    .await;
+                }
+                Err(e) => {
+                    let e = UploadError::from(e);
+                    return DataResponse::error(format!("Failed to upload item: {e:?}"), None);
+                }
             }
             DataResponse::success(&success_message)
         }
@@ -108,7 +139,7 @@ impl UploadItemType<Value> for UploadFormItemType {
             }
         }
     }
-    async fn into_item(self, form: Value) -> anyhow::Result<UploadItem> {
+    async fn into_item(self, form: Value, _staged: &mut StagedFiles) -> anyhow::Result<UploadItem> {
         match self {
             Self::Debriefs => {
                 let firstname = get_optional_string_from_form("firstname", &form)
@@ -119,11 +150,17 @@ impl UploadItemType<Value> for UploadFormItemType {
                     .ok_or(UploadError::user_facing("content is none"))?;
                 content = content.replace("\n", "<br/>");
                 let bio = get_optional_string_from_form("bio", &form);
+                let (status, publish_at) = publishing_from_fields(
+                    get_optional_string_from_form("status", &form).as_deref(),
+                    get_optional_string_from_form("publish_at", &form).as_deref(),
+                )?;
                 let test = DBTestimonialParams {
                     firstname,
                     lastname,
                     content,
                     bio,
+                    status,
+                    publish_at,
                 };
                 Ok(UploadItem::Debrief(test))
             }
@@ -1,10 +1,20 @@
 pub mod attachments;
 pub mod form;
 pub mod multipart;
-pub use self::{attachments::*, form::*, multipart::*};
+pub mod publishing;
+pub mod staging;
+pub use self::{attachments::*, form::*, multipart::*, publishing::*, staging::*};
 
-use crate::database::models::{
-    DBAddressParams, DBDedicationParams, DBPatrolLogParams, DBResourceParams, DBTestimonialParams,
+use crate::{
+    database::models::{
+        DBAddressParams, DBBoardMemberParams, DBDedicationParams, DBLandingSlideParams,
+        DBPatrolGearParams, DBPatrolLogParams, DBResourceParams, DBTestimonialParams,
+    },
+    routes::pages::{
+        about_us::BOARD_MEMBERS, debriefs::DEBRIEFS, dedications::DEDICATIONS,
+        landing::LANDING_SLIDES, patrol_gear::PATROL_GEAR, patrol_log::logs::PATROL_LOG,
+        support::SUPPORT,
+    },
 };
 use anyhow::anyhow;
 use chrono::NaiveDate;
@@ -16,6 +26,55 @@ pub enum UploadItem {
     Debrief(DBTestimonialParams),
     PatrolLog(DBPatrolLogParams),
     Dedication(DBDedicationParams),
+    BoardMember(DBBoardMemberParams),
+    LandingSlide(DBLandingSlideParams),
+    PatrolGear(DBPatrolGearParams),
+}
+
+/// Any item that can be uploaded, through either a form or a multipart upload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneralItem {
+    Form(UploadFormItemType),
+    Multi(UploadMultipartItemType),
+}
+
+impl TryFrom<&str> for GeneralItem {
+    type Error = anyhow::Error;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if let Ok(item) = UploadFormItemType::try_from_str(value) {
+            return Ok(Self::Form(item));
+        } else if let Ok(item) = UploadMultipartItemType::try_from_str(value) {
+            return Ok(Self::Multi(item));
+        }
+
+        Err(anyhow!("{} is not a supported item", value))
+    }
+}
+
+impl GeneralItem {
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::Form(UploadFormItemType::Debriefs),
+            Self::Multi(UploadMultipartItemType::PatrolLog),
+            Self::Multi(UploadMultipartItemType::Dedications),
+            Self::Multi(UploadMultipartItemType::Support),
+            Self::Multi(UploadMultipartItemType::BoardMembers),
+            Self::Multi(UploadMultipartItemType::LandingSlides),
+            Self::Multi(UploadMultipartItemType::PatrolGear),
+        ]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Form(UploadFormItemType::Debriefs) => DEBRIEFS,
+            Self::Multi(UploadMultipartItemType::PatrolLog) => PATROL_LOG,
+            Self::Multi(UploadMultipartItemType::Dedications) => DEDICATIONS,
+            Self::Multi(UploadMultipartItemType::Support) => SUPPORT,
+            Self::Multi(UploadMultipartItemType::BoardMembers) => BOARD_MEMBERS,
+            Self::Multi(UploadMultipartItemType::LandingSlides) => LANDING_SLIDES,
+            Self::Multi(UploadMultipartItemType::PatrolGear) => PATROL_GEAR,
+        }
+    }
 }
 
 pub const IMAGES_DIRECTORY: &str = "public/assets/images";
@@ -27,7 +86,13 @@ where
     fn try_from_str(str: &str) -> anyhow::Result<Self>
     where
         Self: Sized;
-    async fn into_item(self, form_or_multipart: T) -> anyhow::Result<UploadItem>;
+    /// Parses the request into params. Any files are written to `staged` rather
+    /// than straight to their final place
+    async fn into_item(
+        self,
+        form_or_multipart: T,
+        staged: &mut StagedFiles,
+    ) -> anyhow::Result<UploadItem>;
 }
 
 pub(super) fn naive_date_from_str(str: &str) -> anyhow::Result<NaiveDate> {
@@ -1,17 +1,29 @@
 use crate::{
-    auth::handlers::{
-        error::UploadError,
-        upload::{attachments::FileAttachment, naive_date_from_str},
+    auth::{
+        audit::{self, AuditAction, Subject},
+        client::ClientInfo,
+        handlers::{
+            error::UploadError,
+            upload::{
+                attachments::FileAttachment, naive_date_from_str, publishing_from_fields,
+                staging::StagedFiles,
+            },
+        },
+        middleware::AdminExtension,
     },
     database::{
         handles::DbData,
         models::{
-            DBAddressParams, DBDedication, DBDedicationParams, DBImage, DBImageParams, DBPatrolLog,
-            DBPatrolLogParams, DBResource, DBResourceParams,
+            DBAddressParams, DBBoardMember, DBBoardMemberParams, DBDedication, DBDedicationParams,
+            DBLandingSlide, DBLandingSlideParams, DBPatrolGear, DBPatrolGearParams, DBPatrolLog,
+            DBPatrolLogParams, DBResource, DBResourceParams, GearCategory, GearVariant,
         },
     },
     error::{DataApiReturn, InternalError},
-    routes::pages::{dedications::DEDICATIONS, patrol_log::logs::PATROL_LOG, support::SUPPORT},
+    routes::pages::{
+        about_us::BOARD_MEMBERS, dedications::DEDICATIONS, landing::LANDING_SLIDES,
+        patrol_gear::PATROL_GEAR, patrol_log::logs::PATROL_LOG, support::SUPPORT,
+    },
     state::SharedState,
 };
 use anyhow::anyhow;
@@ -19,19 +31,23 @@ use axum::{
     extract::{multipart::Field, Multipart, Path, State},
     http::Response,
     response::IntoResponse,
+    Extension,
 };
 use chrono::NaiveDate;
-use reqwest::StatusCode;
+use sqlx::types::Json;
 use std::ops::Deref;
 use tracing::warn;
 
 use super::{UploadItem, UploadItemType, IMAGES_DIRECTORY};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UploadMultipartItemType {
     PatrolLog,
     Dedications,
     Support,
+    BoardMembers,
+    LandingSlides,
+    PatrolGear,
 }
 
 async fn handle_other(other: &str, field: Field<'_>, attachments: &mut Vec<FileAttachment>) {
@@ -82,6 +98,20 @@ fn none_if_empty_string(str: String) -> Option<String> {
     }
 }
 
+/// Checkboxes send `on` when ticked and nothing at all otherwise
+fn bool_from_str(str: &str) -> bool {
+    matches!(str.trim(), "on" | "true")
+}
+
+/// Tag names typed into one box, separated by commas
+fn tags_from_str(str: &str) -> Vec<String> {
+    str.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 impl UploadItemType<Multipart> for UploadMultipartItemType {
     fn try_from_str(str: &str) -> anyhow::Result<Self> {
         warn!("getting item: {}", str);
@@ -89,6 +119,9 @@ impl UploadItemType<Multipart> for UploadMultipartItemType {
             _ if str == PATROL_LOG => Ok(Self::PatrolLog),
             _ if str == DEDICATIONS => Ok(Self::Dedications),
             _ if str == SUPPORT => Ok(Self::Support),
+            _ if str == BOARD_MEMBERS => Ok(Self::BoardMembers),
+            _ if str == LANDING_SLIDES => Ok(Self::LandingSlides),
+            _ if str == PATROL_GEAR => Ok(Self::PatrolGear),
             other => {
                 warn!("none found for: {}", other);
                 Err(anyhow!("{} is not a valid upload multipart item", other))
@@ -96,13 +129,20 @@ impl UploadItemType<Multipart> for UploadMultipartItemType {
         }
     }
 
-    async fn into_item(self, mut multipart: Multipart) -> anyhow::Result<UploadItem> {
+    async fn into_item(
+        self,
+        mut multipart: Multipart,
+        staged: &mut StagedFiles,
+    ) -> anyhow::Result<UploadItem> {
         warn!("coercing multipart: {:?}", multipart);
         match self {
             Self::PatrolLog => {
                 let mut date = Option::<NaiveDate>::None;
                 let mut heading = Option::<String>::None;
                 let mut description = Option::<String>::None;
+                let mut tags = vec![];
+                let mut status = Option::<String>::None;
+                let mut publish_at = Option::<String>::None;
                 let mut attachments = vec![];
                 while let Some(field) = multipart.next_field().await? {
                     let fieldname = field
@@ -121,6 +161,15 @@ impl UploadItemType<Multipart> for UploadMultipartItemType {
                         "description" => {
                             description = Some(field.text().await?);
                         }
+                        "tags" => {
+                            tags = tags_from_str(&field.text().await?);
+                        }
+                        "status" => {
+                            status = Some(field.text().await?);
+                        }
+                        "publish_at" => {
+                            publish_at = Some(field.text().await?);
+                        }
                         "images" => {
                             let attachment = FileAttachment::new(
                                 field
@@ -141,23 +190,24 @@ impl UploadItemType<Multipart> for UploadMultipartItemType {
                 let heading = heading.expect("no heading");
                 let mut img_params = vec![];
                 if !attachments.is_empty() {
-                    img_params = FileAttachment::save_multiple_to_filesys(
-                        attachments,
-                        &self,
-                        Some(&heading),
-                    )
-                    .expect("failed to save attachments to filesys");
+                    img_params =
+                        FileAttachment::stage_multiple(attachments, &self, Some(&heading), staged)?;
                 }
 
                 let description = description
                     .expect("expected description")
                     .replace("\n", "<br/>");
 
+                let (status, publish_at) =
+                    publishing_from_fields(status.as_deref(), publish_at.as_deref())?;
                 let log = DBPatrolLogParams {
                     heading,
                     description,
                     date: date.expect("no date"),
                     img_params,
+                    status,
+                    publish_at,
+                    tags,
                 };
 
                 Ok(UploadItem::PatrolLog(log))
@@ -168,6 +218,8 @@ impl UploadItemType<Multipart> for UploadMultipartItemType {
                 let mut death = Option::<NaiveDate>::None;
                 let mut names = Option::<Vec<String>>::None;
                 let mut bio = Option::<String>::None;
+                let mut status = Option::<String>::None;
+                let mut publish_at = Option::<String>::None;
                 let mut attachments = vec![];
                 while let Some(field) = multipart.next_field().await? {
                     let fieldname = field
@@ -194,6 +246,12 @@ impl UploadItemType<Multipart> for UploadMultipartItemType {
                             bio = Some(field.text().await?);
                         }
 
+                        "status" => {
+                            status = Some(field.text().await?);
+                        }
+                        "publish_at" => {
+                            publish_at = Some(field.text().await?);
+                        }
                         "images" => {
                             let attachment = FileAttachment::new(
                                 field
@@ -212,21 +270,25 @@ impl UploadItemType<Multipart> for UploadMultipartItemType {
                 let names = names.expect("no names");
                 let mut img_params = vec![];
                 if !attachments.is_empty() {
-                    img_params = FileAttachment::save_multiple_to_filesys(
+                    img_params = FileAttachment::stage_multiple(
                         attachments,
                         &self,
                         Some(&names.join("-")),
-                    )
-                    .expect("failed to save attachments to filesys");
+                        staged,
+                    )?;
                 }
 
                 let bio = bio.expect("expected bio").replace("\n", "<br/>");
+                let (status, publish_at) =
+                    publishing_from_fields(status.as_deref(), publish_at.as_deref())?;
                 let ded = DBDedicationParams {
                     names,
                     bio,
                     birth: birth.expect("no birth"),
                     death: death.expect("no death"),
                     img_params,
+                    status,
+                    publish_at,
                 };
                 Ok(UploadItem::Dedication(ded))
             }
@@ -251,7 +313,11 @@ impl UploadItemType<Multipart> for UploadMultipartItemType {
                 let mut threads = Option::<String>::None;
                 let mut instagram = Option::<String>::None;
 
+                let mut status = Option::<String>::None;
+                let mut publish_at = Option::<String>::None;
+
                 let mut missions = vec![];
+                let mut tags = vec![];
                 let mut attachments = vec![];
                 while let Some(field) = multipart.next_field().await? {
                     let fieldname = field
@@ -352,7 +418,16 @@ impl UploadItemType<Multipart> for UploadMultipartItemType {
                                 })
                                 .collect();
                         }
+                        "tags" => {
+                            tags = tags_from_str(&field.text().await?);
+                        }
 
+                        "status" => {
+                            status = Some(field.text().await?);
+                        }
+                        "publish_at" => {
+                            publish_at = Some(field.text().await?);
+                        }
                         "images" => {
                             let attachment = FileAttachment::new(
                                 field
@@ -386,10 +461,11 @@ impl UploadItemType<Multipart> for UploadMultipartItemType {
                 let mut img_params = vec![];
                 if !attachments.is_empty() {
                     img_params =
-                        FileAttachment::save_multiple_to_filesys(attachments, &self, Some(&name))
-                            .expect("failed to save attachments to filesys");
+                        FileAttachment::stage_multiple(attachments, &self, Some(&name), staged)?;
                 }
 
+                let (status, publish_at) =
+                    publishing_from_fields(status.as_deref(), publish_at.as_deref())?;
                 let res = DBResourceParams {
                     name,
                     img_params,
@@ -399,65 +475,321 @@ impl UploadItemType<Multipart> for UploadMultipartItemType {
                     phone,
                     email,
                     address,
+                    address_id: None,
                     instagram,
                     facebook,
                     youtube,
                     linkedin,
                     threads,
                     twitter,
+                    status,
+                    publish_at,
+                    tags,
                 };
                 Ok(UploadItem::Support(res))
             }
+            Self::BoardMembers => {
+                let mut name = Option::<String>::None;
+                let mut role = Option::<String>::None;
+                let mut bio = Option::<String>::None;
+                let mut active = false;
+                let mut attachments = vec![];
+                while let Some(field) = multipart.next_field().await? {
+                    let fieldname = field
+                        .name()
+                        .ok_or(anyhow!("no name on field: {:?}", field))?
+                        .to_owned();
+
+                    match fieldname.as_str() {
+                        "name" => {
+                            name = Some(field.text().await?);
+                        }
+                        "role" => {
+                            role = Some(field.text().await?);
+                        }
+                        "bio" => {
+                            bio = Some(field.text().await?);
+                        }
+                        "active" => {
+                            active = bool_from_str(&field.text().await?);
+                        }
+                        "images" => {
+                            let attachment = FileAttachment::new(
+                                field
+                                    .file_name()
+                                    .ok_or(anyhow!("no file name on image"))?
+                                    .to_owned()
+                                    .as_str(),
+                                field.bytes().await?.deref(),
+                            );
+                            attachments.push(attachment);
+                        }
+                        other => handle_other(other, field, &mut attachments).await,
+                    }
+                }
+
+                let name = name.ok_or(UploadError::user_facing("name is none"))?;
+                let role = role.ok_or(UploadError::user_facing("role is none"))?;
+                let bio = bio.ok_or(UploadError::user_facing("bio is none"))?;
+                let mut img_params = vec![];
+                if !attachments.is_empty() {
+                    img_params =
+                        FileAttachment::stage_multiple(attachments, &self, Some(&name), staged)?;
+                }
+
+                let member = DBBoardMemberParams {
+                    name,
+                    role,
+                    bio: bio.replace("\n", "<br/>"),
+                    img_params,
+                    active,
+                };
+                Ok(UploadItem::BoardMember(member))
+            }
+            Self::LandingSlides => {
+                let mut alt = Option::<String>::None;
+                let mut subtitle = Option::<String>::None;
+                let mut active = false;
+                let mut attachments = vec![];
+                while let Some(field) = multipart.next_field().await? {
+                    let fieldname = field
+                        .name()
+                        .ok_or(anyhow!("no name on field: {:?}", field))?
+                        .to_owned();
+
+                    match fieldname.as_str() {
+                        "alt" => {
+                            alt = Some(field.text().await?);
+                        }
+                        "subtitle" => {
+                            subtitle = Some(field.text().await?);
+                        }
+                        "active" => {
+                            active = bool_from_str(&field.text().await?);
+                        }
+                        "images" => {
+                            let attachment = FileAttachment::new(
+                                field
+                                    .file_name()
+                                    .ok_or(anyhow!("no file name on image"))?
+                                    .to_owned()
+                                    .as_str(),
+                                field.bytes().await?.deref(),
+                            );
+                            attachments.push(attachment);
+                        }
+                        other => handle_other(other, field, &mut attachments).await,
+                    }
+                }
+
+                let alt = alt.ok_or(UploadError::user_facing("alt is none"))?;
+                let mut img_params = vec![];
+                if !attachments.is_empty() {
+                    for attachment in attachments.iter_mut() {
+                        attachment.alt.get_or_insert(alt.clone());
+                    }
+                    img_params = FileAttachment::stage_multiple(attachments, &self, None, staged)?;
+                }
+
+                let slide = DBLandingSlideParams {
+                    alt,
+                    subtitle: subtitle.unwrap_or_default().replace("\n", "<br/>"),
+                    img_params,
+                    active,
+                };
+                Ok(UploadItem::LandingSlide(slide))
+            }
+            Self::PatrolGear => {
+                let mut name = Option::<String>::None;
+                let mut category = Option::<GearCategory>::None;
+                let mut price = Option::<i32>::None;
+                let mut description = Option::<String>::None;
+                let mut active = false;
+                // one entry per variant row of the form, in the same order
+                let mut sizes = vec![];
+                let mut colors = vec![];
+                let mut stocks = vec![];
+                let mut attachments = vec![];
+                while let Some(field) = multipart.next_field().await? {
+                    let fieldname = field
+                        .name()
+                        .ok_or(anyhow!("no name on field: {:?}", field))?
+                        .to_owned();
+
+                    match fieldname.as_str() {
+                        "name" => {
+                            name = Some(field.text().await?);
+                        }
+                        "category" => {
+                            category = Some(field.text().await?.parse().map_err(
+                                |err: anyhow::Error| UploadError::user_facing(&err.to_string()),
+                            )?);
+                        }
+                        "price" => {
+                            price =
+                                Some(field.text().await?.trim().parse().map_err(|_| {
+                                    UploadError::user_facing("price is not a number")
+                                })?);
+                        }
+                        "description" => {
+                            description = Some(field.text().await?);
+                        }
+                        "active" => {
+                            active = bool_from_str(&field.text().await?);
+                        }
+                        "sizes[]" => {
+                            sizes.push(none_if_empty_string(field.text().await?));
+                        }
+                        "colors[]" => {
+                            colors.push(none_if_empty_string(field.text().await?));
+                        }
+                        "stocks[]" => {
+                            stocks.push(field.text().await?.trim().parse::<u32>().map_err(
+                                |_| UploadError::user_facing("stock is not a whole number"),
+                            )?);
+                        }
+                        "images" => {
+                            let attachment = FileAttachment::new(
+                                field
+                                    .file_name()
+                                    .ok_or(anyhow!("no file name on image"))?
+                                    .to_owned()
+                                    .as_str(),
+                                field.bytes().await?.deref(),
+                            );
+                            attachments.push(attachment);
+                        }
+                        other => handle_other(other, field, &mut attachments).await,
+                    }
+                }
+
+                if sizes.len() != stocks.len() || colors.len() != stocks.len() {
+                    return Err(UploadError::user_facing(
+                        "every variant needs a size, color and stock field",
+                    )
+                    .into());
+                }
+                let variants = sizes
+                    .into_iter()
+                    .zip(colors)
+                    .zip(stocks)
+                    .map(|((size, color), stock)| GearVariant { size, color, stock })
+                    .collect();
+
+                let name = name.ok_or(UploadError::user_facing("name is none"))?;
+                let category = category.ok_or(UploadError::user_facing("category is none"))?;
+                let price = price.ok_or(UploadError::user_facing("price is none"))?;
+                if price < 0 {
+                    return Err(UploadError::user_facing("price can't be negative").into());
+                }
+                let mut img_params = vec![];
+                if !attachments.is_empty() {
+                    for attachment in attachments.iter_mut() {
+                        attachment.alt.get_or_insert(name.clone());
+                    }
+                    img_params = FileAttachment::stage_multiple(
+                        attachments,
+                        &self,
+                        Some(category.as_str()),
+                        staged,
+                    )?;
+                }
+
+                let gear = DBPatrolGearParams {
+                    name,
+                    category,
+                    price,
+                    description: description.unwrap_or_default().replace("\n", "<br/>"),
+                    variants: Json(variants),
+                    img_params,
+                    active,
+                };
+                Ok(UploadItem::PatrolGear(gear))
+            }
         }
     }
 }
 
-#[tracing::instrument(name = "upload multipart handler", skip(data))]
+#[tracing::instrument(name = "upload multipart handler", skip(data, admin_ext, client))]
 pub async fn upload_multipart_handler(
     State(data): State<SharedState>,
     Path(item_str): Path<String>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    client: ClientInfo,
     multipart: Multipart,
 ) -> Result<impl IntoResponse, DataApiReturn> {
     let item = UploadMultipartItemType::try_from_str(item_str.as_str())
         .expect("failed to get upload item");
     let success_message = format!("succesfully uploaded {}", item_str);
     warn!("got item: {:?}", item);
-    match item.into_item(multipart).await {
+    let mut staged = StagedFiles::new();
+    match item.into_item(multipart, &mut staged).await {
         Ok(uploadable) => {
             let r = data.read().await;
+            let mut tx =
+                r.db.begin()
+                    .await
+                    .map_err(|err| UploadError::from(err).into_data_api_return())?;
             warn!("inserting: {:?}", uploadable);
-            match uploadable {
+            let subjects: Vec<Subject> = match uploadable {
                 UploadItem::Dedication(ded) => {
-                    DBImage::insert_multiple_with_images::<DBDedication, DBDedicationParams>(
-                        &r.db,
-                        vec![ded],
-                    )
-                    .await
-                    .map_err(|err| {
-                        warn!("error: {:?}", err);
-                        UploadError::from(err).into_data_api_return()
-                    })?;
+                    let row = DBDedication::insert_revised(ded, &admin_ext.admin, &mut tx)
+                        .await
+                        .map_err(|err| {
+                            warn!("error: {:?}", err);
+                            UploadError::from(err).into_data_api_return()
+                        })?;
+                    vec![Subject::row(&item_str, row.id(), &row)]
                 }
 
                 UploadItem::PatrolLog(log) => {
-                    DBImage::insert_multiple_with_images::<DBPatrolLog, DBPatrolLogParams>(
-                        &r.db,
-                        vec![log],
-                    )
-                    .await
-                    .map_err(|err| {
-                        warn!("error: {:?}", err);
-                        UploadError::from(err).into_data_api_return()
-                    })?;
+                    let row = DBPatrolLog::insert_revised(log, &admin_ext.admin, &mut tx)
+                        .await
+                        .map_err(|err| {
+                            warn!("error: {:?}", err);
+                            UploadError::from(err).into_data_api_return()
+                        })?;
+                    vec![Subject::row(&item_str, row.id(), &row)]
                 }
 
                 UploadItem::Support(support) => {
-                    DBResource::insert_one(support, &r.db)
+                    let row = DBResource::insert_revised(support, &admin_ext.admin, &mut tx)
+                        .await
+                        .map_err(|err| {
+                            warn!("error: {:?}", err);
+                            UploadError::from(err).into_data_api_return()
+                        })?;
+                    vec![Subject::row(&item_str, row.id(), &row)]
+                }
+
+                UploadItem::BoardMember(member) => {
+                    let row = DBBoardMember::insert_revised(member, &admin_ext.admin, &mut tx)
                         .await
                         .map_err(|err| {
                             warn!("error: {:?}", err);
                             UploadError::from(err).into_data_api_return()
                         })?;
+                    vec![Subject::row(&item_str, row.id(), &row)]
+                }
+
+                UploadItem::LandingSlide(slide) => {
+                    let row = DBLandingSlide::insert_revised(slide, &admin_ext.admin, &mut tx)
+                        .await
+                        .map_err(|err| {
+                            warn!("error: {:?}", err);
+                            UploadError::from(err).into_data_api_return()
+                        })?;
+                    vec![Subject::row(&item_str, row.id(), &row)]
+                }
+
+                UploadItem::PatrolGear(gear) => {
+                    let row = DBPatrolGear::insert_revised(gear, &admin_ext.admin, &mut tx)
+                        .await
+                        .map_err(|err| {
+                            warn!("error: {:?}", err);
+                            UploadError::from(err).into_data_api_return()
+                        })?;
+                    vec![Subject::row(&item_str, row.id(), &row)]
                 }
                 other => {
                     let m = format!(
@@ -467,6 +799,20 @@ pub async fn upload_multipart_handler(
                     warn!(m);
                     return Ok(Response::new(m));
                 }
+            };
+            staged
+                .commit(tx)
+                .await
+                .map_err(|err| UploadError::from(err).into_data_api_return())?;
+            for subject in subjects {
+                audit::record(
+                    &r.db,
+                    &admin_ext.admin,
+                    &client,
+                    AuditAction::Upload,
+                    Some(subject),
+                )
+                .await;
             }
 
             let response = Response::new(success_message);
@@ -474,11 +820,7 @@ pub async fn upload_multipart_handler(
         }
         Err(err) => {
             warn!("returning err: {:?}", err);
-            let r = Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(err.to_string())
-                .unwrap();
-            return Ok(r);
+            Err(UploadError::from(err).into_data_api_return())
         }
     }
 }
@@ -0,0 +1,117 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::{Pool, Postgres};
+use tracing::{error, info};
+
+use crate::{
+    auth::handlers::error::UploadError,
+    database::{
+        handles::DbData,
+        models::{ContentStatus, DBDedication, DBPatrolLog, DBResource, DBTestimonial},
+    },
+};
+
+use super::{GeneralItem, UploadFormItemType, UploadMultipartItemType};
+
+/// How often scheduled items are checked for whether they're due
+const PUBLISH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Reads the `status` and `publish_at` fields of an upload, publishing right
+/// away when there's no status. Times without an offset, like the ones
+/// `datetime-local` inputs send, are taken as UTC
+pub fn publishing_from_fields(
+    status: Option<&str>,
+    publish_at: Option<&str>,
+) -> anyhow::Result<(ContentStatus, Option<DateTime<Utc>>)> {
+    let status = match status {
+        Some(status) => status
+            .parse()
+            .map_err(|_| UploadError::user_facing("unknown status"))?,
+        None => ContentStatus::default(),
+    };
+    let publish_at = match publish_at.map(str::trim).filter(|at| !at.is_empty()) {
+        Some(at) => Some(
+            DateTime::parse_from_rfc3339(at)
+                .map(|at| at.with_timezone(&Utc))
+                .or_else(|_| {
+                    NaiveDateTime::parse_from_str(at, "%Y-%m-%dT%H:%M").map(|at| at.and_utc())
+                })
+                .map_err(|_| UploadError::user_facing("publish_at is not a valid time"))?,
+        ),
+        None => None,
+    };
+    if status == ContentStatus::Scheduled && publish_at.is_none() {
+        return Err(UploadError::user_facing("scheduled items need a publish_at").into());
+    }
+    Ok((status, publish_at))
+}
+
+/// Publishes every scheduled item that's due, then again every
+/// [`PUBLISH_INTERVAL`]
+pub async fn publish_scheduled_task(pool: Pool<Postgres>) {
+    let mut interval = tokio::time::interval(PUBLISH_INTERVAL);
+    loop {
+        interval.tick().await;
+        for item in GeneralItem::all() {
+            match publish_due(&pool, item).await {
+                Ok(ids) => {
+                    for id in ids {
+                        info!("published scheduled {} {}", item.as_str(), id);
+                    }
+                }
+                Err(err) => error!("failed to publish scheduled {}: {:?}", item.as_str(), err),
+            }
+        }
+    }
+}
+
+async fn publish_due(pool: &Pool<Postgres>, item: GeneralItem) -> anyhow::Result<Vec<uuid::Uuid>> {
+    let ids = match item {
+        GeneralItem::Form(UploadFormItemType::Debriefs) => DBTestimonial::publish_due(pool)
+            .await?
+            .iter()
+            .map(|row| row.id)
+            .collect(),
+        GeneralItem::Multi(UploadMultipartItemType::Support) => DBResource::publish_due(pool)
+            .await?
+            .iter()
+            .map(|row| row.id)
+            .collect(),
+        GeneralItem::Multi(UploadMultipartItemType::PatrolLog) => DBPatrolLog::publish_due(pool)
+            .await?
+            .iter()
+            .map(|row| row.id)
+            .collect(),
+        GeneralItem::Multi(UploadMultipartItemType::Dedications) => DBDedication::publish_due(pool)
+            .await?
+            .iter()
+            .map(|row| row.id)
+            .collect(),
+        // these are shown or not through their active flag
+        GeneralItem::Multi(UploadMultipartItemType::BoardMembers)
+        | GeneralItem::Multi(UploadMultipartItemType::LandingSlides)
+        | GeneralItem::Multi(UploadMultipartItemType::PatrolGear) => vec![],
+    };
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::publishing_from_fields;
+    use crate::database::models::ContentStatus;
+
+    #[test]
+    fn scheduling_needs_a_valid_time() {
+        assert_eq!(
+            publishing_from_fields(None, None).unwrap(),
+            (ContentStatus::Published, None)
+        );
+        let (status, at) =
+            publishing_from_fields(Some("scheduled"), Some("2024-10-18T14:30")).unwrap();
+        assert_eq!(status, ContentStatus::Scheduled);
+        assert_eq!(at.unwrap().to_rfc3339(), "2024-10-18T14:30:00+00:00");
+
+        assert!(publishing_from_fields(Some("scheduled"), Some("")).is_err());
+        assert!(publishing_from_fields(Some("scheduled"), Some("tomorrow")).is_err());
+        assert!(publishing_from_fields(Some("live"), None).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use anyhow::anyhow;
+use sqlx::{Postgres, Transaction};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use super::attachments::remove_file_and_empty_dir;
+
+/// Files written while handling an upload. They wait in a temp directory and
+/// only move into `public/assets/images` when the rows pointing at them are
+/// committed. Dropping this without committing deletes them
+#[derive(Debug)]
+pub struct StagedFiles {
+    dir: PathBuf,
+    files: Vec<StagedFile>,
+}
+
+#[derive(Debug)]
+struct StagedFile {
+    staged: PathBuf,
+    destination: PathBuf,
+}
+
+impl StagedFiles {
+    pub fn new() -> Self {
+        Self {
+            dir: std::env::temp_dir().join(format!("semperflies-upload-{}", Uuid::new_v4())),
+            files: vec![],
+        }
+    }
+
+    /// Where to write a file that should end up at `destination`
+    pub fn stage(&mut self, destination: &str) -> anyhow::Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let staged = self.dir.join(self.files.len().to_string());
+        self.files.push(StagedFile {
+            staged: staged.clone(),
+            destination: PathBuf::from(destination),
+        });
+        Ok(staged)
+    }
+
+    /// Moves every staged file into place, then commits `tx`. If either step
+    /// fails the files moved so far are removed again and the transaction
+    /// rolls back when it's dropped
+    pub async fn commit(self, tx: Transaction<'_, Postgres>) -> anyhow::Result<()> {
+        let mut moved = vec![];
+        for file in self.files.iter() {
+            if let Err(err) = file.move_into_place() {
+                Self::undo(&moved);
+                return Err(err);
+            }
+            moved.push(file);
+        }
+
+        if let Err(err) = tx.commit().await {
+            Self::undo(&moved);
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    fn undo(moved: &[&StagedFile]) {
+        for file in moved {
+            if let Err(err) = remove_file_and_empty_dir(&file.destination) {
+                error!(
+                    "failed to remove {:?} after a failed upload: {:?}",
+                    file.destination, err
+                );
+            }
+        }
+    }
+}
+
+impl StagedFile {
+    /// Refuses to replace an existing file, since a rollback couldn't bring it
+    /// back and another item may still point at it
+    fn move_into_place(&self) -> anyhow::Result<()> {
+        if let Some(parent) = self.destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if self.destination.exists() {
+            return Err(anyhow!("file: {:?} already exists", self.destination));
+        }
+        // the temp directory may be on another filesystem
+        if fs::rename(&self.staged, &self.destination).is_err() {
+            fs::copy(&self.staged, &self.destination)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for StagedFiles {
+    fn drop(&mut self) {
+        if Path::new(&self.dir).exists() {
+            if let Err(err) = fs::remove_dir_all(&self.dir) {
+                warn!(
+                    "failed to clean up staged files in {:?}: {:?}",
+                    self.dir, err
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StagedFiles;
+    use std::fs;
+
+    #[test]
+    fn dropped_uploads_leave_nothing_behind() {
+        let destination = std::env::temp_dir().join(format!(
+            "semperflies-staging-test-{}/image.webp",
+            uuid::Uuid::new_v4()
+        ));
+        let mut staged = StagedFiles::new();
+        let path = staged.stage(destination.to_str().unwrap()).unwrap();
+        fs::write(&path, b"webp").unwrap();
+        let dir = staged.dir.clone();
+        assert!(dir.exists());
+
+        drop(staged);
+        assert!(!dir.exists());
+        assert!(!destination.exists());
+    }
+
+    #[test]
+    fn existing_files_are_never_replaced() {
+        let destination = std::env::temp_dir().join(format!(
+            "semperflies-staging-test-{}/image.webp",
+            uuid::Uuid::new_v4()
+        ));
+        fs::create_dir_all(destination.parent().unwrap()).unwrap();
+        fs::write(&destination, b"already here").unwrap();
+
+        let mut staged = StagedFiles::new();
+        let path = staged.stage(destination.to_str().unwrap()).unwrap();
+        fs::write(&path, b"webp").unwrap();
+        assert!(staged.files[0].move_into_place().is_err());
+        assert_eq!(fs::read(&destination).unwrap(), b"already here");
+
+        fs::remove_dir_all(destination.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,478 @@
+use axum::{
+    extract::{Path, State},
+    http::Response,
+    Extension, Json,
+};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use tracing::warn;
+use uuid::Uuid;
+use webauthn_rs::prelude::{PasskeyAuthentication, PasskeyRegistration};
+
+use crate::{
+    auth::{
+        client::ClientInfo,
+        error::AuthError,
+        middleware::AdminExtension,
+        model::{PasskeyLoginFinishSchema, PasskeyLoginStartSchema, PasskeyRegisterFinishSchema},
+        passkey::{self, CHALLENGE_MINUTES, LOGIN_CHALLENGE, REGISTER_CHALLENGE},
+    },
+    database::{
+        handles::DbData,
+        models::{
+            DBAdmin, DBPasskey, DBPasskeyParams, DBWebauthnChallenge, DBWebauthnChallengeParams,
+        },
+    },
+    error::{DataApiReturn, DataResponse, InternalError},
+    state::{AppState, SharedState},
+};
+
+use super::start_session;
+
+const MAX_PASSKEY_NAME_LEN: usize = 100;
+
+/// Stores the server side state of a challenge, returning the id the browser
+/// sends back with its answer
+async fn save_challenge(
+    state: &AppState,
+    admin_id: Uuid,
+    kind: &str,
+    challenge_state: Value,
+) -> anyhow::Result<Uuid> {
+    if let Err(err) = DBWebauthnChallenge::delete_expired(&state.db).await {
+        warn!("failed to clean up expired webauthn challenges: {:?}", err);
+    }
+    let challenge = DBWebauthnChallenge::insert_one(
+        DBWebauthnChallengeParams {
+            admin_id,
+            kind: kind.to_string(),
+            state: sqlx::types::Json(challenge_state),
+            expires_at: chrono::Utc::now() + chrono::Duration::minutes(CHALLENGE_MINUTES),
+        },
+        &state.db,
+    )
+    .await?;
+    Ok(challenge.id)
+}
+
+#[tracing::instrument(name = "start passkey registration", skip_all)]
+pub async fn register_start_handler(
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+) -> Result<Json<Value>, DataApiReturn> {
+    let admin = admin_ext.admin;
+    let r = data.read().await;
+    let webauthn =
+        passkey::webauthn(&r.env).map_err(|err| AuthError::from(err).into_data_api_return())?;
+    let existing = DBPasskey::get_for_admin(&r.db, admin.id)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?
+        .into_iter()
+        .map(|p| p.passkey.cred_id().clone())
+        .collect();
+
+    let (options, registration) = webauthn
+        .start_passkey_registration(admin.id, &admin.username, &admin.username, Some(existing))
+        .map_err(|err| AuthError::from(anyhow::Error::from(err)).into_data_api_return())?;
+    let challenge_state = serde_json::to_value(&registration)
+        .map_err(|err| AuthError::from(anyhow::Error::from(err)).into_data_api_return())?;
+    let challenge_id = save_challenge(&r, admin.id, REGISTER_CHALLENGE, challenge_state)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "challenge_id": challenge_id,
+        "options": options,
+    })))
+}
+
+#[tracing::instrument(name = "finish passkey registration", skip_all)]
+pub async fn register_finish_handler(
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    Json(body): Json<PasskeyRegisterFinishSchema>,
+) -> Result<DataApiReturn, DataApiReturn> {
+    let admin = admin_ext.admin;
+    let name = body.name.trim().to_string();
+    if name.is_empty() || name.len() > MAX_PASSKEY_NAME_LEN {
+        return Err(DataResponse::error(
+            format!(
+                "passkey name must be between 1 and {} characters",
+                MAX_PASSKEY_NAME_LEN
+            ),
+            Some(StatusCode::BAD_REQUEST),
+        ));
+    }
+
+    let r = data.read().await;
+    let challenge = DBWebauthnChallenge::take(&r.db, body.challenge_id, REGISTER_CHALLENGE)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?
+        .filter(|c| c.admin_id == admin.id)
+        .ok_or(AuthError::PasskeyRejected.into_data_api_return())?;
+    let registration: PasskeyRegistration = serde_json::from_value(challenge.state.0)
+        .map_err(|err| AuthError::from(anyhow::Error::from(err)).into_data_api_return())?;
+
+    let webauthn =
+        passkey::webauthn(&r.env).map_err(|err| AuthError::from(err).into_data_api_return())?;
+    let passkey = webauthn
+        .finish_passkey_registration(&body.credential, &registration)
+        .map_err(|err| {
+            warn!(
+                "passkey registration for {} failed: {:?}",
+                admin.username, err
+            );
+            AuthError::PasskeyRejected.into_data_api_return()
+        })?;
+
+    let credential_id = passkey::credential_key(passkey.cred_id());
+    let taken = DBPasskey::exists_with_credential_id(&r.db, &credential_id)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    if taken {
+        return Err(DataResponse::error(
+            "that passkey is already registered",
+            Some(StatusCode::CONFLICT),
+        ));
+    }
+    DBPasskey::insert_one(
+        DBPasskeyParams {
+            admin_id: admin.id,
+            name,
+            credential_id,
+            passkey: sqlx::types::Json(passkey),
+        },
+        &r.db,
+    )
+    .await
+    .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    warn!("{} registered a passkey", admin.username);
+
+    Ok(DataResponse::success("succesfully registered passkey"))
+}
+
+#[tracing::instrument(name = "remove passkey", skip(data, admin_ext))]
+pub async fn remove_passkey_handler(
+    Path(id): Path<Uuid>,
+    State(data): State<SharedState>,
+    Extension(admin_ext): Extension<AdminExtension>,
+) -> Result<DataApiReturn, DataApiReturn> {
+    let removed = DBPasskey::delete_for_admin(&data.read().await.db, id, admin_ext.admin.id)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?
+        .ok_or(DataResponse::error(
+            "no passkey with that id",
+            Some(StatusCode::NOT_FOUND),
+        ))?;
+    Ok(DataResponse::success(format!(
+        "succesfully removed passkey {}",
+        removed.name
+    )))
+}
+
+#[tracing::instrument(name = "start passkey login", skip_all)]
+pub async fn login_start_handler(
+    State(data): State<SharedState>,
+    Json(body): Json<PasskeyLoginStartSchema>,
+) -> Result<Json<Value>, DataApiReturn> {
+    let r = data.read().await;
+    let admin = DBAdmin::get_by_username(&r.db, body.username.trim())
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?
+        .filter(|admin| !admin.disabled)
+        .ok_or(AuthError::PasskeyRejected.into_data_api_return())?;
+    let passkeys: Vec<_> = DBPasskey::get_for_admin(&r.db, admin.id)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?
+        .into_iter()
+        .map(|p| p.passkey.0)
+        .collect();
+    if passkeys.is_empty() {
+        return Err(AuthError::PasskeyRejected.into_data_api_return());
+    }
+
+    let webauthn =
+        passkey::webauthn(&r.env).map_err(|err| AuthError::from(err).into_data_api_return())?;
+    let (options, authentication) = webauthn
+        .start_passkey_authentication(&passkeys)
+        .map_err(|err| AuthError::from(anyhow::Error::from(err)).into_data_api_return())?;
+    let challenge_state = serde_json::to_value(&authentication)
+        .map_err(|err| AuthError::from(anyhow::Error::from(err)).into_data_api_return())?;
+    let challenge_id = save_challenge(&r, admin.id, LOGIN_CHALLENGE, challenge_state)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "challenge_id": challenge_id,
+        "options": options,
+    })))
+}
+
+/// Passkeys require user verification on the device, so a verified assertion
+/// logs the admin straight in without asking for a TOTP code
+#[tracing::instrument(name = "finish passkey login", skip_all)]
+pub async fn login_finish_handler(
+    State(data): State<SharedState>,
+    client: ClientInfo,
+    Json(body): Json<PasskeyLoginFinishSchema>,
+) -> Result<Response<String>, DataApiReturn> {
+    let r = data.read().await;
+    let challenge = DBWebauthnChallenge::take(&r.db, body.challenge_id, LOGIN_CHALLENGE)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?
+        .ok_or(AuthError::PasskeyRejected.into_data_api_return())?;
+    let authentication: PasskeyAuthentication = serde_json::from_value(challenge.state.0)
+        .map_err(|err| AuthError::from(anyhow::Error::from(err)).into_data_api_return())?;
+
+    let webauthn =
+        passkey::webauthn(&r.env).map_err(|err| AuthError::from(err).into_data_api_return())?;
+    let result = webauthn
+        .finish_passkey_authentication(&body.credential, &authentication)
+        .map_err(|err| {
+            warn!("passkey login failed: {:?}", err);
+            AuthError::PasskeyRejected.into_data_api_return()
+        })?;
+
+    let admin = DBAdmin::get_single_by(&r.db, challenge.admin_id)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?
+        .filter(|admin| !admin.disabled)
+        .ok_or(AuthError::PasskeyRejected.into_data_api_return())?;
+    let mut stored = DBPasskey::get_for_admin(&r.db, admin.id)
+        .await
+        .map_err(|err| AuthError::from(err).into_data_api_return())?
+        .into_iter()
+        .find(|p| p.passkey.cred_id() == result.cred_id())
+        .ok_or(AuthError::PasskeyRejected.into_data_api_return())?;
+    if stored.passkey.update_credential(&result).is_some() {
+        DBPasskey::record_use(&r.db, stored.id, stored.passkey.0)
+            .await
+            .map_err(|err| AuthError::from(err).into_data_api_return())?;
+    }
+    drop(r);
+
+    start_session(&data, &admin, client).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header, Request, StatusCode},
+        Router,
+    };
+    use serde_json::{json, Value};
+    use sqlx::postgres::PgPoolOptions;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+    use webauthn_authenticator_rs::{softpasskey::SoftPasskey, WebauthnAuthenticator};
+    use webauthn_rs::prelude::{CreationChallengeResponse, RequestChallengeResponse, Url};
+
+    use crate::{
+        auth::{csrf, password, permissions::Role},
+        database::{
+            handles::DbData,
+            models::{DBAdmin, DBAdminParams},
+        },
+        routes::create_router,
+        state::{AppState, Config},
+    };
+
+    const ORIGIN: &str = "http://localhost:3000";
+
+    /// The token cookie and CSRF token a login hands back
+    struct TestSession {
+        cookie: String,
+        csrf_token: String,
+    }
+
+    async fn send(
+        app: &Router,
+        uri: &str,
+        session: Option<&TestSession>,
+        content_type: &str,
+        body: String,
+    ) -> (StatusCode, Option<TestSession>, Value) {
+        let mut req = Request::post(uri).header(header::CONTENT_TYPE, content_type);
+        if let Some(session) = session {
+            req = req
+                .header(header::COOKIE, &session.cookie)
+                .header(csrf::HEADER_NAME, &session.csrf_token);
+        }
+        let res = app
+            .clone()
+            .oneshot(req.body(Body::from(body)).unwrap())
+            .await
+            .unwrap();
+        let status = res.status();
+        let cookie = res
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .find(|v| v.starts_with("token="))
+            .map(|v| v.split(';').next().unwrap().to_string());
+        let bytes = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+        let session = cookie.map(|cookie| TestSession {
+            cookie,
+            csrf_token: body["csrf_token"].as_str().unwrap_or_default().to_string(),
+        });
+        (status, session, body)
+    }
+
+    #[tokio::test]
+    async fn passkey_registration_and_login() {
+        dotenv::dotenv().ok();
+        let database_url = std::env::var("DB_URL").expect("DATABASE_URL must be set");
+        let pool = PgPoolOptions::new()
+            .max_connections(2)
+            .connect(&database_url)
+            .await
+            .unwrap();
+        let username = format!("passkey-test-{}", uuid::Uuid::new_v4());
+        let admin = DBAdmin::insert_one(
+            DBAdminParams {
+                username: username.clone(),
+                password_hash: password::hash_password("correct horse battery").unwrap(),
+                disabled: false,
+                role: Role::Editor,
+                email: None,
+            },
+            &pool,
+        )
+        .await
+        .unwrap();
+
+        let app = create_router(Arc::new(RwLock::new(AppState {
+            db: pool.clone(),
+            env: Config {
+                database_url,
+                jwt_secret: "passkey test secret".to_string(),
+                webauthn_origin: ORIGIN.to_string(),
+                oidc: None,
+                trash_retention: chrono::Duration::days(30),
+                trusted_proxies: vec![],
+            },
+        })));
+        let form = "application/x-www-form-urlencoded";
+        let json_type = "application/json";
+        let origin = Url::parse(ORIGIN).unwrap();
+        let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+
+        let (status, password_session, _) = send(
+            &app,
+            "/data/auth/login",
+            None,
+            form,
+            format!("username={}&password=correct+horse+battery", username),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let password_session = password_session.expect("password login sets a token");
+
+        // writes need the session's CSRF token as well as the cookie
+        let no_csrf = TestSession {
+            cookie: password_session.cookie.clone(),
+            csrf_token: String::new(),
+        };
+        let (status, _, _) = send(
+            &app,
+            "/data/auth/webauthn/register/start",
+            Some(&no_csrf),
+            json_type,
+            String::new(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, _, start) = send(
+            &app,
+            "/data/auth/webauthn/register/start",
+            Some(&password_session),
+            json_type,
+            String::new(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let options: CreationChallengeResponse =
+            serde_json::from_value(start["options"].clone()).unwrap();
+        let credential = authenticator
+            .do_registration(origin.clone(), options)
+            .unwrap();
+        let (status, _, _) = send(
+            &app,
+            "/data/auth/webauthn/register/finish",
+            Some(&password_session),
+            json_type,
+            json!({
+                "challenge_id": start["challenge_id"],
+                "name": "soft passkey",
+                "credential": credential,
+            })
+            .to_string(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _, start) = send(
+            &app,
+            "/data/auth/webauthn/login/start",
+            None,
+            json_type,
+            json!({ "username": username }).to_string(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let options: RequestChallengeResponse =
+            serde_json::from_value(start["options"].clone()).unwrap();
+        let assertion = authenticator.do_authentication(origin, options).unwrap();
+        let finish = json!({
+            "challenge_id": start["challenge_id"],
+            "credential": assertion,
+        })
+        .to_string();
+
+        let (status, passkey_session, body) = send(
+            &app,
+            "/data/auth/webauthn/login/finish",
+            None,
+            json_type,
+            finish.clone(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "success");
+        let passkey_session = passkey_session.expect("passkey login sets a token");
+        assert_ne!(passkey_session.cookie, password_session.cookie);
+
+        // the challenge is spent, so the same assertion can't be replayed
+        let (status, replayed, _) = send(
+            &app,
+            "/data/auth/webauthn/login/finish",
+            None,
+            json_type,
+            finish,
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(replayed.is_none());
+
+        // the passkey session works on authenticated routes
+        let (status, _, _) = send(
+            &app,
+            "/data/auth/logout",
+            Some(&passkey_session),
+            form,
+            String::new(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        admin.delete_one(&pool).await.unwrap();
+    }
+}
@@ -1,43 +1,53 @@
-use super::error::{AuthError, AuthResult};
+use super::{
+    api_token, csrf,
+    error::{AuthError, AuthResult},
+    permissions::{RequiredPermission, Scope},
+};
 use crate::{
     auth::model::TokenClaims,
+    database::{
+        handles::DbData,
+        models::{DBAdmin, DBApiToken, DBSession},
+    },
     error::{DataApiReturn, InternalError},
-    state::SharedState,
+    state::{AppState, SharedState},
 };
 use axum::{
     body::Body,
-    extract::State,
-    http::{header, Request},
+    extract::{Path, State},
+    http::{header, Method, Request},
     middleware::Next,
     response::IntoResponse,
+    Extension,
 };
 use axum_extra::extract::cookie::CookieJar;
 use jsonwebtoken::{decode, DecodingKey, Validation};
-use std::str::FromStr;
-use tracing::{debug, warn};
+use std::{collections::HashMap, str::FromStr};
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy)]
+pub struct AdminSession {
+    pub admin_id: uuid::Uuid,
+    pub session_id: uuid::Uuid,
+}
+
+fn bearer_token(req: &Request<Body>) -> Option<&str> {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|auth_header| auth_header.to_str().ok())
+        .and_then(|auth_value| auth_value.strip_prefix("Bearer "))
+}
 
 pub fn get_admin_session_id(
     jwt_secret: &[u8],
     cookie_jar: CookieJar,
     req: &Request<Body>,
-) -> AuthResult<uuid::Uuid> {
+) -> AuthResult<AdminSession> {
     let token = cookie_jar
         .get("token")
         .map(|cookie| cookie.value().to_string())
-        .or_else(|| {
-            req.headers()
-                .get(header::AUTHORIZATION)
-                .and_then(|auth_header| auth_header.to_str().ok())
-                .and_then(|auth_value| {
-                    if auth_value.starts_with("Bearer ") {
-                        Some(auth_value[7..].to_owned())
-                    } else {
-                        None
-                    }
-                })
-        })
+        .or_else(|| bearer_token(req).map(|token| token.to_owned()))
         .ok_or(AuthError::NotLoggedIn)?;
-    warn!("Token got from cookie jar: {:?}", token);
 
     let claims = decode::<TokenClaims>(
         &token,
@@ -46,35 +56,199 @@ pub fn get_admin_session_id(
     )?
     .claims;
 
-    let id = uuid::Uuid::from_str(&claims.sub)?;
-    Ok(id)
+    Ok(AdminSession {
+        admin_id: uuid::Uuid::from_str(&claims.sub)?,
+        session_id: uuid::Uuid::from_str(&claims.sid)?,
+    })
+}
+
+/// The admin behind a credential, as long as they still exist and aren't disabled
+async fn active_admin(state: &AppState, admin_id: uuid::Uuid) -> AuthResult<DBAdmin> {
+    match DBAdmin::get_single_by(&state.db, admin_id).await? {
+        Some(admin) if !admin.disabled => Ok(admin),
+        Some(_) => {
+            warn!("admin {} is disabled", admin_id);
+            Err(AuthError::NotLoggedIn)
+        }
+        None => {
+            warn!("no admin with id {}", admin_id);
+            Err(AuthError::NotLoggedIn)
+        }
+    }
+}
+
+/// Resolves the request's token to an admin account, rejecting tokens for
+/// revoked or expired sessions and for admins that have since been disabled or removed
+async fn authenticate(
+    state: &AppState,
+    session: AuthResult<AdminSession>,
+) -> AuthResult<(DBAdmin, DBSession)> {
+    let session = session?;
+    let db_session = DBSession::touch_valid(&state.db, session.session_id, session.admin_id)
+        .await?
+        .ok_or_else(|| {
+            warn!("session {} is not valid", session.session_id);
+            AuthError::NotLoggedIn
+        })?;
+    let admin = active_admin(state, session.admin_id).await?;
+    Ok((admin, db_session))
+}
+
+/// Like [`authenticate`] for an API token, which is looked up on every request
+/// so revoking one takes effect straight away
+async fn authenticate_api_token(
+    state: &AppState,
+    token: &str,
+) -> AuthResult<(DBAdmin, DBApiToken)> {
+    let api_token = DBApiToken::touch_valid(&state.db, &api_token::hash(token))
+        .await?
+        .ok_or_else(|| {
+            warn!("api token is unknown, revoked or expired");
+            AuthError::NotLoggedIn
+        })?;
+    let admin = active_admin(state, api_token.admin_id).await?;
+    Ok((admin, api_token))
+}
+
+/// How the admin proved who they are
+#[derive(Clone, Debug)]
+pub enum Credential {
+    Session(DBSession),
+    ApiToken(DBApiToken),
+}
+
+/// Inserted by [`admin_auth`] for handlers that need to know who is acting
+#[derive(Clone, Debug)]
+pub struct AdminExtension {
+    pub admin: DBAdmin,
+    pub credential: Credential,
+}
+
+impl AdminExtension {
+    /// The browser session behind the request, API tokens can't manage accounts
+    pub fn session(&self) -> AuthResult<&DBSession> {
+        match &self.credential {
+            Credential::Session(session) => Ok(session),
+            Credential::ApiToken(_) => Err(AuthError::SessionRequired),
+        }
+    }
 }
 
 pub async fn admin_auth(
     State(data): State<SharedState>,
     cookie_jar: CookieJar,
-    req: Request<Body>,
+    mut req: Request<Body>,
     next: Next,
 ) -> Result<impl IntoResponse, DataApiReturn> {
     let r = data.read().await;
-    let id = get_admin_session_id(r.env.jwt_secret.as_ref(), cookie_jar, &req)
+    let authenticated = match bearer_token(&req).filter(|t| t.starts_with(api_token::PREFIX)) {
+        Some(token) => authenticate_api_token(&r, token)
+            .await
+            .map(|(admin, token)| (admin, Credential::ApiToken(token))),
+        None => {
+            let session = get_admin_session_id(r.env.jwt_secret.as_ref(), cookie_jar, &req);
+            authenticate(&r, session)
+                .await
+                .map(|(admin, session)| (admin, Credential::Session(session)))
+        }
+    };
+    let (admin, credential) = authenticated.map_err(|err| err.into_data_api_return())?;
+    drop(r);
+    req.extensions_mut()
+        .insert(AdminExtension { admin, credential });
+    Ok(next.run(req).await)
+}
+
+/// Keeps API tokens off routes that manage the account itself, they are only
+/// for content routes gated by [`require_permission`]
+pub async fn session_only(
+    Extension(admin_ext): Extension<AdminExtension>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<impl IntoResponse, DataApiReturn> {
+    admin_ext
+        .session()
+        .map_err(|err| err.into_data_api_return())?;
+    Ok(next.run(req).await)
+}
+
+/// Runs inside [`admin_auth`], checking the acting admin's role against the
+/// [`RequiredPermission`] extension on the matched route. Requests made with an
+/// API token also need a scope covering the permission
+pub async fn require_permission(
+    Extension(required): Extension<RequiredPermission>,
+    Extension(admin_ext): Extension<AdminExtension>,
+    path_params: Option<Path<HashMap<String, String>>>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<impl IntoResponse, DataApiReturn> {
+    let item = path_params.as_ref().and_then(|Path(p)| p.get("item"));
+    let permission = required
+        .resolve(item.map(|i| i.as_str()))
         .map_err(|err| err.into_data_api_return())?;
-    if let Some(state_id) = r.admin_session_id {
-        if state_id != id {
-            warn!("session id from state does not match admin id");
-            return Err(AuthError::NotLoggedIn.into_data_api_return());
+
+    if !admin_ext.admin.role.allows(&permission) {
+        warn!(
+            "{} ({}) is missing permission {}",
+            admin_ext.admin.username, admin_ext.admin.role, permission
+        );
+        return Err(AuthError::Forbidden(permission).into_data_api_return());
+    }
+    if let Credential::ApiToken(token) = &admin_ext.credential {
+        let covered = token
+            .scopes
+            .iter()
+            .filter_map(|scope| Scope::try_from(scope.as_str()).ok())
+            .any(|scope| scope.covers(&permission));
+        if !covered {
+            warn!(
+                "api token {} of {} is not scoped for {}",
+                token.name, admin_ext.admin.username, permission
+            );
+            return Err(AuthError::Forbidden(permission).into_data_api_return());
         }
-    } else {
-        warn!("no session id from state");
-        return Err(AuthError::NotLoggedIn.into_data_api_return());
     }
-    warn!("admin is logged in");
+    Ok(next.run(req).await)
+}
+
+/// Runs inside [`admin_auth`], rejecting writes that don't echo back the
+/// session's CSRF token in the [`csrf::HEADER_NAME`] header. API tokens are
+/// sent explicitly rather than by the browser, so they skip the check
+pub async fn csrf_check(
+    Extension(admin_ext): Extension<AdminExtension>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<impl IntoResponse, DataApiReturn> {
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return Ok(next.run(req).await);
+    }
+    let Credential::Session(session) = &admin_ext.credential else {
+        return Ok(next.run(req).await);
+    };
+    let given = req
+        .headers()
+        .get(csrf::HEADER_NAME)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    if !csrf::tokens_match(&session.csrf_token, given) {
+        warn!(
+            "csrf token mismatch for {} on {} {}",
+            admin_ext.admin.username,
+            req.method(),
+            req.uri()
+        );
+        return Err(AuthError::CsrfMismatch.into_data_api_return());
+    }
     Ok(next.run(req).await)
 }
 
 #[derive(Clone, Debug)]
 pub struct SoftAuthExtension {
     pub is_logged_in: bool,
+    pub admin: Option<DBAdmin>,
+    pub session_id: Option<uuid::Uuid>,
+    /// for templates to put in the `hx-headers` of their admin forms
+    pub csrf_token: Option<String>,
 }
 
 pub async fn soft_auth(
@@ -84,20 +258,19 @@ pub async fn soft_auth(
     next: Next,
 ) -> Result<impl IntoResponse, DataApiReturn> {
     let r = data.read().await;
-    let is_logged_in = {
-        let mut ret = false;
-        if let Some(user_id) =
-            get_admin_session_id(r.env.jwt_secret.as_ref(), cookie_jar, &req).ok()
-        {
-            if let Some(session_id) = r.admin_session_id {
-                warn!("got session id from state");
-                ret = user_id == session_id;
-            }
-        }
-        ret
+    let session = get_admin_session_id(r.env.jwt_secret.as_ref(), cookie_jar, &req);
+    let authenticated = authenticate(&r, session).await.ok();
+    drop(r);
+    let (admin, session_id, csrf_token) = match authenticated {
+        Some((admin, session)) => (Some(admin), Some(session.id), Some(session.csrf_token)),
+        None => (None, None, None),
     };
-    req.extensions_mut()
-        .insert(SoftAuthExtension { is_logged_in });
+    req.extensions_mut().insert(SoftAuthExtension {
+        is_logged_in: admin.is_some(),
+        admin,
+        session_id,
+        csrf_token,
+    });
 
     Ok(next.run(req).await)
 }
@@ -1,15 +1,49 @@
-use std::sync::LazyLock;
+use sqlx::{Pool, Postgres};
+use tracing::{info, warn};
 
+use crate::database::{
+    handles::DbData,
+    models::{DBAdmin, DBAdminParams},
+};
+
+pub mod api_token;
+pub mod audit;
+pub mod client;
+pub mod csrf;
 pub mod error;
 pub mod handlers;
 pub mod middleware;
 pub mod model;
+pub mod oidc;
+pub mod passkey;
+pub mod password;
+pub mod permissions;
+pub mod throttle;
+pub mod totp;
 
-pub struct AdminCredentials {
-    password: String,
-}
+const DEFAULT_ADMIN_USERNAME: &str = "admin";
 
-pub static ADMIN_CREDENTIALS: LazyLock<AdminCredentials> = LazyLock::new(|| {
-    let password = std::env::var("ADMIN_PASSWORD").expect("Failed to get admin password");
-    AdminCredentials { password }
-});
+/// Creates the first admin account from `ADMIN_USERNAME`/`ADMIN_PASSWORD` when the
+/// admins table is empty, so a fresh deployment isn't locked out
+pub async fn bootstrap_admin(pool: &Pool<Postgres>) -> anyhow::Result<()> {
+    if !DBAdmin::get_all_ordered(pool).await?.is_empty() {
+        return Ok(());
+    }
+    let Ok(password) = std::env::var("ADMIN_PASSWORD") else {
+        warn!("no admins exist and ADMIN_PASSWORD is not set, nobody will be able to log in");
+        return Ok(());
+    };
+    let username =
+        std::env::var("ADMIN_USERNAME").unwrap_or_else(|_| DEFAULT_ADMIN_USERNAME.to_string());
+
+    let params = DBAdminParams {
+        username,
+        password_hash: password::hash_password(&password)?,
+        disabled: false,
+        role: permissions::Role::Owner,
+        email: None,
+    };
+    let admin = DBAdmin::insert_one(params, pool).await?;
+    info!("bootstrapped admin account: {}", admin.username);
+    Ok(())
+}
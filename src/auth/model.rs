@@ -1,13 +1,115 @@
 use serde::{Deserialize, Serialize};
+use webauthn_rs::prelude::{PublicKeyCredential, RegisterPublicKeyCredential};
+
+use super::permissions::Role;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenClaims {
+    /// id of the admin the token was issued to
+    pub sub: String,
+    /// id of the login session the token belongs to
+    pub sid: String,
+    pub iat: usize,
+    pub exp: usize,
+}
+
+/// Issued once the password checks out for an admin with two-factor enabled,
+/// only good for finishing the login with a second factor
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingLoginClaims {
     pub sub: String,
+    pub purpose: String,
+    pub iat: usize,
+    pub exp: usize,
+}
+
+/// Remembers an in-flight single sign-on attempt between leaving for the
+/// provider and coming back to the callback
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OidcLoginClaims {
+    pub state: String,
+    pub nonce: String,
+    pub verifier: String,
     pub iat: usize,
     pub exp: usize,
 }
 
 #[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize)]
 pub struct LoginAdminSchema {
+    pub username: String,
+    pub password: String,
+}
+
+/// Never print the password, even by accident
+impl std::fmt::Debug for LoginAdminSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoginAdminSchema")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateAdminSchema {
+    pub username: String,
     pub password: String,
+    pub role: Role,
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+impl std::fmt::Debug for CreateAdminSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CreateAdminSchema")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .field("role", &self.role)
+            .field("email", &self.email)
+            .finish()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangeRoleSchema {
+    pub role: Role,
+}
+
+/// Either a 6 digit authenticator code or one of the admin's recovery codes
+#[derive(Deserialize)]
+pub struct SecondFactorSchema {
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PasskeyLoginStartSchema {
+    pub username: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PasskeyLoginFinishSchema {
+    pub challenge_id: uuid::Uuid,
+    pub credential: PublicKeyCredential,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PasskeyRegisterFinishSchema {
+    pub challenge_id: uuid::Uuid,
+    pub name: String,
+    pub credential: RegisterPublicKeyCredential,
+}
+
+/// `scopes` is a comma or space separated list like `upload:patrol_log, delete:*`
+#[derive(Debug, Deserialize)]
+pub struct CreateApiTokenSchema {
+    pub name: String,
+    pub scopes: String,
+    pub expires_in_days: i64,
 }
@@ -0,0 +1,179 @@
+use anyhow::anyhow;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, DecodingKey, Validation};
+use rand::{distributions::Alphanumeric, rngs::OsRng, Rng};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::state::OidcConfig;
+
+const SCOPES: &str = "openid email profile";
+
+/// The parts of the provider's `/.well-known/openid-configuration` we use
+#[derive(Debug, Deserialize)]
+pub struct Discovery {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    pub nonce: Option<String>,
+}
+
+pub async fn discover(config: &OidcConfig) -> anyhow::Result<Discovery> {
+    let url = format!("{}/.well-known/openid-configuration", config.issuer);
+    let discovery: Discovery = reqwest::get(&url).await?.error_for_status()?.json().await?;
+    if discovery.issuer.trim_end_matches('/') != config.issuer {
+        return Err(anyhow!(
+            "discovery document is for issuer {}, expected {}",
+            discovery.issuer,
+            config.issuer
+        ));
+    }
+    Ok(discovery)
+}
+
+/// Random url safe string for the state, nonce and PKCE verifier
+pub fn random_token() -> String {
+    OsRng
+        .sample_iter(&Alphanumeric)
+        .take(64)
+        .map(char::from)
+        .collect()
+}
+
+/// S256 PKCE challenge for a verifier
+pub fn pkce_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+pub fn authorization_url(
+    config: &OidcConfig,
+    discovery: &Discovery,
+    state: &str,
+    nonce: &str,
+    verifier: &str,
+) -> anyhow::Result<String> {
+    let url = reqwest::Url::parse_with_params(
+        &discovery.authorization_endpoint,
+        &[
+            ("response_type", "code"),
+            ("client_id", config.client_id.as_str()),
+            ("redirect_uri", config.redirect_url.as_str()),
+            ("scope", SCOPES),
+            ("state", state),
+            ("nonce", nonce),
+            ("code_challenge", pkce_challenge(verifier).as_str()),
+            ("code_challenge_method", "S256"),
+        ],
+    )?;
+    Ok(url.to_string())
+}
+
+/// Trades the authorization code for an id token and verifies its signature
+/// against the provider's published keys, its issuer, audience and nonce
+pub async fn exchange_code(
+    config: &OidcConfig,
+    discovery: &Discovery,
+    code: &str,
+    verifier: &str,
+    nonce: &str,
+) -> anyhow::Result<IdTokenClaims> {
+    let client = reqwest::Client::new();
+    let token: TokenResponse = client
+        .post(&discovery.token_endpoint)
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", config.redirect_url.as_str()),
+            ("client_id", config.client_id.as_str()),
+            ("client_secret", config.client_secret.as_str()),
+            ("code_verifier", verifier),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let header = decode_header(&token.id_token)?;
+    let jwks: JwkSet = client
+        .get(&discovery.jwks_uri)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let jwk = match header.kid.as_deref() {
+        Some(kid) => jwks.find(kid),
+        None if jwks.keys.len() == 1 => jwks.keys.first(),
+        None => None,
+    }
+    .ok_or(anyhow!("no matching key for the id token"))?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_audience(&[&config.client_id]);
+    validation.set_issuer(&[&discovery.issuer]);
+    let claims =
+        decode::<IdTokenClaims>(&token.id_token, &DecodingKey::from_jwk(jwk)?, &validation)?.claims;
+
+    if claims.nonce.as_deref() != Some(nonce) {
+        return Err(anyhow!("id token nonce does not match"));
+    }
+    Ok(claims)
+}
+
+/// Whether a verified email may have an account made for it on first sign in
+pub fn is_allowed(config: &OidcConfig, email: &str) -> bool {
+    let email = email.to_lowercase();
+    if config.allowed_emails.contains(&email) {
+        return true;
+    }
+    match email.rsplit_once('@') {
+        Some((_, domain)) => config.allowed_domains.iter().any(|d| d == domain),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_allowed, pkce_challenge};
+    use crate::state::OidcConfig;
+
+    #[test]
+    fn pkce_challenge_matches_rfc_example() {
+        // RFC 7636 appendix B
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn allowed_by_domain_or_exact_email() {
+        let config = OidcConfig {
+            issuer: "https://accounts.example.com".to_string(),
+            client_id: "client".to_string(),
+            client_secret: "secret".to_string(),
+            redirect_url: "https://example.com/data/auth/oidc/callback".to_string(),
+            allowed_domains: vec!["semperflies.org".to_string()],
+            allowed_emails: vec!["friend@gmail.com".to_string()],
+        };
+        assert!(is_allowed(&config, "Chesty@SemperFlies.org"));
+        assert!(is_allowed(&config, "friend@gmail.com"));
+        assert!(!is_allowed(&config, "stranger@gmail.com"));
+        assert!(!is_allowed(&config, "chesty@semperflies.org.evil.com"));
+    }
+}
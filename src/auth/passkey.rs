@@ -0,0 +1,29 @@
+use anyhow::anyhow;
+use webauthn_rs::prelude::{CredentialID, Url, Webauthn, WebauthnBuilder};
+
+use crate::state::Config;
+
+const RP_NAME: &str = "Semper Flies";
+/// How long the browser has to answer a registration or login challenge
+pub const CHALLENGE_MINUTES: i64 = 5;
+pub const REGISTER_CHALLENGE: &str = "register";
+pub const LOGIN_CHALLENGE: &str = "login";
+
+/// Relying party for the configured origin, passkeys only work on that host
+pub fn webauthn(config: &Config) -> anyhow::Result<Webauthn> {
+    let origin = Url::parse(&config.webauthn_origin)?;
+    let rp_id = origin
+        .host_str()
+        .ok_or(anyhow!("webauthn origin has no host: {}", origin))?
+        .to_string();
+    let webauthn = WebauthnBuilder::new(&rp_id, &origin)?
+        .rp_name(RP_NAME)
+        .build()?;
+    Ok(webauthn)
+}
+
+/// Hex form of a credential id, used to keep one credential from being
+/// registered to two accounts
+pub fn credential_key(id: &CredentialID) -> String {
+    id.iter().map(|b| format!("{:02x}", b)).collect()
+}
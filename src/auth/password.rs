@@ -0,0 +1,46 @@
+use anyhow::anyhow;
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use std::sync::LazyLock;
+
+/// Verified against when no admin matches the submitted username, so a miss
+/// costs the same as a wrong password
+static DUMMY_HASH: LazyLock<String> =
+    LazyLock::new(|| hash_password("not a real password").expect("failed to hash dummy password"));
+
+pub fn hash_password(password: &str) -> anyhow::Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|err| anyhow!("failed to hash password: {:?}", err))?;
+    Ok(hash.to_string())
+}
+
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    match PasswordHash::new(hash) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+pub fn burn_verification(password: &str) {
+    let _ = verify_password(password, &DUMMY_HASH);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash_password, verify_password};
+
+    #[test]
+    fn hashed_password_verifies() {
+        let hash = hash_password("semper fi").unwrap();
+        assert_ne!(hash, "semper fi");
+        assert!(verify_password("semper fi", &hash));
+        assert!(!verify_password("semper fly", &hash));
+        assert!(!verify_password("semper fi", "not a hash"));
+    }
+}
@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use super::{
+    error::{AuthError, AuthResult},
+    handlers::upload::{GeneralItem, UploadFormItemType, UploadMultipartItemType},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "admin_role", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Everything, including managing other admins
+    Owner,
+    /// Upload, edit and delete any content
+    Editor,
+    /// Edit and delete any content, upload debriefs
+    Moderator,
+    /// Upload patrol logs and debriefs
+    Volunteer,
+}
+
+impl Role {
+    pub fn all() -> Vec<Self> {
+        vec![Self::Owner, Self::Editor, Self::Moderator, Self::Volunteer]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Owner => "owner",
+            Self::Editor => "editor",
+            Self::Moderator => "moderator",
+            Self::Volunteer => "volunteer",
+        }
+    }
+
+    pub fn allows(&self, permission: &Permission) -> bool {
+        use GeneralItem::*;
+        match (self, permission) {
+            (Self::Owner, _) => true,
+            (_, Permission::ManageAdmins) => false,
+            (Self::Editor, Permission::Content(_, _)) => true,
+            (Self::Moderator, Permission::Content(Action::Upload, item)) => {
+                matches!(item, Form(UploadFormItemType::Debriefs))
+            }
+            (Self::Moderator, Permission::Content(_, _)) => true,
+            (Self::Volunteer, Permission::Content(Action::Upload, item)) => matches!(
+                item,
+                Form(UploadFormItemType::Debriefs) | Multi(UploadMultipartItemType::PatrolLog)
+            ),
+            (Self::Volunteer, Permission::Content(_, _)) => false,
+        }
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Upload,
+    Edit,
+    Delete,
+}
+
+impl Action {
+    pub fn all() -> Vec<Self> {
+        vec![Self::Upload, Self::Edit, Self::Delete]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Upload => "upload",
+            Self::Edit => "edit",
+            Self::Delete => "delete",
+        }
+    }
+}
+
+impl TryFrom<&str> for Action {
+    type Error = anyhow::Error;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::all()
+            .into_iter()
+            .find(|a| a.as_str() == value)
+            .ok_or(anyhow::anyhow!("{} is not an action", value))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    Content(Action, GeneralItem),
+    ManageAdmins,
+}
+
+impl Display for Permission {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Content(action, item) => write!(f, "{}:{}", action.as_str(), item.as_str()),
+            Self::ManageAdmins => write!(f, "manage:admins"),
+        }
+    }
+}
+
+/// What an API token may do, written `action:item` like `upload:patrol_log`,
+/// or `action:*` for every item. Tokens only ever carry content permissions,
+/// and a scope never grants more than the owning admin's role allows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scope {
+    pub action: Action,
+    /// `None` covers every item
+    pub item: Option<GeneralItem>,
+}
+
+impl Scope {
+    pub fn covers(&self, permission: &Permission) -> bool {
+        match permission {
+            Permission::Content(action, item) => {
+                self.action == *action && self.item.is_none_or(|i| i == *item)
+            }
+            Permission::ManageAdmins => false,
+        }
+    }
+}
+
+impl TryFrom<&str> for Scope {
+    type Error = anyhow::Error;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let (action, item) = value
+            .trim()
+            .split_once(':')
+            .ok_or(anyhow::anyhow!("{} is not in the form action:item", value))?;
+        let item = match item {
+            "*" => None,
+            item => Some(GeneralItem::try_from(item)?),
+        };
+        Ok(Self {
+            action: Action::try_from(action)?,
+            item,
+        })
+    }
+}
+
+impl Display for Scope {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self.item {
+            Some(item) => write!(f, "{}:{}", self.action.as_str(), item.as_str()),
+            None => write!(f, "{}:*", self.action.as_str()),
+        }
+    }
+}
+
+/// Attached to routes as an extension so [`super::middleware::require_permission`]
+/// knows what to check. Content permissions are resolved against the route's
+/// `:item` path parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequiredPermission {
+    Content(Action),
+    ManageAdmins,
+}
+
+impl RequiredPermission {
+    pub fn resolve(&self, item: Option<&str>) -> AuthResult<Permission> {
+        match self {
+            Self::ManageAdmins => Ok(Permission::ManageAdmins),
+            Self::Content(action) => {
+                let item = item.ok_or(AuthError::Undefined(anyhow::anyhow!(
+                    "route requires a content permission but has no item"
+                )))?;
+                let item = GeneralItem::try_from(item).map_err(|_| AuthError::UnknownItem)?;
+                Ok(Permission::Content(*action, item))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, Permission, RequiredPermission, Role, Scope};
+    use crate::auth::handlers::upload::GeneralItem;
+
+    fn content(action: Action, item: &str) -> Permission {
+        RequiredPermission::Content(action)
+            .resolve(Some(item))
+            .unwrap()
+    }
+
+    #[test]
+    fn volunteers_can_post_logs_but_not_delete_dedications() {
+        assert!(Role::Volunteer.allows(&content(Action::Upload, "patrol_log")));
+        assert!(!Role::Volunteer.allows(&content(Action::Delete, "dedications")));
+        assert!(!Role::Volunteer.allows(&content(Action::Edit, "patrol_log")));
+        assert!(!Role::Volunteer.allows(&content(Action::Upload, "dedications")));
+        assert!(!Role::Volunteer.allows(&Permission::ManageAdmins));
+    }
+
+    #[test]
+    fn only_owners_manage_admins() {
+        for role in Role::all() {
+            assert_eq!(
+                role.allows(&Permission::ManageAdmins),
+                role == Role::Owner,
+                "{role}"
+            );
+        }
+        for item in ["debriefs", "patrol_log", "dedications", "support"] {
+            let item = GeneralItem::try_from(item).unwrap();
+            assert!(Role::Editor.allows(&Permission::Content(Action::Delete, item)));
+            assert!(Role::Moderator.allows(&Permission::Content(Action::Delete, item)));
+            assert!(Role::Moderator.allows(&Permission::Content(Action::Edit, item)));
+        }
+    }
+
+    #[test]
+    fn unknown_items_are_rejected() {
+        assert!(RequiredPermission::Content(Action::Upload)
+            .resolve(Some("not_an_item"))
+            .is_err());
+        assert_eq!(
+            content(Action::Delete, "support").to_string(),
+            "delete:support"
+        );
+    }
+
+    #[test]
+    fn scopes_cover_matching_content_only() {
+        let wildcard = Scope::try_from("delete:*").unwrap();
+        assert!(wildcard.covers(&content(Action::Delete, "dedications")));
+        assert!(!wildcard.covers(&content(Action::Upload, "dedications")));
+        assert!(!wildcard.covers(&Permission::ManageAdmins));
+
+        let single = Scope::try_from("upload:patrol_log").unwrap();
+        assert!(single.covers(&content(Action::Upload, "patrol_log")));
+        assert!(!single.covers(&content(Action::Upload, "support")));
+        assert_eq!(single.to_string(), "upload:patrol_log");
+
+        assert!(Scope::try_from("upload").is_err());
+        assert!(Scope::try_from("manage:admins").is_err());
+        assert!(Scope::try_from("upload:not_an_item").is_err());
+    }
+}
@@ -0,0 +1,205 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Pool, Postgres, Transaction};
+use tracing::warn;
+use uuid::Uuid;
+
+use super::{
+    client::ClientInfo,
+    error::{AuthError, AuthResult},
+};
+use crate::database::{
+    handles::DbData,
+    models::{AttemptKey, DBLoginAttempt, DBLoginAttemptParams},
+};
+
+/// How failures against one key are turned into a wait before the next attempt
+#[derive(Debug, Clone, Copy)]
+pub struct Policy {
+    /// failures allowed before any wait is imposed
+    pub free_attempts: i64,
+    /// the wait doubles with each failure past `free_attempts`, up to this
+    pub max_wait_seconds: i64,
+}
+
+/// An account is locked for the full 15 minutes after 15 straight failures
+pub const ACCOUNT_POLICY: Policy = Policy {
+    free_attempts: 5,
+    max_wait_seconds: 15 * 60,
+};
+
+/// Looser than the account policy since several admins may share an office ip
+pub const IP_POLICY: Policy = Policy {
+    free_attempts: 20,
+    max_wait_seconds: 15 * 60,
+};
+
+/// Failures older than this no longer count towards a lockout
+const WINDOW_SECONDS: i64 = 24 * 60 * 60;
+/// How long attempts are kept around for review
+const RETENTION_DAYS: i64 = 90;
+
+impl Policy {
+    /// Seconds left before another attempt is allowed, if any
+    pub fn retry_after(
+        &self,
+        failures: i64,
+        last_failure: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Option<i64> {
+        let over = failures - self.free_attempts;
+        if over < 0 {
+            return None;
+        }
+        let wait = 2_i64
+            .checked_pow(over as u32)
+            .unwrap_or(i64::MAX)
+            .min(self.max_wait_seconds);
+        // round up so a partial second left still counts as a wait
+        let remaining = (last_failure + Duration::seconds(wait) - now).num_milliseconds();
+        (remaining > 0).then_some((remaining + 999) / 1000)
+    }
+}
+
+async fn retry_after(
+    tx: &mut Transaction<'_, Postgres>,
+    key: AttemptKey,
+    value: &str,
+    policy: Policy,
+) -> AuthResult<Option<i64>> {
+    // attempts against the same key queue up here, so each one counts the
+    // attempts let through before it
+    DBLoginAttempt::lock(&mut *tx, key, value).await?;
+    let (failures, last_failure) =
+        DBLoginAttempt::recent_failures(&mut *tx, key, value, WINDOW_SECONDS).await?;
+    Ok(last_failure.and_then(|last| policy.retry_after(failures, last, Utc::now())))
+}
+
+/// An attempt let through by [`check`]. It counts as a failure until
+/// [`record`] says otherwise, so a burst of parallel attempts can't all get in
+/// before the first of them fails
+#[derive(Debug)]
+#[must_use]
+pub struct Attempt {
+    id: Uuid,
+}
+
+/// Refuses the attempt if the account or the client's ip is backing off,
+/// otherwise counts it against both straight away
+pub async fn check(
+    pool: &Pool<Postgres>,
+    username: &str,
+    client: &ClientInfo,
+) -> AuthResult<Attempt> {
+    let mut tx = pool.begin().await.map_err(anyhow::Error::from)?;
+    let mut wait = retry_after(&mut tx, AttemptKey::Username, username, ACCOUNT_POLICY).await?;
+    if let Some(ip) = client.ip.as_deref() {
+        let ip_wait = retry_after(&mut tx, AttemptKey::Ip, ip, IP_POLICY).await?;
+        wait = wait.max(ip_wait);
+    }
+    if let Some(seconds) = wait {
+        warn!(
+            "refusing login for {} from {:?}, locked for {}s",
+            username, client.ip, seconds
+        );
+        return Err(AuthError::TooManyAttempts(seconds));
+    }
+
+    let params = DBLoginAttemptParams {
+        username: username.to_string(),
+        ip: client.ip.clone(),
+        user_agent: client.user_agent.clone(),
+        succeeded: false,
+    };
+    let attempt = DBLoginAttempt::insert_one(params, &mut tx).await?;
+    tx.commit().await.map_err(anyhow::Error::from)?;
+    Ok(Attempt { id: attempt.id })
+}
+
+/// Records the outcome of an attempt. A success resets the backoff for both
+/// the account and the ip
+pub async fn record(pool: &Pool<Postgres>, attempt: Attempt, succeeded: bool) {
+    if let Err(err) = DBLoginAttempt::finish(pool, attempt.id, succeeded).await {
+        warn!("failed to record login attempt: {:?}", err);
+    }
+    if succeeded {
+        let cutoff = Utc::now() - Duration::days(RETENTION_DAYS);
+        if let Err(err) = DBLoginAttempt::delete_before(pool, cutoff).await {
+            warn!("failed to clean up old login attempts: {:?}", err);
+        }
+    }
+}
+
+/// Drops an attempt that is neither a success nor a failure yet, like a
+/// password that still needs its second factor
+pub async fn forget(pool: &Pool<Postgres>, attempt: Attempt) {
+    if let Err(err) = DBLoginAttempt::delete_one_with_id(attempt.id, pool).await {
+        warn!("failed to forget login attempt: {:?}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, Policy, ACCOUNT_POLICY};
+    use crate::auth::client::ClientInfo;
+    use chrono::{Duration, Utc};
+    use sqlx::postgres::PgPoolOptions;
+
+    #[test]
+    fn backoff_doubles_then_caps() {
+        let now = Utc::now();
+        let policy = ACCOUNT_POLICY;
+        assert_eq!(policy.retry_after(4, now, now), None);
+        assert_eq!(policy.retry_after(5, now, now), Some(1));
+        assert_eq!(policy.retry_after(8, now, now), Some(8));
+        assert_eq!(policy.retry_after(15, now, now), Some(15 * 60));
+        assert_eq!(policy.retry_after(500, now, now), Some(15 * 60));
+    }
+
+    #[test]
+    fn backoff_expires() {
+        let policy = Policy {
+            free_attempts: 0,
+            max_wait_seconds: 60,
+        };
+        let last = Utc::now();
+        assert_eq!(
+            policy.retry_after(3, last, last + Duration::seconds(5)),
+            Some(3)
+        );
+        assert_eq!(
+            policy.retry_after(3, last, last + Duration::seconds(8)),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn parallel_attempts_share_the_free_ones() {
+        dotenv::dotenv().ok();
+        let database_url = std::env::var("DB_URL").expect("DATABASE_URL must be set");
+        let pool = PgPoolOptions::new()
+            .max_connections(10)
+            .connect(&database_url)
+            .await
+            .unwrap();
+        let username = format!("throttle-test-{}", uuid::Uuid::new_v4());
+
+        let mut attempts = tokio::task::JoinSet::new();
+        for _ in 0..20 {
+            let (pool, username) = (pool.clone(), username.clone());
+            attempts.spawn(async move { check(&pool, &username, &ClientInfo::default()).await });
+        }
+        let mut let_through = 0;
+        while let Some(attempt) = attempts.join_next().await {
+            if attempt.unwrap().is_ok() {
+                let_through += 1;
+            }
+        }
+        assert_eq!(let_through, ACCOUNT_POLICY.free_attempts);
+
+        sqlx::query("DELETE FROM login_attempts WHERE username = $1;")
+            .bind(&username)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::anyhow;
+use rand::{distributions::Uniform, rngs::OsRng, Rng};
+use sha2::{Digest, Sha256};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const ISSUER: &str = "Semper Flies";
+const DIGITS: usize = 6;
+const STEP_SECONDS: u64 = 30;
+/// How many steps either side of now a code is still accepted, to allow for clock drift
+const SKEW_STEPS: i64 = 1;
+pub const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+/// Base32 encoded secret for a new enrollment
+pub fn generate_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+fn build(secret: &str, username: &str) -> anyhow::Result<TOTP> {
+    let bytes = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|err| anyhow!("invalid totp secret: {:?}", err))?;
+    // the provisioning uri uses ':' to separate the issuer from the account
+    let account = username.replace(':', "_");
+    TOTP::new(
+        Algorithm::SHA1,
+        DIGITS,
+        0,
+        STEP_SECONDS,
+        bytes,
+        Some(ISSUER.to_string()),
+        account,
+    )
+    .map_err(|err| anyhow!("failed to build totp: {:?}", err))
+}
+
+/// The `otpauth://` uri and a base64 png QR code of it, for authenticator apps
+pub fn provisioning(secret: &str, username: &str) -> anyhow::Result<(String, String)> {
+    let totp = build(secret, username)?;
+    let qr = totp
+        .get_qr_base64()
+        .map_err(|err| anyhow!("failed to render qr code: {}", err))?;
+    Ok((totp.get_url(), qr))
+}
+
+/// Returns the time step the code was generated for, if it is valid at `now`
+pub fn matching_step(
+    secret: &str,
+    username: &str,
+    code: &str,
+    now: u64,
+) -> anyhow::Result<Option<i64>> {
+    let code = code.trim().replace(' ', "");
+    if code.len() != DIGITS || !code.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(None);
+    }
+    let totp = build(secret, username)?;
+    let current = (now / STEP_SECONDS) as i64;
+    let matched = (current - SKEW_STEPS..=current + SKEW_STEPS)
+        .filter(|step| *step >= 0)
+        .find(|step| totp.generate(*step as u64 * STEP_SECONDS) == code);
+    Ok(matched)
+}
+
+pub fn generate_recovery_codes() -> Vec<String> {
+    let dist = Uniform::from(0..RECOVERY_CODE_ALPHABET.len());
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let chars: String = OsRng
+                .sample_iter(dist)
+                .take(10)
+                .map(|i| RECOVERY_CODE_ALPHABET[i] as char)
+                .collect();
+            format!("{}-{}", &chars[..5], &chars[5..])
+        })
+        .collect()
+}
+
+/// Recovery codes are random enough that a plain digest is safe to store, and
+/// lets us look a submitted code up directly
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build, generate_secret, hash_recovery_code, matching_step, STEP_SECONDS};
+
+    #[test]
+    fn codes_match_within_skew_only() {
+        let secret = generate_secret();
+        let totp = build(&secret, "admin").unwrap();
+        let now = 1_700_000_000;
+        let step = (now / STEP_SECONDS) as i64;
+
+        let code = totp.generate(now);
+        assert_eq!(
+            matching_step(&secret, "admin", &code, now).unwrap(),
+            Some(step)
+        );
+        let previous = totp.generate(now - STEP_SECONDS);
+        assert_eq!(
+            matching_step(&secret, "admin", &previous, now).unwrap(),
+            Some(step - 1)
+        );
+        let stale = totp.generate(now - 5 * STEP_SECONDS);
+        assert_eq!(matching_step(&secret, "admin", &stale, now).unwrap(), None);
+        assert_eq!(
+            matching_step(&secret, "admin", "abc123", now).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn recovery_code_hash_ignores_formatting() {
+        assert_eq!(
+            hash_recovery_code("abcde-fghjk"),
+            hash_recovery_code(" ABCDE FGHJK ")
+        );
+        assert_ne!(
+            hash_recovery_code("abcde-fghjk"),
+            hash_recovery_code("abcde-fghjm")
+        );
+    }
+}
@@ -1 +1,2 @@
 pub mod carousel;
+pub mod tags;
@@ -0,0 +1,28 @@
+use askama::Template;
+
+use crate::database::models::DBTag;
+
+/// Chips narrowing a list page down to one tag. Each one swaps `target` for
+/// the page at `url` with its tag added, the active one takes it off again
+#[derive(Template, Debug)]
+#[template(path = "components/tag_filter.html")]
+pub struct TagFilterTemplate {
+    pub tags: Vec<DBTag>,
+    pub active: Option<String>,
+    /// the page with the other filters it's showing, ends in `?` or `&`
+    pub url: String,
+    pub target: &'static str,
+}
+
+impl TagFilterTemplate {
+    fn is_active(&self, slug: &str) -> bool {
+        self.active.as_deref() == Some(slug)
+    }
+
+    fn link(&self, slug: &str) -> String {
+        match self.is_active(slug) {
+            true => self.url.trim_end_matches(['?', '&']).to_string(),
+            false => format!("{}tag={}", self.url, slug),
+        }
+    }
+}
@@ -2,20 +2,55 @@ use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 use sqlx::{
     database::HasArguments,
+    pool::PoolConnection,
     postgres::PgRow,
     query::{Query, QueryAs},
-    Arguments, FromRow, Pool, Postgres, Row,
+    types::Json,
+    Arguments, FromRow, PgConnection, Pool, Postgres, Row, Transaction,
 };
+use std::{future::Future, ops::DerefMut};
 use tracing::warn;
 use uuid::Uuid;
 
-use crate::database::models::DBImage;
+use crate::database::models::{DBAdmin, DBImage, DBRevision, DBRevisionParams, DBTag};
+
+use super::query::{ListQuery, Page};
 
 use super::models::DBImageParams;
 
-pub(super) type QueryType<'q, O> =
+pub(crate) type QueryType<'q, O> =
     QueryAs<'q, Postgres, O, <Postgres as HasArguments<'q>>::Arguments>;
 
+/// What the [`DbData`] methods run on: the pool, or a connection or transaction
+/// whose writes have to land together. Unlike `sqlx::Acquire` this has no
+/// lifetime parameter, which keeps handler futures provably `Send` when they
+/// pass a borrowed transaction
+pub trait DbConn: Send {
+    type Conn: DerefMut<Target = PgConnection> + Send;
+    fn conn(self) -> impl Future<Output = sqlx::Result<Self::Conn>> + Send;
+}
+
+impl DbConn for &Pool<Postgres> {
+    type Conn = PoolConnection<Postgres>;
+    fn conn(self) -> impl Future<Output = sqlx::Result<Self::Conn>> + Send {
+        self.acquire()
+    }
+}
+
+impl<'c> DbConn for &'c mut PgConnection {
+    type Conn = &'c mut PgConnection;
+    fn conn(self) -> impl Future<Output = sqlx::Result<Self::Conn>> + Send {
+        std::future::ready(Ok(self))
+    }
+}
+
+impl<'c> DbConn for &'c mut Transaction<'_, Postgres> {
+    type Conn = &'c mut PgConnection;
+    fn conn(self) -> impl Future<Output = sqlx::Result<Self::Conn>> + Send {
+        std::future::ready(Ok(&mut **self))
+    }
+}
+
 pub trait DbData<P>:
     std::fmt::Debug + Serialize + for<'de> Deserialize<'de> + Send + Unpin + for<'r> FromRow<'r, PgRow>
 where
@@ -27,34 +62,95 @@ where
     fn take_images(_params: &mut P) -> Option<Vec<DBImageParams>> {
         None
     }
+    /// Names of the tags to put on the row, for tables that have them
+    fn take_tags(_params: &mut P) -> Option<Vec<String>> {
+        None
+    }
     fn bind_tables(params: P, query: QueryType<Self>) -> QueryType<Self>;
+    /// Runs before the row is inserted or updated, on the same connection, for
+    /// params that only turn into columns once other rows exist
+    async fn prepare(_params: &mut P, _conn: &mut PgConnection) -> anyhow::Result<()> {
+        Ok(())
+    }
+    /// Whether the table has a `deleted_at` column. Rows with it set are in
+    /// the trash and left out of every read unless asked for
+    fn soft_deletes() -> bool {
+        false
+    }
+
+    /// Condition leaving out rows in the trash, for tables that have one
+    fn not_trashed() -> &'static str {
+        match Self::soft_deletes() {
+            true => "deleted_at IS NULL",
+            false => "TRUE",
+        }
+    }
 
-    async fn get_single_by(pool: &Pool<Postgres>, id: Uuid) -> anyhow::Result<Option<Self>> {
-        let query = format!("SELECT * FROM {} WHERE id = $1;", Self::table_name());
+    async fn get_single_by(db: impl DbConn, id: Uuid) -> anyhow::Result<Option<Self>> {
+        let mut conn = db.conn().await?;
+        let query = format!(
+            "SELECT * FROM {} WHERE id = $1 AND {};",
+            Self::table_name(),
+            Self::not_trashed()
+        );
         let strct = sqlx::query_as::<_, Self>(&query)
             .bind(id)
-            .fetch_optional(pool)
+            .fetch_optional(&mut *conn)
             .await?;
         Ok(strct)
     }
 
-    async fn get_multiple(pool: &Pool<Postgres>) -> anyhow::Result<Vec<Self>> {
-        let query = format!("SELECT * FROM {};", Self::table_name());
-        let all = sqlx::query_as::<_, Self>(&query).fetch_all(pool).await?;
+    async fn get_multiple(db: impl DbConn) -> anyhow::Result<Vec<Self>> {
+        let mut conn = db.conn().await?;
+        let query = format!(
+            "SELECT * FROM {} WHERE {};",
+            Self::table_name(),
+            Self::not_trashed()
+        );
+        let all = sqlx::query_as::<_, Self>(&query)
+            .fetch_all(&mut *conn)
+            .await?;
         warn!("got all: {:?}", all);
         Ok(all)
     }
 
-    async fn delete_one(&self, pool: &Pool<Postgres>) -> anyhow::Result<Option<Self>> {
+    /// The rows `list` picks, in its order
+    async fn get_page(list: &ListQuery<Self>, db: impl DbConn) -> anyhow::Result<Page<Self>> {
+        let mut conn = db.conn().await?;
+        let sql = list.sql(1, &Self::table_name(), Self::soft_deletes());
+        let query = format!(
+            "SELECT d.*, {} FROM {} d {} {} {};",
+            sql.sort_value,
+            Self::table_name(),
+            sql.where_clause,
+            sql.order_by,
+            sql.limit
+        );
+        let mut q = sqlx::query(&query);
+        for value in sql.binds {
+            q = q.bind(value);
+        }
+        let mut rows = vec![];
+        for row in q.fetch_all(&mut *conn).await? {
+            let item = Self::from_row(&row)?;
+            let id = item.id();
+            rows.push((item, row.try_get("sort_value")?, id));
+        }
+        Ok(list.page(rows))
+    }
+
+    async fn delete_one(&self, db: impl DbConn) -> anyhow::Result<Option<Self>> {
+        let mut conn = db.conn().await?;
         let query = format!("DELETE FROM {} WHERE id = $1;", Self::table_name());
         let strct = sqlx::query_as::<_, Self>(&query)
             .bind(self.id())
-            .fetch_optional(pool)
+            .fetch_optional(&mut *conn)
             .await?;
         Ok(strct)
     }
 
-    async fn delete_one_with_id(id: Uuid, pool: &Pool<Postgres>) -> anyhow::Result<Self> {
+    async fn delete_one_with_id(id: Uuid, db: impl DbConn) -> anyhow::Result<Self> {
+        let mut conn = db.conn().await?;
         warn!("deleting item with id: {}", id);
         let query = format!(
             "DELETE FROM {} WHERE id = $1 RETURNING *;",
@@ -62,7 +158,7 @@ where
         );
         match sqlx::query_as::<_, Self>(&query)
             .bind(id)
-            .fetch_optional(pool)
+            .fetch_optional(&mut *conn)
             .await?
         {
             Some(ret) => Ok(ret),
@@ -75,10 +171,8 @@ where
         }
     }
 
-    async fn delete_many_by_ids(
-        ids: Vec<Uuid>,
-        pool: &Pool<Postgres>,
-    ) -> anyhow::Result<Vec<Self>> {
+    async fn delete_many_by_ids(ids: Vec<Uuid>, db: impl DbConn) -> anyhow::Result<Vec<Self>> {
+        let mut conn = db.conn().await?;
         let query = format!(
             "DELETE FROM {} WHERE id = ANY($1) RETURNING *;",
             Self::table_name()
@@ -86,67 +180,489 @@ where
 
         let deleted_rows = sqlx::query_as::<_, Self>(&query)
             .bind(&ids)
-            .fetch_all(pool)
+            .fetch_all(&mut *conn)
             .await?;
 
         Ok(deleted_rows)
     }
 
-    async fn delete_many(pool: &Pool<Postgres>) -> anyhow::Result<Vec<Self>> {
+    async fn delete_many(db: impl DbConn) -> anyhow::Result<Vec<Self>> {
+        let mut conn = db.conn().await?;
         let query = format!("DELETE FROM {};", Self::table_name());
-        let strct = sqlx::query_as::<_, Self>(&query).fetch_all(pool).await?;
+        let strct = sqlx::query_as::<_, Self>(&query)
+            .fetch_all(&mut *conn)
+            .await?;
         Ok(strct)
     }
 
-    async fn insert_one(params: P, pool: &Pool<Postgres>) -> anyhow::Result<Self> {
-        if Self::fields().contains(&"img_ids".to_string()) {
-            warn!("make sure you've inserted images before inserting this'");
+    /// Inserts the row and the images in `params`, attached in order, and tags
+    /// it
+    async fn insert_one(mut params: P, db: impl DbConn) -> anyhow::Result<Self> {
+        let mut conn = db.conn().await?;
+        Self::prepare(&mut params, &mut conn).await?;
+        let images = Self::take_images(&mut params);
+        let tags = Self::take_tags(&mut params);
+        let query = DBImage::insert_query::<Self, P>();
+        let q = sqlx::query_as::<_, Self>(&query);
+        let ret = Self::bind_tables(params, q).fetch_one(&mut *conn).await?;
+        if let Some(images) = images {
+            let img_ids = DBImage::insert_all(images, &mut conn).await?;
+            Self::attach_images(ret.id(), &img_ids, &mut *conn).await?;
+        }
+        if let Some(tags) = tags {
+            Self::set_tags(ret.id(), &tags, &mut *conn).await?;
         }
-        let binds = Self::fields()
+        Ok(ret)
+    }
+
+    /// Overwrites every field of the row with `params`. Images in `params` are
+    /// inserted and attached after the ones the row already has, tags replace
+    /// the row's tags. Returns `None`
+    /// when no row has the id, or it's in the trash
+    async fn update_one(mut params: P, db: impl DbConn, id: Uuid) -> anyhow::Result<Option<Self>> {
+        let mut conn = db.conn().await?;
+        Self::prepare(&mut params, &mut conn).await?;
+        let images = Self::take_images(&mut params);
+        let tags = Self::take_tags(&mut params);
+        let assignments: Vec<String> = Self::fields()
             .iter()
             .enumerate()
-            .fold(String::new(), |mut acc, (i, _)| {
-                let mut str_to_push = format!("${}", i + 1);
-                if i + 1 != Self::fields().len() {
-                    str_to_push.push_str(",");
-                }
-                acc.push_str(&str_to_push);
-                acc
-            });
+            .map(|(i, field)| format!("{} = ${}", field, i + 1))
+            .collect();
         let query = format!(
-            "INSERT INTO {} ({}) VALUES ({}) RETURNING *;",
+            "UPDATE {} SET {} WHERE id = ${} AND {} RETURNING *;",
             Self::table_name(),
-            Self::fields().join(","),
-            binds
+            assignments.join(","),
+            assignments.len() + 1,
+            Self::not_trashed()
         );
-        println!("running query: {}", query);
-        let q = sqlx::query_as::<_, Self>(&query);
-        let ret = Self::bind_tables(params, q).fetch_one(pool).await?;
-        Ok(ret)
+        let row = Self::bind_tables(params, sqlx::query_as::<_, Self>(&query))
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?;
+        if let (Some(row), Some(images)) = (&row, images) {
+            let img_ids = DBImage::insert_all(images, &mut conn).await?;
+            Self::attach_images(row.id(), &img_ids, &mut *conn).await?;
+        }
+        if let (Some(row), Some(tags)) = (&row, tags) {
+            Self::set_tags(row.id(), &tags, &mut *conn).await?;
+        }
+        Ok(row)
     }
 
-    async fn update_one(params: P, pool: &Pool<Postgres>, id: Uuid) -> anyhow::Result<()> {
-        let binds = Self::fields()
-            .iter()
-            .enumerate()
-            .fold(String::new(), |mut acc, (i, _)| {
-                acc.push_str(&format!("${}", i + 1));
-                acc
-            });
+    /// Moves the row to the trash. `None` when there's no such row outside it
+    async fn trash_one(id: Uuid, db: impl DbConn) -> anyhow::Result<Option<Self>> {
+        Self::set_deleted_at(id, "NOW()", "deleted_at IS NULL", db).await
+    }
+
+    /// Takes the row back out of the trash. `None` when it isn't in there
+    async fn restore_one(id: Uuid, db: impl DbConn) -> anyhow::Result<Option<Self>> {
+        Self::set_deleted_at(id, "NULL", "deleted_at IS NOT NULL", db).await
+    }
+
+    async fn set_deleted_at(
+        id: Uuid,
+        value: &str,
+        condition: &str,
+        db: impl DbConn,
+    ) -> anyhow::Result<Option<Self>> {
+        if !Self::soft_deletes() {
+            return Err(anyhow!("{} has no trash", Self::table_name()));
+        }
+        let mut conn = db.conn().await?;
         let query = format!(
-            "UPDATE {} WHERE id = {} ({}) VALUES ({});",
+            "UPDATE {} SET deleted_at = {} WHERE id = $1 AND {} RETURNING *;",
             Self::table_name(),
-            id,
-            Self::fields().join(","),
-            binds
+            value,
+            condition
         );
-        let q = sqlx::query_as::<_, Self>(&query);
-        Self::bind_tables(params, q).fetch_one(pool).await?;
+        let row = sqlx::query_as::<_, Self>(&query)
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?;
+        Ok(row)
+    }
+
+    /// Deletes the row for good, as long as it's in the trash
+    async fn purge_one(id: Uuid, db: impl DbConn) -> anyhow::Result<Option<Self>> {
+        if !Self::soft_deletes() {
+            return Err(anyhow!("{} has no trash", Self::table_name()));
+        }
+        let mut conn = db.conn().await?;
+        let query = format!(
+            "DELETE FROM {} WHERE id = $1 AND deleted_at IS NOT NULL RETURNING *;",
+            Self::table_name()
+        );
+        let row = sqlx::query_as::<_, Self>(&query)
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?;
+        Ok(row)
+    }
+
+    /// Ids of rows that have been in the trash for longer than `retention`
+    async fn trashed_before(
+        retention: chrono::Duration,
+        db: impl DbConn,
+    ) -> anyhow::Result<Vec<Uuid>> {
+        if !Self::soft_deletes() {
+            return Ok(vec![]);
+        }
+        let mut conn = db.conn().await?;
+        let query = format!(
+            "SELECT id FROM {} WHERE deleted_at < NOW() - make_interval(secs => $1);",
+            Self::table_name()
+        );
+        let ids = sqlx::query_scalar(&query)
+            .bind(retention.num_seconds() as f64)
+            .fetch_all(&mut *conn)
+            .await?;
+        Ok(ids)
+    }
+
+    /// Publishes the scheduled rows whose `publish_at` has passed and returns
+    /// them, leaving the trash alone. Only for tables with a `status` column
+    async fn publish_due(db: impl DbConn) -> anyhow::Result<Vec<Self>> {
+        let mut conn = db.conn().await?;
+        let query = format!(
+            "UPDATE {} SET status = 'published'
+            WHERE status = 'scheduled' AND publish_at <= NOW() AND {}
+            RETURNING *;",
+            Self::table_name(),
+            Self::not_trashed()
+        );
+        let rows = sqlx::query_as::<_, Self>(&query)
+            .fetch_all(&mut *conn)
+            .await?;
+        Ok(rows)
+    }
+
+    /// [`DbData::insert_one`], then stores the new row as its first revision
+    async fn insert_revised(params: P, author: &DBAdmin, db: impl DbConn) -> anyhow::Result<Self> {
+        let mut conn = db.conn().await?;
+        let row = Self::insert_one(params, &mut *conn).await?;
+        row.record_revision(author, &mut *conn).await?;
+        Ok(row)
+    }
+
+    /// [`DbData::update_one`], then stores the row as a new revision
+    async fn update_revised(
+        params: P,
+        author: &DBAdmin,
+        db: impl DbConn,
+        id: Uuid,
+    ) -> anyhow::Result<Option<Self>> {
+        let mut conn = db.conn().await?;
+        let row = Self::update_one(params, &mut *conn, id).await?;
+        if let Some(row) = &row {
+            row.record_revision(author, &mut *conn).await?;
+        }
+        Ok(row)
+    }
+
+    /// Stores the row as it is now, with the images it shows, as a revision
+    async fn record_revision(&self, author: &DBAdmin, db: impl DbConn) -> anyhow::Result<()> {
+        let mut conn = db.conn().await?;
+        let image_ids = Self::images(self.id(), &mut *conn)
+            .await?
+            .into_iter()
+            .map(|img| img.id)
+            .collect();
+        let params = DBRevisionParams {
+            entity_type: Self::table_name(),
+            entity_id: self.id(),
+            snapshot: Json(serde_json::to_value(self)?),
+            image_ids,
+            author_id: Some(author.id),
+            author_name: author.username.clone(),
+        };
+        DBRevision::insert_one(params, &mut *conn).await?;
+        Ok(())
+    }
+
+    /// Every revision of the row, newest first
+    async fn revisions(id: Uuid, db: impl DbConn) -> anyhow::Result<Vec<DBRevision>> {
+        let mut conn = db.conn().await?;
+        let query = format!(
+            "SELECT * FROM {} WHERE entity_type = $1 AND entity_id = $2
+                ORDER BY created_at DESC, id DESC;",
+            DBRevision::table_name()
+        );
+        let revisions = sqlx::query_as::<_, DBRevision>(&query)
+            .bind(Self::table_name())
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await?;
+        Ok(revisions)
+    }
+
+    /// Drops the row's revisions, returning the images they showed so they
+    /// can be deleted if nothing else does
+    async fn delete_revisions(id: Uuid, db: impl DbConn) -> anyhow::Result<Vec<Uuid>> {
+        let mut conn = db.conn().await?;
+        let query = format!(
+            "DELETE FROM {} WHERE entity_type = $1 AND entity_id = $2 RETURNING image_ids;",
+            DBRevision::table_name()
+        );
+        let image_ids: Vec<Vec<Uuid>> = sqlx::query_scalar(&query)
+            .bind(Self::table_name())
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await?;
+        Ok(image_ids.into_iter().flatten().collect())
+    }
+
+    /// Puts the row's fields and images back the way `revision_id` has them,
+    /// then records that as a new revision. `None` when the row or revision
+    /// doesn't exist, or the row is in the trash
+    async fn roll_back(
+        id: Uuid,
+        revision_id: Uuid,
+        author: &DBAdmin,
+        db: impl DbConn,
+    ) -> anyhow::Result<Option<Self>> {
+        let mut conn = db.conn().await?;
+        let query = format!(
+            "SELECT * FROM {} WHERE id = $1 AND entity_type = $2 AND entity_id = $3;",
+            DBRevision::table_name()
+        );
+        let Some(revision) = sqlx::query_as::<_, DBRevision>(&query)
+            .bind(revision_id)
+            .bind(Self::table_name())
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        // the snapshot's keys are the column names
+        let assignments: Vec<String> = Self::fields()
+            .iter()
+            // columns added after the snapshot was taken keep their value
+            .map(|f| format!("{f} = CASE WHEN $1 ? '{f}' THEN r.{f} ELSE d.{f} END"))
+            .collect();
+        let query = format!(
+            "UPDATE {table} d SET {}
+                FROM jsonb_populate_record(NULL::{table}, $1) r
+                WHERE d.id = $2 AND d.{} RETURNING d.*;",
+            assignments.join(","),
+            Self::not_trashed(),
+            table = Self::table_name(),
+        );
+        let Some(row) = sqlx::query_as::<_, Self>(&query)
+            .bind(&revision.snapshot)
+            .bind(id)
+            .fetch_optional(&mut *conn)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        sqlx::query("DELETE FROM entity_images WHERE entity_type = $1 AND entity_id = $2;")
+            .bind(Self::table_name())
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+        Self::attach_images(id, &revision.image_ids, &mut *conn).await?;
+        row.record_revision(author, &mut *conn).await?;
+        Ok(Some(row))
+    }
+
+    /// The item's tags by name
+    async fn tags(id: Uuid, db: impl DbConn) -> anyhow::Result<Vec<DBTag>> {
+        let mut conn = db.conn().await?;
+        let tags = sqlx::query_as::<_, DBTag>(
+            "SELECT t.* FROM entity_tags e
+                JOIN tags t ON t.id = e.tag_id
+                WHERE e.entity_type = $1 AND e.entity_id = $2
+                ORDER BY t.name;",
+        )
+        .bind(Self::table_name())
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await?;
+        Ok(tags)
+    }
+
+    /// Leaves the item with just the tags called `names`, making new ones as
+    /// needed. Tags nothing is tagged with anymore stay for later
+    async fn set_tags(id: Uuid, names: &[String], db: impl DbConn) -> anyhow::Result<()> {
+        let mut conn = db.conn().await?;
+        let tag_ids = DBTag::find_or_insert_all(names, &mut *conn).await?;
+        sqlx::query("DELETE FROM entity_tags WHERE entity_type = $1 AND entity_id = $2;")
+            .bind(Self::table_name())
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query(
+            "INSERT INTO entity_tags (entity_type, entity_id, tag_id)
+                SELECT $1, $2, unnest($3::uuid[]);",
+        )
+        .bind(Self::table_name())
+        .bind(id)
+        .bind(&tag_ids)
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    }
+
+    /// The item's images in the order they're shown
+    async fn images(id: Uuid, db: impl DbConn) -> anyhow::Result<Vec<DBImage>> {
+        let mut conn = db.conn().await?;
+        let images = sqlx::query_as::<_, DBImage>(
+            "SELECT i.* FROM entity_images e
+                JOIN images i ON i.id = e.image_id
+                WHERE e.entity_type = $1 AND e.entity_id = $2
+                ORDER BY e.position;",
+        )
+        .bind(Self::table_name())
+        .bind(id)
+        .fetch_all(&mut *conn)
+        .await?;
+        Ok(images)
+    }
+
+    /// Puts images after the ones the item already has. Images already on the
+    /// item keep their place, the same image can be attached to other items
+    async fn attach_images(id: Uuid, image_ids: &[Uuid], db: impl DbConn) -> anyhow::Result<()> {
+        let mut conn = db.conn().await?;
+        sqlx::query(
+            "INSERT INTO entity_images (entity_type, entity_id, image_id, position)
+                SELECT $1, $2, u.image_id, u.ord + COALESCE(
+                    (SELECT MAX(position) FROM entity_images
+                        WHERE entity_type = $1 AND entity_id = $2),
+                    0
+                )
+                FROM unnest($3::uuid[]) WITH ORDINALITY AS u(image_id, ord)
+                ON CONFLICT DO NOTHING;",
+        )
+        .bind(Self::table_name())
+        .bind(id)
+        .bind(image_ids)
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Takes images off the item and returns the ids that were on it. The
+    /// images themselves stay, [`DBImage::delete_unattached`] removes them once
+    /// nothing shows them
+    async fn detach_images(
+        id: Uuid,
+        image_ids: &[Uuid],
+        db: impl DbConn,
+    ) -> anyhow::Result<Vec<Uuid>> {
+        let mut conn = db.conn().await?;
+        let detached = sqlx::query_scalar(
+            "DELETE FROM entity_images
+                WHERE entity_type = $1 AND entity_id = $2 AND image_id = ANY($3)
+                RETURNING image_id;",
+        )
+        .bind(Self::table_name())
+        .bind(id)
+        .bind(image_ids)
+        .fetch_all(&mut *conn)
+        .await?;
+        Ok(detached)
+    }
+
+    /// Shows the item's images in the order of `image_ids`. Attached images
+    /// missing from it keep their relative order after the listed ones
+    async fn reorder_images(id: Uuid, image_ids: &[Uuid], db: impl DbConn) -> anyhow::Result<()> {
+        let mut conn = db.conn().await?;
+        sqlx::query(
+            "UPDATE entity_images e SET position = o.position
+                FROM (
+                    SELECT ei.image_id,
+                        ROW_NUMBER() OVER (ORDER BY u.ord NULLS LAST, ei.position)::INT AS position
+                    FROM entity_images ei
+                    LEFT JOIN unnest($3::uuid[]) WITH ORDINALITY AS u(image_id, ord)
+                        ON u.image_id = ei.image_id
+                    WHERE ei.entity_type = $1 AND ei.entity_id = $2
+                ) o
+                WHERE e.entity_type = $1 AND e.entity_id = $2 AND e.image_id = o.image_id;",
+        )
+        .bind(Self::table_name())
+        .bind(id)
+        .bind(image_ids)
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Takes every image off the item, for items that show a single one and
+    /// are getting a new one. Returns the ids that were on it
+    async fn detach_all_images(id: Uuid, db: impl DbConn) -> anyhow::Result<Vec<Uuid>> {
+        let mut conn = db.conn().await?;
+        let attached: Vec<Uuid> = Self::images(id, &mut *conn)
+            .await?
+            .iter()
+            .map(|img| img.id)
+            .collect();
+        Self::detach_images(id, &attached, &mut *conn).await
+    }
+
+    /// Shows the rows in the order of `ids`. Rows missing from it, trashed
+    /// ones included, keep their relative order after the listed ones. Only
+    /// for tables with a `position` column
+    async fn reorder(ids: &[Uuid], db: impl DbConn) -> anyhow::Result<()> {
+        let mut conn = db.conn().await?;
+        let query = format!(
+            "UPDATE {table} d SET position = o.position
+                FROM (
+                    SELECT r.id,
+                        ROW_NUMBER() OVER (ORDER BY u.ord NULLS LAST, r.position, r.id)::INT
+                            AS position
+                    FROM {table} r
+                    LEFT JOIN unnest($1::uuid[]) WITH ORDINALITY AS u(id, ord) ON u.id = r.id
+                ) o
+                WHERE d.id = o.id;",
+            table = Self::table_name()
+        );
+        sqlx::query(&query).bind(ids).execute(&mut *conn).await?;
         Ok(())
     }
+
+    /// Moves the row to `place` among the others, counting from 1. `None`
+    /// when there's no such row outside the trash. Only for tables with a
+    /// `position` column
+    async fn move_to(id: Uuid, place: usize, db: impl DbConn) -> anyhow::Result<Option<Self>> {
+        let mut conn = db.conn().await?;
+        let query = format!(
+            "SELECT id FROM {} WHERE {} ORDER BY position, id;",
+            Self::table_name(),
+            Self::not_trashed()
+        );
+        let mut ids: Vec<Uuid> = sqlx::query_scalar(&query).fetch_all(&mut *conn).await?;
+        let Some(from) = ids.iter().position(|row| *row == id) else {
+            return Ok(None);
+        };
+        ids.remove(from);
+        ids.insert(place.saturating_sub(1).min(ids.len()), id);
+        Self::reorder(&ids, &mut *conn).await?;
+        Self::get_single_by(&mut *conn, id).await
+    }
 }
 
 impl DBImage {
+    /// Inserts images on a connection the caller already holds, so they land in
+    /// the same transaction as the row that points at them
+    pub(super) async fn insert_all(
+        images: Vec<DBImageParams>,
+        conn: &mut PgConnection,
+    ) -> anyhow::Result<Vec<Uuid>> {
+        // not insert_one, which calls this and would make its future recursive
+        let query = Self::insert_query::<Self, DBImageParams>();
+        let mut ids = vec![];
+        for img in images {
+            let q = sqlx::query_as::<_, Self>(&query);
+            let i = Self::bind_tables(img, q).fetch_one(&mut *conn).await?;
+            ids.push(i.id);
+        }
+        Ok(ids)
+    }
+
     pub(super) fn insert_query<D, P>() -> String
     where
         P: std::fmt::Debug + Serialize + for<'de> Deserialize<'de> + Send + Unpin,
@@ -174,119 +690,117 @@ impl DBImage {
     }
 
     pub async fn insert_multiple_with_images<D, P>(
-        pool: &Pool<Postgres>,
+        db: impl DbConn,
         multiple: Vec<P>,
-    ) -> anyhow::Result<()>
+    ) -> anyhow::Result<Vec<D>>
     where
         P: std::fmt::Debug + Serialize + for<'de> Deserialize<'de> + Send + Unpin,
         D: DbData<P>,
     {
-        for mut params in multiple {
-            let mut imgs_ids = vec![];
-            if let Some(images) = D::take_images(&mut params) {
-                for img in images {
-                    let i = DBImage::insert_one(img, pool).await?;
-                    imgs_ids.push(i.id);
-                }
-            } else {
-                return Err(anyhow!("should include images"));
-            }
-            let query = Self::insert_query::<D, P>();
-
-            let q = sqlx::query_as::<_, D>(&query);
-            D::bind_tables(params, q)
-                .bind(imgs_ids)
-                .fetch_all(pool)
-                .await?;
+        let mut conn = db.conn().await?;
+        let mut inserted = vec![];
+        for params in multiple {
+            inserted.push(D::insert_one(params, &mut *conn).await?);
         }
-        Ok(())
+        Ok(inserted)
+    }
+
+    /// Images that nothing shows anymore, and no revision could bring back,
+    /// out of `ids`, are deleted and returned so their files can go too
+    pub async fn delete_unattached(ids: Vec<Uuid>, db: impl DbConn) -> anyhow::Result<Vec<Self>> {
+        let mut conn = db.conn().await?;
+        let query = format!(
+            "DELETE FROM {} i
+                WHERE i.id = ANY($1)
+                  AND NOT EXISTS (SELECT 1 FROM entity_images e WHERE e.image_id = i.id)
+                  AND NOT EXISTS (SELECT 1 FROM revisions r WHERE r.image_ids @> ARRAY[i.id])
+                RETURNING i.*;",
+            Self::table_name()
+        );
+        let deleted = sqlx::query_as::<_, Self>(&query)
+            .bind(&ids)
+            .fetch_all(&mut *conn)
+            .await?;
+        Ok(deleted)
+    }
+
+    pub async fn get_by_ids(ids: &[Uuid], db: impl DbConn) -> anyhow::Result<Vec<Self>> {
+        let mut conn = db.conn().await?;
+        let query = format!("SELECT * FROM {} WHERE id = ANY($1);", Self::table_name());
+        let images = sqlx::query_as::<_, Self>(&query)
+            .bind(ids)
+            .fetch_all(&mut *conn)
+            .await?;
+        Ok(images)
+    }
+
+    /// A `LEFT JOIN LATERAL` adding an `images` json column to every row of the
+    /// `d` alias, holding the images attached to it in order. Binds the
+    /// entity type as `$1`
+    pub(super) fn images_json_join() -> String {
+        format!(
+            r#"
+LEFT JOIN LATERAL (
+    SELECT COALESCE(
+        json_agg(to_json(i.*) ORDER BY e.position),
+        '[]'::json
+    ) AS images
+    FROM entity_images e
+    JOIN {} i ON i.id = e.image_id
+    WHERE e.entity_type = $1 AND e.entity_id = d.id
+) imgs ON true"#,
+            Self::table_name()
+        )
     }
 
+    /// Every row of `D` with its images, in one query
     pub async fn get_multiple_with_images<D, P>(
-        pool: &Pool<Postgres>,
+        db: impl DbConn,
     ) -> anyhow::Result<Vec<(D, Vec<DBImage>)>>
     where
         P: std::fmt::Debug + Serialize + for<'de> Deserialize<'de> + Send + Unpin,
         D: DbData<P>,
     {
+        Ok(Self::get_page_with_images(&ListQuery::new(), db)
+            .await?
+            .items)
+    }
+
+    /// The rows of `D` that `list` picks with their images, in one query
+    pub async fn get_page_with_images<D, P>(
+        list: &ListQuery<D>,
+        db: impl DbConn,
+    ) -> anyhow::Result<Page<(D, Vec<DBImage>)>>
+    where
+        P: std::fmt::Debug + Serialize + for<'de> Deserialize<'de> + Send + Unpin,
+        D: DbData<P>,
+    {
+        let mut conn = db.conn().await?;
+        let sql = list.sql(2, &D::table_name(), D::soft_deletes());
         let query = format!(
-            r#"
-SELECT
-     d.*, 
-     i.id AS i_id,
-     i.path, 
-     i.alt, 
-     i.subtitle
-FROM {} d
-LEFT JOIN LATERAL unnest(d.img_ids) AS img_id ON true
-LEFT JOIN {} i ON i.id = img_id
-ORDER BY d.id, i.id
-        "#,
+            "SELECT d.*, imgs.images, {} FROM {} d {} {} {} {};",
+            sql.sort_value,
             D::table_name(),
-            Self::table_name(),
+            Self::images_json_join(),
+            sql.where_clause,
+            sql.order_by,
+            sql.limit,
         );
-        let rows = sqlx::query(&query)
-            .fetch_all(pool)
-            .await
-            .expect("failed to query database");
+        let mut q = sqlx::query(&query).bind(D::table_name());
+        for value in sql.binds {
+            q = q.bind(value);
+        }
+        let rows = q.fetch_all(&mut *conn).await?;
         warn!("got {:?} rows", rows.len());
 
-        let mut result = Vec::new();
-        let mut current_item: Option<(D, Vec<DBImage>)> = None;
-
+        let mut result = Vec::with_capacity(rows.len());
         for row in rows {
-            // for col in row.columns() {
-            //     warn!("{col:?}");
-            // }
-            if let Some(item_id) = row.try_get("id").ok() {
-                let expected_imgs_amt: usize = row
-                    .try_get::<Vec<Uuid>, _>("img_ids")
-                    .ok()
-                    .unwrap_or(vec![])
-                    .len();
-                warn!("expecting {:?} images", expected_imgs_amt);
-
-                if current_item.is_none()
-                    || (current_item.as_ref().unwrap().0.id() != item_id
-                        && current_item.as_ref().unwrap().1.len() == expected_imgs_amt)
-                {
-                    if let Some(item) = current_item {
-                        result.push(item);
-                    }
-                    let new_item: D = D::from_row(&row)?;
-                    current_item = Some((new_item, Vec::new()));
-                }
-
-                if row.try_get::<String, _>("path").is_ok()
-                    && row.try_get::<String, _>("alt").is_ok()
-                    && row.try_get::<Option<String>, _>("subtitle").is_ok()
-                {
-                    if let Ok(image_id) = row.try_get::<Uuid, _>("i_id") {
-                        let image = DBImage {
-                            id: image_id,
-                            path: row.get("path"),
-                            alt: row.get("alt"),
-                            subtitle: row.get("subtitle"),
-                        };
-                        current_item.as_mut().unwrap().1.push(image);
-                    }
-                }
-
-                if let Some(item) = current_item.take() {
-                    if item.1.len() == expected_imgs_amt {
-                        warn!("pushing item: {:?}", item);
-                        result.push(item);
-                    } else {
-                        warn!("item: {:?} not ready", item);
-                        current_item = Some(item);
-                    }
-                }
-            } else {
-                warn!("row does not have an id field");
-            }
+            let Json(images) = row.try_get::<Json<Vec<DBImage>>, _>("images")?;
+            let item = D::from_row(&row)?;
+            let id = item.id();
+            result.push(((item, images), row.try_get("sort_value")?, id));
         }
-
-        Ok(result)
+        Ok(list.page(result))
     }
 }
 
@@ -294,20 +808,30 @@ mod tests {
     use std::{str::FromStr, sync::LazyLock};
 
     use chrono::NaiveDate;
-    use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+    use sqlx::{postgres::PgPoolOptions, types::Json, Pool, Postgres};
     use uuid::Uuid;
 
     use crate::{
+        auth::permissions::Role,
         database::models::{
-            DBAddress, DBAddressParams, DBDedication, DBDedicationParams, DBImage, DBImageParams,
-            DBPatrolLog, DBPatrolLogParams, DBResource, DBResourceParams, DBTestimonial,
-            DBTestimonialParams,
+            ContentStatus, DBAddress, DBAddressParams, DBAdmin, DBAdminParams, DBBoardMember,
+            DBBoardMemberParams, DBDedication, DBDedicationParams, DBImage, DBImageParams,
+            DBPatrolGear, DBPatrolGearParams, DBPatrolLog, DBPatrolLogParams, DBResource,
+            DBResourceParams, DBRevision, DBTag, DBTestimonial, DBTestimonialParams, GearCategory,
+            GearVariant,
         },
         telemetry::{get_subscriber, init_subscriber},
         TRACING,
     };
 
-    use super::DbData;
+    use super::{
+        super::{
+            query::{Direction, ListQuery},
+            search::{highlight, search},
+            seeds::{seed_all, SEEDS_DIRECTORY},
+        },
+        DbData,
+    };
 
     async fn connect_to_database() -> Pool<Postgres> {
         dotenv::dotenv().ok();
@@ -354,6 +878,37 @@ mod tests {
             .await
             .unwrap();
 
+        // nothing a rolled back upload inserted sticks around
+        let images_amt = DBImage::get_multiple(&pool).await.unwrap().len();
+        let mut tx = pool.begin().await.unwrap();
+        DBImage::insert_multiple_with_images::<DBPatrolLog, DBPatrolLogParams>(
+            &mut tx,
+            vec![DBPatrolLogParams {
+                heading: "Rolled Back".to_string(),
+                description: "never committed".to_string(),
+                date: NaiveDate::from_ymd_opt(2023, 4, 2).unwrap(),
+                img_params: vec![DBImageParams {
+                    path: "path5".to_string(),
+                    alt: "alt5".to_string(),
+                    subtitle: None,
+                }],
+                status: ContentStatus::Published,
+                publish_at: None,
+                tags: vec![],
+            }],
+        )
+        .await
+        .unwrap();
+        tx.rollback().await.unwrap();
+        assert_eq!(
+            DBImage::get_multiple(&pool).await.unwrap().len(),
+            images_amt
+        );
+        assert_eq!(
+            DBPatrolLog::get_multiple(&pool).await.unwrap().len(),
+            logs_amt
+        );
+
         let testi_amt = td.testimonials.len();
         for params in td.testimonials {
             DBTestimonial::insert_one(params, &pool).await.unwrap();
@@ -364,6 +919,112 @@ mod tests {
             DBResource::insert_one(params, &pool).await.unwrap();
         }
 
+        let test = DBTestimonial::get_multiple(&pool).await.unwrap().remove(0);
+        let edit = || DBTestimonialParams {
+            firstname: "Janet".to_string(),
+            lastname: test.lastname.clone(),
+            bio: None,
+            content: test.content.clone(),
+            status: ContentStatus::Published,
+            publish_at: None,
+        };
+        let updated = DBTestimonial::update_one(edit(), &pool, test.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.firstname, "Janet");
+        assert_eq!(updated.bio, None);
+        assert!(DBTestimonial::update_one(edit(), &pool, Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_none());
+
+        let log = DBPatrolLog::get_multiple(&pool).await.unwrap().remove(0);
+        let log_images = DBPatrolLog::images(log.id, &pool).await.unwrap();
+        let updated = DBPatrolLog::update_one(
+            DBPatrolLogParams {
+                heading: "Edited Patrol Log".to_string(),
+                description: log.description.clone(),
+                date: log.date,
+                img_params: vec![DBImageParams {
+                    path: "path4".to_string(),
+                    alt: "alt4".to_string(),
+                    subtitle: None,
+                }],
+                status: ContentStatus::Published,
+                publish_at: None,
+                tags: vec![],
+            },
+            &pool,
+            log.id,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(updated.heading, "Edited Patrol Log");
+        let updated_images = DBPatrolLog::images(log.id, &pool).await.unwrap();
+        assert_eq!(updated_images.len(), log_images.len() + 1);
+        assert_eq!(updated_images[log_images.len()].path, "path4");
+        for (before, after) in log_images.iter().zip(updated_images.iter()) {
+            assert_eq!(before.id, after.id);
+        }
+
+        let res = DBResource::get_multiple(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|r| r.name == "Resource 1")
+            .unwrap();
+        let website = DBResource::get_multiple(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|r| r.name == "Resource 2")
+            .and_then(|r| r.website_url);
+        assert_eq!(website.as_deref(), Some("www.website.com"));
+        let res_images = DBResource::images(res.id, &pool).await.unwrap();
+        assert_eq!(res_images.len(), 3);
+        let updated = DBResource::update_one(
+            DBResourceParams {
+                name: "Resource 1".to_string(),
+                description: "An edited description".to_string(),
+                missions: vec![],
+                phone: None,
+                email: None,
+                website_url: None,
+                address: Some(DBAddressParams {
+                    city: "Los Angeles".to_string(),
+                    state: "CA".to_string(),
+                    zip: "90001".to_string(),
+                    line_1: "456 Elm St".to_string(),
+                    line_2: Some("Apt 2".to_string()),
+                }),
+                address_id: None,
+                twitter: None,
+                facebook: None,
+                instagram: None,
+                linkedin: None,
+                threads: None,
+                youtube: None,
+                img_params: vec![],
+                status: ContentStatus::Published,
+                publish_at: None,
+                tags: vec![],
+            },
+            &pool,
+            res.id,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(updated.description, "An edited description");
+        assert!(updated.missions.is_empty());
+        assert_eq!(updated.address_id, res.address_id);
+        assert_eq!(
+            DBResource::images(res.id, &pool).await.unwrap().len(),
+            res_images.len()
+        );
+
         let all_deds_and_imgs =
             DBImage::get_multiple_with_images::<DBDedication, DBDedicationParams>(&pool)
                 .await
@@ -458,6 +1119,921 @@ mod tests {
         assert_eq!(all.len(), 0);
     }
 
+    #[tokio::test]
+    async fn entity_images_test() {
+        LazyLock::force(&TRACING);
+
+        let pool = connect_to_database().await;
+        // crud_test empties these tables, so nothing here is ever committed
+        let mut tx = pool.begin().await.unwrap();
+        let image = |n: &str| DBImageParams {
+            path: format!("entity_images_test/{}", n),
+            alt: n.to_string(),
+            subtitle: None,
+        };
+        let log = DBImage::insert_multiple_with_images::<DBPatrolLog, DBPatrolLogParams>(
+            &mut tx,
+            vec![DBPatrolLogParams {
+                heading: "Image Order".to_string(),
+                description: "images get attached in order".to_string(),
+                date: NaiveDate::from_ymd_opt(2023, 5, 1).unwrap(),
+                img_params: vec![image("a"), image("b"), image("c")],
+                status: ContentStatus::Published,
+                publish_at: None,
+                tags: vec![],
+            }],
+        )
+        .await
+        .unwrap()
+        .remove(0);
+        let ids = |images: Vec<DBImage>| images.into_iter().map(|i| i.id).collect::<Vec<_>>();
+        let original = ids(DBPatrolLog::images(log.id, &mut tx).await.unwrap());
+        assert_eq!(original.len(), 3);
+
+        let reordered = vec![original[2], original[0]];
+        DBPatrolLog::reorder_images(log.id, &reordered, &mut tx)
+            .await
+            .unwrap();
+        assert_eq!(
+            ids(DBPatrolLog::images(log.id, &mut tx).await.unwrap()),
+            vec![original[2], original[0], original[1]]
+        );
+
+        // attaching twice doesn't duplicate the image
+        DBPatrolLog::attach_images(log.id, &original[..1], &mut tx)
+            .await
+            .unwrap();
+        assert_eq!(DBPatrolLog::images(log.id, &mut tx).await.unwrap().len(), 3);
+
+        let ded = DBDedication::insert_one(
+            DBDedicationParams {
+                names: vec!["Shared Image".to_string()],
+                bio: "shows an image of the log".to_string(),
+                birth: NaiveDate::from_ymd_opt(1980, 5, 15).unwrap(),
+                death: NaiveDate::from_ymd_opt(2050, 12, 31).unwrap(),
+                img_params: vec![],
+                status: ContentStatus::Published,
+                publish_at: None,
+            },
+            &mut tx,
+        )
+        .await
+        .unwrap();
+        DBDedication::attach_images(ded.id, &original[..1], &mut tx)
+            .await
+            .unwrap();
+
+        let detached = DBPatrolLog::detach_images(log.id, &original[1..2], &mut tx)
+            .await
+            .unwrap();
+        assert_eq!(detached, original[1..2]);
+
+        // deleting the log drops its links, only the shared image survives
+        DBPatrolLog::delete_one_with_id(log.id, &mut tx)
+            .await
+            .unwrap();
+        assert!(DBPatrolLog::images(log.id, &mut tx)
+            .await
+            .unwrap()
+            .is_empty());
+        let deleted = DBImage::delete_unattached(original.clone(), &mut tx)
+            .await
+            .unwrap();
+        assert_eq!(deleted.len(), 2);
+        assert_eq!(
+            ids(DBDedication::images(ded.id, &mut tx).await.unwrap()),
+            original[..1]
+        );
+
+        DBDedication::delete_one_with_id(ded.id, &mut tx)
+            .await
+            .unwrap();
+        let deleted = DBImage::delete_unattached(original, &mut tx).await.unwrap();
+        assert_eq!(deleted.len(), 1);
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn paging_test() {
+        LazyLock::force(&TRACING);
+
+        let pool = connect_to_database().await;
+        let mut tx = pool.begin().await.unwrap();
+        let log = |heading: &str, day: u32| DBPatrolLogParams {
+            heading: heading.to_string(),
+            description: String::new(),
+            date: NaiveDate::from_ymd_opt(1901, 1, day).unwrap(),
+            img_params: vec![],
+            status: ContentStatus::Published,
+            publish_at: None,
+            tags: vec![],
+        };
+        // two logs share a date so the cursor has to fall back on the id
+        DBImage::insert_multiple_with_images::<DBPatrolLog, DBPatrolLogParams>(
+            &mut tx,
+            vec![
+                log("first", 1),
+                log("second", 2),
+                log("third", 2),
+                log("fourth", 3),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let list = || {
+            ListQuery::new()
+                .sort_by(DBPatrolLog::DATE, Direction::Desc)
+                .filter_range(
+                    DBPatrolLog::DATE,
+                    NaiveDate::from_ymd_opt(1901, 1, 1),
+                    NaiveDate::from_ymd_opt(1901, 1, 31),
+                )
+                .limit(3)
+        };
+        let first = DBPatrolLog::get_page(&list(), &mut tx).await.unwrap();
+        assert_eq!(first.items.len(), 3);
+        assert_eq!(first.items[0].heading, "fourth");
+        let second = DBPatrolLog::get_page(&list().after(first.next.as_deref()).unwrap(), &mut tx)
+            .await
+            .unwrap();
+        assert!(second.next.is_none());
+        let mut headings: Vec<_> = first
+            .items
+            .iter()
+            .chain(second.items.iter())
+            .map(|l| l.heading.as_str())
+            .collect();
+        assert_eq!(headings.pop(), Some("first"));
+        headings.sort();
+        assert_eq!(headings, ["fourth", "second", "third"]);
+
+        let only = ListQuery::new().filter_eq(DBPatrolLog::HEADING, "third");
+        let found = DBPatrolLog::get_page(&only, &mut tx).await.unwrap();
+        assert_eq!(found.items.len(), 1);
+
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn trash_test() {
+        LazyLock::force(&TRACING);
+
+        let pool = connect_to_database().await;
+        let mut tx = pool.begin().await.unwrap();
+        let params = || DBPatrolLogParams {
+            heading: "Trashed".to_string(),
+            description: "goes to the trash".to_string(),
+            date: NaiveDate::from_ymd_opt(1902, 1, 1).unwrap(),
+            img_params: vec![DBImageParams {
+                path: "trash_test/a".to_string(),
+                alt: "a".to_string(),
+                subtitle: None,
+            }],
+            status: ContentStatus::Published,
+            publish_at: None,
+            tags: vec![],
+        };
+        let log = DBPatrolLog::insert_one(params(), &mut tx).await.unwrap();
+        let trashed = DBPatrolLog::trash_one(log.id, &mut tx)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(trashed.deleted_at.is_some());
+        // only once
+        assert!(DBPatrolLog::trash_one(log.id, &mut tx)
+            .await
+            .unwrap()
+            .is_none());
+
+        assert!(DBPatrolLog::get_single_by(&mut tx, log.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(DBPatrolLog::update_one(params(), &mut tx, log.id)
+            .await
+            .unwrap()
+            .is_none());
+        let only = || ListQuery::new().filter_eq(DBPatrolLog::HEADING, "Trashed");
+        assert!(DBPatrolLog::get_page(&only(), &mut tx)
+            .await
+            .unwrap()
+            .items
+            .is_empty());
+        let in_trash = DBPatrolLog::get_page(&only().trashed(), &mut tx)
+            .await
+            .unwrap();
+        assert_eq!(in_trash.items.len(), 1);
+        // images stay attached while it's in the trash
+        assert_eq!(DBPatrolLog::images(log.id, &mut tx).await.unwrap().len(), 1);
+
+        DBPatrolLog::restore_one(log.id, &mut tx)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(DBPatrolLog::get_single_by(&mut tx, log.id)
+            .await
+            .unwrap()
+            .is_some());
+        // purging only takes what's in the trash
+        assert!(DBPatrolLog::purge_one(log.id, &mut tx)
+            .await
+            .unwrap()
+            .is_none());
+
+        DBPatrolLog::trash_one(log.id, &mut tx).await.unwrap();
+        assert!(
+            DBPatrolLog::trashed_before(chrono::Duration::days(1), &mut tx)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        sqlx::query("UPDATE patrol_logs SET deleted_at = NOW() - INTERVAL '2 days' WHERE id = $1;")
+            .bind(log.id)
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        assert_eq!(
+            DBPatrolLog::trashed_before(chrono::Duration::days(1), &mut tx)
+                .await
+                .unwrap(),
+            vec![log.id]
+        );
+        assert!(DBPatrolLog::purge_one(log.id, &mut tx)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(DBPatrolLog::images(log.id, &mut tx)
+            .await
+            .unwrap()
+            .is_empty());
+        // addresses don't have a trash
+        assert!(DBAddress::trash_one(log.id, &mut tx).await.is_err());
+
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn publishing_test() {
+        LazyLock::force(&TRACING);
+
+        let pool = connect_to_database().await;
+        let mut tx = pool.begin().await.unwrap();
+        let now = chrono::Utc::now();
+        let debrief = |firstname: &str, status, publish_at| DBTestimonialParams {
+            firstname: firstname.to_string(),
+            lastname: "Publishing".to_string(),
+            bio: None,
+            content: "not live yet".to_string(),
+            status,
+            publish_at,
+        };
+        let draft =
+            DBTestimonial::insert_one(debrief("Draft", ContentStatus::Draft, None), &mut tx)
+                .await
+                .unwrap();
+        let due = DBTestimonial::insert_one(
+            debrief(
+                "Due",
+                ContentStatus::Scheduled,
+                Some(now - chrono::Duration::minutes(1)),
+            ),
+            &mut tx,
+        )
+        .await
+        .unwrap();
+        let later = DBTestimonial::insert_one(
+            debrief(
+                "Later",
+                ContentStatus::Scheduled,
+                Some(now + chrono::Duration::days(1)),
+            ),
+            &mut tx,
+        )
+        .await
+        .unwrap();
+        let trashed = DBTestimonial::insert_one(
+            debrief(
+                "Trashed",
+                ContentStatus::Scheduled,
+                Some(now - chrono::Duration::minutes(1)),
+            ),
+            &mut tx,
+        )
+        .await
+        .unwrap();
+        DBTestimonial::trash_one(trashed.id, &mut tx)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let names = |page: super::super::query::Page<DBTestimonial>| {
+            page.items
+                .into_iter()
+                .map(|t| t.firstname)
+                .collect::<Vec<_>>()
+        };
+        let only = || ListQuery::new().filter_eq(DBTestimonial::LASTNAME, "Publishing");
+        assert_eq!(
+            names(DBTestimonial::get_page(&only(), &mut tx).await.unwrap()).len(),
+            3
+        );
+        assert!(names(
+            DBTestimonial::get_page(&only().published(), &mut tx)
+                .await
+                .unwrap()
+        )
+        .is_empty());
+
+        let published: Vec<Uuid> = DBTestimonial::publish_due(&mut tx)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        assert!(published.contains(&due.id));
+        assert!(!published.contains(&draft.id) && !published.contains(&later.id));
+        assert!(!published.contains(&trashed.id));
+        assert_eq!(
+            names(
+                DBTestimonial::get_page(&only().published(), &mut tx)
+                    .await
+                    .unwrap()
+            ),
+            ["Due"]
+        );
+
+        // scheduling needs a time
+        assert!(DBTestimonial::insert_one(
+            debrief("Never", ContentStatus::Scheduled, None),
+            &mut tx
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn search_test() {
+        LazyLock::force(&TRACING);
+
+        let pool = connect_to_database().await;
+        let mut tx = pool.begin().await.unwrap();
+        let dedication = |name: &str, bio: &str, status| DBDedicationParams {
+            names: vec![name.to_string()],
+            bio: bio.to_string(),
+            birth: NaiveDate::from_ymd_opt(1950, 1, 1).unwrap(),
+            death: NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(),
+            img_params: vec![],
+            status,
+            publish_at: None,
+        };
+        let named = DBDedication::insert_one(
+            dedication(
+                "Sgt Quillfeather",
+                "served<br/>with honor",
+                ContentStatus::Published,
+            ),
+            &mut tx,
+        )
+        .await
+        .unwrap();
+        let mentioned = DBDedication::insert_one(
+            dedication(
+                "Cpl Other",
+                "remembered by Quillfeather",
+                ContentStatus::Published,
+            ),
+            &mut tx,
+        )
+        .await
+        .unwrap();
+        let draft = DBDedication::insert_one(
+            dedication("Quillfeather Draft", "", ContentStatus::Draft),
+            &mut tx,
+        )
+        .await
+        .unwrap();
+        let trashed = DBDedication::insert_one(
+            dedication("Quillfeather Trashed", "", ContentStatus::Published),
+            &mut tx,
+        )
+        .await
+        .unwrap();
+        DBDedication::trash_one(trashed.id, &mut tx).await.unwrap();
+
+        let hits = search::<DBDedication, DBDedicationParams>("quillfeather", false, 10, &mut tx)
+            .await
+            .unwrap();
+        // names outrank bios
+        assert_eq!(
+            hits.iter().map(|h| h.id).collect::<Vec<_>>(),
+            [named.id, mentioned.id]
+        );
+        assert_eq!(hits[0].marked_title, "Sgt \u{1}Quillfeather\u{2}");
+        assert!(!hits[0].snippet.contains("<br/>"));
+
+        let with_drafts =
+            search::<DBDedication, DBDedicationParams>("quillfeather", true, 10, &mut tx)
+                .await
+                .unwrap();
+        assert!(with_drafts.iter().any(|h| h.id == draft.id));
+        assert!(!with_drafts.iter().any(|h| h.id == trashed.id));
+
+        // marks typed into an upload don't survive to be turned into html
+        DBDedication::insert_one(
+            dedication(
+                "Cpl \u{1}Brightwater",
+                "\u{1}Fish & Chips\u{2} Brightwater \u{2}",
+                ContentStatus::Published,
+            ),
+            &mut tx,
+        )
+        .await
+        .unwrap();
+        let hits = search::<DBDedication, DBDedicationParams>("brightwater", false, 10, &mut tx)
+            .await
+            .unwrap();
+        assert_eq!(hits[0].marked_title, "Cpl \u{1}Brightwater\u{2}");
+        assert_eq!(
+            highlight(&hits[0].snippet),
+            "Fish &amp; Chips <mark>Brightwater</mark>"
+        );
+    }
+
+    #[tokio::test]
+    async fn tags_test() {
+        LazyLock::force(&TRACING);
+
+        let pool = connect_to_database().await;
+        let mut tx = pool.begin().await.unwrap();
+        let mut td = test_data();
+        assert_eq!(DBTag::slugify("  Fly & Fishing!! "), "fly-fishing");
+
+        let mut log = td.logs.remove(0);
+        log.heading = "Tagged patrol".to_string();
+        log.tags = ["PTSD", "Fly Fishing", "ptsd ", "!!"]
+            .map(String::from)
+            .to_vec();
+        let log = DBPatrolLog::insert_one(log, &mut tx).await.unwrap();
+        let mut res = td.resources.remove(0);
+        res.tags = vec!["fly fishing".to_string()];
+        let res = DBResource::insert_one(res, &mut tx).await.unwrap();
+
+        let log_tags = DBPatrolLog::tags(log.id, &mut tx).await.unwrap();
+        let names: Vec<&str> = log_tags.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["Fly Fishing", "PTSD"]);
+        // the resource's tag is the log's, under the name it was made with
+        assert_eq!(
+            DBResource::tags(res.id, &mut tx).await.unwrap(),
+            [log_tags[0].clone()]
+        );
+
+        let logs = DBPatrolLog::get_page(&ListQuery::new().tagged("ptsd"), &mut tx)
+            .await
+            .unwrap();
+        assert_eq!(logs.items.len(), 1);
+        assert_eq!(logs.items[0].id, log.id);
+        assert!(
+            DBResource::get_page(&ListQuery::new().tagged("ptsd"), &mut tx)
+                .await
+                .unwrap()
+                .items
+                .is_empty()
+        );
+
+        let mut edit = test_data().logs.remove(0);
+        edit.tags = vec!["Family".to_string()];
+        DBPatrolLog::update_one(edit, &mut tx, log.id)
+            .await
+            .unwrap()
+            .unwrap();
+        let by_log = DBTag::for_entities(&DBPatrolLog::table_name(), &[log.id], &mut tx)
+            .await
+            .unwrap();
+        assert_eq!(by_log[&log.id].len(), 1);
+        assert_eq!(by_log[&log.id][0].slug, "family");
+        let used: Vec<String> = DBTag::used_by(&DBResource::table_name(), false, &mut tx)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.slug)
+            .collect();
+        assert!(used.contains(&"fly-fishing".to_string()) && !used.contains(&"ptsd".to_string()));
+    }
+
+    #[tokio::test]
+    async fn seeds_test() {
+        LazyLock::force(&TRACING);
+
+        let pool = connect_to_database().await;
+        let mut tx = pool.begin().await.unwrap();
+        let dir = std::path::Path::new(SEEDS_DIRECTORY);
+        // the database may have been seeded by the app already
+        sqlx::query("DELETE FROM seeds;")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+
+        let loaded = seed_all(dir, &mut tx).await.unwrap();
+        assert!(loaded > 0);
+        let list = ListQuery::new().filter_eq(DBTestimonial::LASTNAME, "Garcia");
+        let seeded = DBTestimonial::get_page(&list, &mut tx).await.unwrap().items;
+        assert_eq!(seeded.len(), 1);
+
+        // loading again adds nothing, and deleted entries stay deleted
+        DBTestimonial::delete_one_with_id(seeded[0].id, &mut tx)
+            .await
+            .unwrap();
+        assert_eq!(seed_all(dir, &mut tx).await.unwrap(), 0);
+        assert!(DBTestimonial::get_page(&list, &mut tx)
+            .await
+            .unwrap()
+            .items
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn board_members_test() {
+        LazyLock::force(&TRACING);
+
+        let pool = connect_to_database().await;
+        let mut tx = pool.begin().await.unwrap();
+        let member = |name: &str, photo: &str, active| DBBoardMemberParams {
+            name: name.to_string(),
+            role: "Board Member".to_string(),
+            bio: format!("{} is on the board", name),
+            img_params: vec![DBImageParams {
+                path: format!("board_members_test/{}", photo),
+                alt: name.to_string(),
+                subtitle: None,
+            }],
+            active,
+        };
+        // seeded members may be there too, only the order of these counts
+        async fn order(
+            ids: &[Uuid],
+            list: ListQuery<DBBoardMember>,
+            conn: &mut sqlx::PgConnection,
+        ) -> Vec<Uuid> {
+            let list = list.sort_by(DBBoardMember::POSITION, Direction::Asc);
+            DBBoardMember::get_page(&list, conn)
+                .await
+                .unwrap()
+                .items
+                .into_iter()
+                .map(|m| m.id)
+                .filter(|id| ids.contains(id))
+                .collect()
+        }
+
+        let mut ids = vec![];
+        for (name, active) in [("First", true), ("Second", true), ("Hidden", false)] {
+            let row = DBBoardMember::insert_one(member(name, name, active), &mut tx)
+                .await
+                .unwrap();
+            ids.push(row.id);
+        }
+        assert_eq!(order(&ids, ListQuery::new(), &mut tx).await, ids);
+        let active = ListQuery::new().filter_eq(DBBoardMember::ACTIVE, true);
+        assert_eq!(order(&ids, active, &mut tx).await, ids[..2]);
+
+        DBBoardMember::move_to(ids[2], 1, &mut tx)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            order(&ids, ListQuery::new(), &mut tx).await,
+            vec![ids[2], ids[0], ids[1]]
+        );
+        // past the end is last
+        DBBoardMember::move_to(ids[0], 100, &mut tx)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            order(&ids, ListQuery::new(), &mut tx).await,
+            vec![ids[2], ids[1], ids[0]]
+        );
+        assert!(DBBoardMember::move_to(Uuid::new_v4(), 1, &mut tx)
+            .await
+            .unwrap()
+            .is_none());
+
+        // a new photo replaces the old one, edits leave the position alone
+        let before = DBBoardMember::get_single_by(&mut tx, ids[0])
+            .await
+            .unwrap()
+            .unwrap();
+        let params = member("First", "new", true);
+        DBBoardMember::detach_all_images(ids[0], &mut tx)
+            .await
+            .unwrap();
+        let after = DBBoardMember::update_one(params, &mut tx, ids[0])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(after.position, before.position);
+        let photos = DBBoardMember::images(ids[0], &mut tx).await.unwrap();
+        assert_eq!(photos.len(), 1);
+        assert_eq!(photos[0].path, "board_members_test/new");
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn patrol_gear_test() {
+        LazyLock::force(&TRACING);
+
+        let pool = connect_to_database().await;
+        let mut tx = pool.begin().await.unwrap();
+        let variant = |size: &str, stock| GearVariant {
+            size: Some(size.to_string()),
+            color: Some("Black".to_string()),
+            stock,
+        };
+        let params = |variants| DBPatrolGearParams {
+            name: "Test Hoodie".to_string(),
+            category: GearCategory::Tops,
+            price: 60,
+            description: String::new(),
+            variants: Json(variants),
+            img_params: vec![],
+            active: true,
+        };
+
+        let gear =
+            DBPatrolGear::insert_one(params(vec![variant("M", 0), variant("L", 2)]), &mut tx)
+                .await
+                .unwrap();
+        let stored = DBPatrolGear::get_single_by(&mut tx, gear.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.category, GearCategory::Tops);
+        assert_eq!(stored.variants.0, vec![variant("M", 0), variant("L", 2)]);
+        assert_eq!(stored.variants[1].label(), "L / Black");
+        assert!(stored.in_stock());
+
+        let sold_out = DBPatrolGear::update_one(params(vec![variant("M", 0)]), &mut tx, gear.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!sold_out.in_stock());
+        // without variants the stock isn't tracked
+        let untracked = DBPatrolGear::update_one(params(vec![]), &mut tx, gear.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(untracked.in_stock());
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn revisions_test() {
+        LazyLock::force(&TRACING);
+
+        let pool = connect_to_database().await;
+        let mut tx = pool.begin().await.unwrap();
+        let author = DBAdmin::insert_one(
+            DBAdminParams {
+                username: format!("revisions_test_{}", Uuid::new_v4()),
+                password_hash: String::new(),
+                disabled: false,
+                role: Role::Editor,
+                email: None,
+            },
+            &mut tx,
+        )
+        .await
+        .unwrap();
+        let params = |bio: &str, image: &str| DBDedicationParams {
+            names: vec!["Revised".to_string()],
+            bio: bio.to_string(),
+            birth: NaiveDate::from_ymd_opt(1950, 1, 1).unwrap(),
+            death: NaiveDate::from_ymd_opt(2000, 1, 1).unwrap(),
+            img_params: vec![DBImageParams {
+                path: format!("revisions_test/{}", image),
+                alt: image.to_string(),
+                subtitle: None,
+            }],
+            status: ContentStatus::Published,
+            publish_at: None,
+        };
+
+        let ded = DBDedication::insert_revised(params("first", "a"), &author, &mut tx)
+            .await
+            .unwrap();
+        DBDedication::update_revised(params("second", "b"), &author, &mut tx, ded.id)
+            .await
+            .unwrap()
+            .unwrap();
+        let revisions = DBDedication::revisions(ded.id, &mut tx).await.unwrap();
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].image_ids.len(), 2);
+        assert_eq!(revisions[0].author_name, author.username);
+        let first = &revisions[1];
+        let changed: Vec<_> = revisions[0]
+            .changes_since(first, &Default::default())
+            .into_iter()
+            .map(|c| c.field)
+            .collect();
+        assert_eq!(changed, ["bio", "images"]);
+
+        let rolled_back = DBDedication::roll_back(ded.id, first.id, &author, &mut tx)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(rolled_back.bio, "first");
+        let images = DBDedication::images(ded.id, &mut tx).await.unwrap();
+        assert_eq!(
+            images.iter().map(|i| i.id).collect::<Vec<_>>(),
+            first.image_ids
+        );
+        assert_eq!(
+            DBDedication::revisions(ded.id, &mut tx)
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+
+        // snapshots from before a column existed leave that column alone
+        sqlx::query(&format!(
+            "UPDATE {} SET snapshot = snapshot - 'status' - 'publish_at' WHERE id = $1;",
+            DBRevision::table_name()
+        ))
+        .bind(first.id)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+        let rolled_back = DBDedication::roll_back(ded.id, first.id, &author, &mut tx)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(rolled_back.bio, "first");
+        assert_eq!(rolled_back.status, ContentStatus::Published);
+
+        // the detached image stays while a revision still shows it
+        let detached = revisions[0].image_ids[1];
+        assert!(DBImage::delete_unattached(vec![detached], &mut tx)
+            .await
+            .unwrap()
+            .is_empty());
+        DBDedication::delete_revisions(ded.id, &mut tx)
+            .await
+            .unwrap();
+        assert_eq!(
+            DBImage::delete_unattached(vec![detached], &mut tx)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        // a revision of another item doesn't roll this one back
+        assert!(DBPatrolLog::roll_back(ded.id, first.id, &author, &mut tx)
+            .await
+            .unwrap()
+            .is_none());
+
+        tx.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn aggregate_loading_test() {
+        LazyLock::force(&TRACING);
+
+        let pool = connect_to_database().await;
+        let mut tx = pool.begin().await.unwrap();
+        let image = |n: &str| DBImageParams {
+            path: format!("aggregate_loading_test/{}", n),
+            alt: n.to_string(),
+            subtitle: None,
+        };
+        let log = |heading: &str, img_params| DBPatrolLogParams {
+            heading: heading.to_string(),
+            description: "loaded in one query".to_string(),
+            date: NaiveDate::from_ymd_opt(2023, 6, 1).unwrap(),
+            img_params,
+            status: ContentStatus::Published,
+            publish_at: None,
+            tags: vec![],
+        };
+        let inserted = DBImage::insert_multiple_with_images::<DBPatrolLog, DBPatrolLogParams>(
+            &mut tx,
+            vec![
+                log("No Images", vec![]),
+                log("Three Images", vec![image("a"), image("b"), image("c")]),
+                log("Shared Image", vec![]),
+            ],
+        )
+        .await
+        .unwrap();
+        let (empty, full, shared) = (&inserted[0], &inserted[1], &inserted[2]);
+        let full_images = DBPatrolLog::images(full.id, &mut tx).await.unwrap();
+
+        // the same image twice on one log and once on another
+        let first = full_images[0].id;
+        DBPatrolLog::attach_images(shared.id, &[first, first], &mut tx)
+            .await
+            .unwrap();
+        DBPatrolLog::attach_images(full.id, &[first], &mut tx)
+            .await
+            .unwrap();
+        // an image row going away takes its place on the log with it
+        DBImage::delete_one_with_id(full_images[1].id, &mut tx)
+            .await
+            .unwrap();
+
+        let loaded = DBImage::get_multiple_with_images::<DBPatrolLog, DBPatrolLogParams>(&mut tx)
+            .await
+            .unwrap();
+        let images_of = |id: Uuid| {
+            loaded
+                .iter()
+                .find(|(log, _)| log.id == id)
+                .map(|(_, images)| images.iter().map(|i| i.id).collect::<Vec<_>>())
+                .unwrap()
+        };
+        assert!(images_of(empty.id).is_empty());
+        assert_eq!(images_of(full.id), vec![first, full_images[2].id]);
+        assert_eq!(images_of(shared.id), vec![first]);
+
+        let with_address = DBResource::insert_one(
+            DBResourceParams {
+                name: "Aggregate With Address".to_string(),
+                description: "has an address".to_string(),
+                missions: vec![],
+                phone: None,
+                email: None,
+                website_url: None,
+                address: Some(DBAddressParams {
+                    city: "Carson".to_string(),
+                    state: "CA".to_string(),
+                    zip: "90745".to_string(),
+                    line_1: "1 Aggregate Way".to_string(),
+                    line_2: None,
+                }),
+                address_id: None,
+                twitter: None,
+                facebook: None,
+                instagram: None,
+                linkedin: None,
+                threads: None,
+                youtube: None,
+                img_params: vec![image("logo")],
+                status: ContentStatus::Published,
+                publish_at: None,
+                tags: vec![],
+            },
+            &mut tx,
+        )
+        .await
+        .unwrap();
+        let without_address = DBResource::insert_one(
+            DBResourceParams {
+                name: "Aggregate Without Address".to_string(),
+                description: "has no address".to_string(),
+                missions: vec![],
+                phone: None,
+                email: None,
+                website_url: None,
+                address: None,
+                address_id: None,
+                twitter: None,
+                facebook: None,
+                instagram: None,
+                linkedin: None,
+                threads: None,
+                youtube: None,
+                img_params: vec![],
+                status: ContentStatus::Published,
+                publish_at: None,
+                tags: vec![],
+            },
+            &mut tx,
+        )
+        .await
+        .unwrap();
+
+        let loaded = DBResource::get_page_with_address_and_images(&ListQuery::new(), &mut tx)
+            .await
+            .unwrap()
+            .items;
+        let (_, address, images) = loaded
+            .iter()
+            .find(|(res, _, _)| res.id == with_address.id)
+            .unwrap();
+        assert_eq!(address.as_ref().unwrap().line_1, "1 Aggregate Way");
+        assert_eq!(images.len(), 1);
+        let (_, address, images) = loaded
+            .iter()
+            .find(|(res, _, _)| res.id == without_address.id)
+            .unwrap();
+        assert!(address.is_none());
+        assert!(images.is_empty());
+
+        tx.rollback().await.unwrap();
+    }
+
     struct TestData {
         dedications: Vec<DBDedicationParams>,
         logs: Vec<DBPatrolLogParams>,
@@ -490,6 +2066,8 @@ mod tests {
             birth: NaiveDate::from_ymd_opt(1980, 5, 15).unwrap(),
             death: NaiveDate::from_ymd_opt(2050, 12, 31).unwrap(),
             img_params: vec![],
+            status: ContentStatus::Published,
+            publish_at: None,
         }];
 
         let logs = vec![DBPatrolLogParams {
@@ -497,6 +2075,9 @@ mod tests {
             description: "This is a patrol log description".to_string(),
             date: NaiveDate::from_ymd_opt(2023, 4, 1).unwrap(),
             img_params: images.clone(),
+            status: ContentStatus::Published,
+            publish_at: None,
+            tags: vec![],
         }];
 
         let testimonials = vec![DBTestimonialParams {
@@ -504,6 +2085,8 @@ mod tests {
             lastname: "Smith".to_string(),
             bio: Some("A satisfied customer".to_string()),
             content: "I really enjoyed the service!".to_string(),
+            status: ContentStatus::Published,
+            publish_at: None,
         }];
 
         let resources = vec![
@@ -521,6 +2104,7 @@ mod tests {
                     line_1: "456 Elm St".to_string(),
                     line_2: Some("Apt 2".to_string()),
                 }),
+                address_id: None,
                 twitter: None,
                 facebook: None,
                 instagram: None,
@@ -528,6 +2112,9 @@ mod tests {
                 threads: None,
                 youtube: None,
                 img_params: images.clone(),
+                status: ContentStatus::Published,
+                publish_at: None,
+                tags: vec![],
             },
             DBResourceParams {
                 name: "Resource 2".to_string(),
@@ -543,6 +2130,7 @@ mod tests {
                     line_1: "123 Main St".to_string(),
                     line_2: None,
                 }),
+                address_id: None,
                 twitter: None,
                 facebook: None,
                 instagram: None,
@@ -550,6 +2138,9 @@ mod tests {
                 threads: None,
                 youtube: None,
                 img_params: images.clone(),
+                status: ContentStatus::Published,
+                publish_at: None,
+                tags: vec![],
             },
         ];
 
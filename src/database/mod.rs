@@ -1,2 +1,6 @@
 pub mod handles;
 pub mod models;
+pub mod query;
+pub mod revisions;
+pub mod search;
+pub mod seeds;
@@ -1,13 +1,142 @@
 use anyhow::anyhow;
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::prelude::FromRow;
+use sqlx::{prelude::FromRow, types::Json, Row};
 use tracing::warn;
 use uuid::Uuid;
 
-use super::handles::DbData;
+use super::handles::{DbConn, DbData};
+use super::query::{Column, ListQuery, Page};
+use crate::auth::permissions::Role;
+use db_derive::DbData;
+use webauthn_rs::prelude::Passkey;
 
-#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+/// Whether visitors can see a piece of content yet. Scheduled content goes
+/// live on its own once its `publish_at` passes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "content_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ContentStatus {
+    Draft,
+    Scheduled,
+    #[default]
+    Published,
+}
+
+impl ContentStatus {
+    pub fn all() -> Vec<Self> {
+        vec![Self::Draft, Self::Scheduled, Self::Published]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Draft => "draft",
+            Self::Scheduled => "scheduled",
+            Self::Published => "published",
+        }
+    }
+
+    /// What admins see next to content visitors can't, `None` once it's live
+    pub fn badge(&self, publish_at: &Option<DateTime<Utc>>) -> Option<String> {
+        match (self, publish_at) {
+            (Self::Published, _) => None,
+            (Self::Scheduled, Some(at)) => Some(format!(
+                "Scheduled for {} UTC",
+                at.format("%b %-d, %Y %H:%M")
+            )),
+            _ => Some("Draft".to_string()),
+        }
+    }
+}
+
+impl std::str::FromStr for ContentStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::all()
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or(anyhow!("{} is not a content status", s))
+    }
+}
+
+impl std::fmt::Display for ContentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Which tab of the Patrol Gear page a piece of gear is listed under
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "gear_category", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum GearCategory {
+    Tops,
+    Hats,
+    Misc,
+}
+
+impl GearCategory {
+    pub fn all() -> Vec<Self> {
+        vec![Self::Tops, Self::Hats, Self::Misc]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tops => "tops",
+            Self::Hats => "hats",
+            Self::Misc => "misc",
+        }
+    }
+
+    /// How the page names it
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Tops => "Tops",
+            Self::Hats => "Hats",
+            Self::Misc => "Miscellaneous",
+        }
+    }
+}
+
+impl std::str::FromStr for GearCategory {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::all()
+            .into_iter()
+            .find(|category| category.as_str() == s)
+            .ok_or(anyhow!("{} is not a gear category", s))
+    }
+}
+
+impl std::fmt::Display for GearCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// One size and/or color of a piece of gear, with how many are left
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GearVariant {
+    pub size: Option<String>,
+    pub color: Option<String>,
+    pub stock: u32,
+}
+
+impl GearVariant {
+    /// Size and color the way the page shows them, `M / Black`
+    pub fn label(&self) -> String {
+        [self.size.as_deref(), self.color.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, DbData)]
+#[db(table = "images")]
 pub struct DBImage {
     pub id: uuid::Uuid,
     pub path: String,
@@ -22,7 +151,8 @@ pub struct DBImageParams {
     pub subtitle: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, FromRow, DbData)]
+#[db(table = "addresses")]
 pub struct DBAddress {
     pub id: uuid::Uuid,
     pub city: String,
@@ -41,14 +171,18 @@ pub struct DBAddressParams {
     pub line_2: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, FromRow, DbData)]
+#[db(table = "dedications", images, soft_delete)]
 pub struct DBDedication {
     pub id: uuid::Uuid,
     pub names: Vec<String>,
     pub bio: String,
     pub birth: NaiveDate,
     pub death: NaiveDate,
-    pub img_ids: Vec<Uuid>,
+    pub status: ContentStatus,
+    pub publish_at: Option<DateTime<Utc>>,
+    #[db(skip)]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -58,15 +192,23 @@ pub struct DBDedicationParams {
     pub birth: NaiveDate,
     pub death: NaiveDate,
     pub img_params: Vec<DBImageParams>,
+    #[serde(default)]
+    pub status: ContentStatus,
+    #[serde(default)]
+    pub publish_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, FromRow, DbData)]
+#[db(table = "patrol_logs", images, tags, soft_delete)]
 pub struct DBPatrolLog {
     pub id: uuid::Uuid,
     pub heading: String,
     pub description: String,
     pub date: NaiveDate,
-    pub img_ids: Vec<Uuid>,
+    pub status: ContentStatus,
+    pub publish_at: Option<DateTime<Utc>>,
+    #[db(skip)]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -75,15 +217,27 @@ pub struct DBPatrolLogParams {
     pub description: String,
     pub date: NaiveDate,
     pub img_params: Vec<DBImageParams>,
+    #[serde(default)]
+    pub status: ContentStatus,
+    #[serde(default)]
+    pub publish_at: Option<DateTime<Utc>>,
+    /// names of the tags, they're made as needed
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, FromRow, DbData)]
+#[db(table = "testimonials", soft_delete)]
 pub struct DBTestimonial {
     pub id: uuid::Uuid,
     pub firstname: String,
     pub lastname: String,
     pub bio: Option<String>,
     pub content: String,
+    pub status: ContentStatus,
+    pub publish_at: Option<DateTime<Utc>>,
+    #[db(skip)]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -92,12 +246,22 @@ pub struct DBTestimonialParams {
     pub lastname: String,
     pub bio: Option<String>,
     pub content: String,
+    #[serde(default)]
+    pub status: ContentStatus,
+    #[serde(default)]
+    pub publish_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, FromRow, DbData)]
+#[db(
+    table = "support_resources",
+    images,
+    tags,
+    prepare = DBResource::resolve_address,
+    soft_delete
+)]
 pub struct DBResource {
     pub id: uuid::Uuid,
-    pub img_ids: Vec<Uuid>,
     pub name: String,
     pub description: String,
     pub missions: Vec<String>,
@@ -111,6 +275,10 @@ pub struct DBResource {
     pub linkedin: Option<String>,
     pub threads: Option<String>,
     pub instagram: Option<String>,
+    pub status: ContentStatus,
+    pub publish_at: Option<DateTime<Utc>>,
+    #[db(skip)]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -123,365 +291,1133 @@ pub struct DBResourceParams {
     pub email: Option<String>,
     pub website_url: Option<String>,
     pub address: Option<DBAddressParams>,
+    /// set from `address` right before the resource is written
+    #[serde(default)]
+    pub address_id: Option<uuid::Uuid>,
     pub twitter: Option<String>,
     pub facebook: Option<String>,
     pub youtube: Option<String>,
     pub linkedin: Option<String>,
     pub threads: Option<String>,
     pub instagram: Option<String>,
+    #[serde(default)]
+    pub status: ContentStatus,
+    #[serde(default)]
+    pub publish_at: Option<DateTime<Utc>>,
+    /// names of the tags, they're made as needed
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, DbData)]
+#[db(table = "board_members", images, soft_delete)]
+pub struct DBBoardMember {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub role: String,
+    pub bio: String,
+    /// only changed by [`DbData::move_to`], edits leave it alone
+    #[db(skip)]
+    pub position: i32,
+    pub active: bool,
+    #[db(skip)]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBBoardMemberParams {
+    pub name: String,
+    pub role: String,
+    pub bio: String,
+    /// the first one is the member's photo
+    pub img_params: Vec<DBImageParams>,
+    pub active: bool,
+}
+
+/// A slide of the landing page carousel
+#[derive(Debug, Serialize, Deserialize, FromRow, DbData)]
+#[db(table = "landing_slides", images, soft_delete)]
+pub struct DBLandingSlide {
+    pub id: uuid::Uuid,
+    pub alt: String,
+    pub subtitle: String,
+    /// only changed by [`DbData::move_to`], edits leave it alone
+    #[db(skip)]
+    pub position: i32,
+    pub active: bool,
+    #[db(skip)]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBLandingSlideParams {
+    pub alt: String,
+    pub subtitle: String,
+    /// the first one is what the slide shows
+    pub img_params: Vec<DBImageParams>,
+    pub active: bool,
+}
+
+/// Merchandise handed out for a donation on the Patrol Gear page
+#[derive(Debug, Serialize, Deserialize, FromRow, DbData)]
+#[db(table = "patrol_gear", images, soft_delete)]
+pub struct DBPatrolGear {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub category: GearCategory,
+    /// suggested donation in whole dollars
+    pub price: i32,
+    pub description: String,
+    /// empty when there's one kind and its stock isn't tracked
+    pub variants: Json<Vec<GearVariant>>,
+    /// only changed by [`DbData::move_to`], edits leave it alone
+    #[db(skip)]
+    pub position: i32,
+    pub active: bool,
+    #[db(skip)]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBPatrolGearParams {
+    pub name: String,
+    pub category: GearCategory,
+    pub price: i32,
+    pub description: String,
+    #[serde(default)]
+    pub variants: Json<Vec<GearVariant>>,
+    pub img_params: Vec<DBImageParams>,
+    pub active: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, DbData)]
+#[db(table = "admins")]
+pub struct DBAdmin {
+    pub id: uuid::Uuid,
+    pub username: String,
+    #[serde(skip_serializing)]
+    pub password_hash: String,
+    pub disabled: bool,
+    #[db(skip)]
+    pub created_at: DateTime<Utc>,
+    pub role: Role,
+    #[serde(skip_serializing)]
+    #[db(skip)]
+    pub totp_secret: Option<String>,
+    #[db(skip)]
+    pub totp_enabled: bool,
+    /// lowercased, used to match single sign-on logins to this account
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBAdminParams {
+    pub username: String,
+    pub password_hash: String,
+    pub disabled: bool,
+    pub role: Role,
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, DbData)]
+#[db(table = "sessions")]
+pub struct DBSession {
+    pub id: uuid::Uuid,
+    pub admin_id: uuid::Uuid,
+    #[db(skip)]
+    pub created_at: DateTime<Utc>,
+    #[db(skip)]
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    #[serde(skip_serializing)]
+    pub csrf_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBSessionParams {
+    pub admin_id: uuid::Uuid,
+    pub expires_at: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub csrf_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, DbData)]
+#[db(table = "recovery_codes")]
+pub struct DBRecoveryCode {
+    pub id: uuid::Uuid,
+    pub admin_id: uuid::Uuid,
+    /// only written, codes are checked against it in SQL
+    #[serde(skip_serializing)]
+    #[allow(dead_code)]
+    pub code_hash: String,
+    #[db(skip)]
+    pub created_at: DateTime<Utc>,
+    #[db(skip)]
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBRecoveryCodeParams {
+    pub admin_id: uuid::Uuid,
+    pub code_hash: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, DbData)]
+#[db(table = "passkeys")]
+pub struct DBPasskey {
+    pub id: uuid::Uuid,
+    pub admin_id: uuid::Uuid,
+    pub name: String,
+    pub credential_id: String,
+    #[serde(skip_serializing)]
+    pub passkey: Json<Passkey>,
+    #[db(skip)]
+    pub created_at: DateTime<Utc>,
+    #[db(skip)]
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBPasskeyParams {
+    pub admin_id: uuid::Uuid,
+    pub name: String,
+    pub credential_id: String,
+    pub passkey: Json<Passkey>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, DbData)]
+#[db(table = "login_attempts")]
+pub struct DBLoginAttempt {
+    pub id: uuid::Uuid,
+    pub username: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub succeeded: bool,
+    #[db(skip)]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBLoginAttemptParams {
+    pub username: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub succeeded: bool,
+}
+
+/// A long lived bearer token for scripts, only its hash is stored
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, DbData)]
+#[db(table = "api_tokens")]
+pub struct DBApiToken {
+    pub id: uuid::Uuid,
+    pub admin_id: uuid::Uuid,
+    pub name: String,
+    /// only written, tokens are checked against it in SQL
+    #[serde(skip_serializing)]
+    #[allow(dead_code)]
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    #[db(skip)]
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    #[db(skip)]
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBApiTokenParams {
+    pub admin_id: uuid::Uuid,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: Vec<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Who did what to which item. The actor's name is copied in so events
+/// outlive the admin that made them
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, DbData)]
+#[db(table = "audit_events")]
+pub struct DBAuditEvent {
+    pub id: uuid::Uuid,
+    pub actor_id: Option<uuid::Uuid>,
+    pub actor_name: String,
+    pub action: String,
+    pub item_type: Option<String>,
+    pub item_id: Option<uuid::Uuid>,
+    pub snapshot: Option<Json<serde_json::Value>>,
+    pub ip: Option<String>,
+    #[db(skip)]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBAuditEventParams {
+    pub actor_id: Option<uuid::Uuid>,
+    pub actor_name: String,
+    pub action: String,
+    pub item_type: Option<String>,
+    pub item_id: Option<uuid::Uuid>,
+    pub snapshot: Option<Json<serde_json::Value>>,
+    pub ip: Option<String>,
+}
+
+/// A content row as it was after an upload, edit or rollback, written by
+/// [`DbData::record_revision`](super::handles::DbData::record_revision)
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, DbData)]
+#[db(table = "revisions")]
+pub struct DBRevision {
+    pub id: uuid::Uuid,
+    /// table of the row
+    pub entity_type: String,
+    pub entity_id: uuid::Uuid,
+    /// the whole row as json
+    pub snapshot: Json<serde_json::Value>,
+    /// the images the row showed, in order
+    pub image_ids: Vec<uuid::Uuid>,
+    pub author_id: Option<uuid::Uuid>,
+    pub author_name: String,
+    #[db(skip)]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBRevisionParams {
+    pub entity_type: String,
+    pub entity_id: uuid::Uuid,
+    pub snapshot: Json<serde_json::Value>,
+    pub image_ids: Vec<uuid::Uuid>,
+    pub author_id: Option<uuid::Uuid>,
+    pub author_name: String,
+}
+
+/// Narrows [`DBAuditEvent::search`], every field left `None` matches anything
+#[derive(Debug, Default, Clone)]
+pub struct AuditFilter {
+    pub actor_name: Option<String>,
+    pub item_type: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// Server side half of an in-flight passkey registration or login, kept until
+/// the browser answers the challenge
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, DbData)]
+#[db(table = "webauthn_challenges")]
+pub struct DBWebauthnChallenge {
+    pub id: uuid::Uuid,
+    pub admin_id: uuid::Uuid,
+    pub kind: String,
+    pub state: Json<serde_json::Value>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBWebauthnChallengeParams {
+    pub admin_id: uuid::Uuid,
+    pub kind: String,
+    pub state: Json<serde_json::Value>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A label shared by patrol logs and support resources, linked to them in
+/// `entity_tags`
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, PartialEq, DbData)]
+#[db(table = "tags")]
+pub struct DBTag {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub slug: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DBTagParams {
+    pub name: String,
+    pub slug: String,
+}
+
+impl DBAddress {
+    /// Reuses an identical address if one is already stored, so resources at
+    /// the same place share a row
+    pub async fn find_or_insert(
+        params: DBAddressParams,
+        conn: &mut sqlx::PgConnection,
+    ) -> anyhow::Result<Uuid> {
+        let query = format!(
+            "SELECT id
+                FROM {}
+                WHERE city = $1
+                  AND state = $2
+                  AND zip = $3
+                  AND line_1 = $4
+                  AND (line_2 IS NOT DISTINCT FROM $5);",
+            Self::table_name()
+        );
+        let existing: Option<Uuid> = sqlx::query_scalar(&query)
+            .bind(&params.city)
+            .bind(&params.state)
+            .bind(&params.zip)
+            .bind(&params.line_1)
+            .bind(&params.line_2)
+            .fetch_optional(&mut *conn)
+            .await?;
+        warn!("got existing address: {:?}", existing);
+
+        match existing {
+            Some(id) => Ok(id),
+            None => Ok(Self::insert_one(params, conn).await?.id),
+        }
+    }
 }
 
-impl DbData<DBImageParams> for DBImage {
-    fn id(&self) -> Uuid {
-        self.id
+impl DBTag {
+    /// How `name` shows up in urls: lowercase ascii letters and digits, with
+    /// one dash for every run of anything else
+    pub fn slugify(name: &str) -> String {
+        name.split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_ascii_lowercase())
+            .collect::<Vec<_>>()
+            .join("-")
     }
-    fn table_name() -> String {
-        "images".to_string()
+
+    pub async fn get_by_slug(slug: &str, db: impl DbConn) -> anyhow::Result<Option<Self>> {
+        let mut conn = db.conn().await?;
+        let query = format!("SELECT * FROM {} WHERE slug = $1;", Self::table_name());
+        let tag = sqlx::query_as::<_, Self>(&query)
+            .bind(slug)
+            .fetch_optional(&mut *conn)
+            .await?;
+        Ok(tag)
     }
-    fn fields() -> Vec<String> {
-        vec!["path", "alt", "subtitle"]
-            .iter()
-            .map(|s| s.to_string())
-            .collect()
+
+    /// The ids of the tags called `names`, making the ones that don't exist
+    /// yet. Names that slugify to nothing are left out, ones that slugify the
+    /// same count once
+    pub async fn find_or_insert_all(
+        names: &[String],
+        db: impl DbConn,
+    ) -> anyhow::Result<Vec<Uuid>> {
+        let mut conn = db.conn().await?;
+        let mut slugs: Vec<String> = vec![];
+        let mut kept: Vec<String> = vec![];
+        for name in names {
+            let slug = Self::slugify(name);
+            if !slug.is_empty() && !slugs.contains(&slug) {
+                slugs.push(slug);
+                kept.push(name.trim().to_string());
+            }
+        }
+        // the update is a no-op that makes existing tags come back too
+        let query = format!(
+            "INSERT INTO {} (name, slug)
+                SELECT * FROM unnest($1::text[], $2::text[])
+                ON CONFLICT (slug) DO UPDATE SET slug = EXCLUDED.slug
+                RETURNING id;",
+            Self::table_name()
+        );
+        let ids = sqlx::query_scalar(&query)
+            .bind(&kept)
+            .bind(&slugs)
+            .fetch_all(&mut *conn)
+            .await?;
+        Ok(ids)
     }
-    fn bind_tables(
-        params: DBImageParams,
-        query: super::handles::QueryType<Self>,
-    ) -> super::handles::QueryType<Self> {
-        query
-            .bind(params.path)
-            .bind(params.alt)
-            .bind(params.subtitle)
+
+    /// Tags on at least one live row of `table`, for visitors to filter by
+    pub async fn used_by(table: &str, drafts: bool, db: impl DbConn) -> anyhow::Result<Vec<Self>> {
+        let mut conn = db.conn().await?;
+        let status = match drafts {
+            true => "TRUE",
+            false => "d.status = 'published'",
+        };
+        let query = format!(
+            "SELECT t.* FROM {} t
+                WHERE EXISTS (
+                    SELECT 1 FROM entity_tags et
+                        JOIN {} d ON d.id = et.entity_id
+                        WHERE et.tag_id = t.id AND et.entity_type = $1
+                          AND d.deleted_at IS NULL AND {}
+                )
+                ORDER BY t.name;",
+            Self::table_name(),
+            table,
+            status
+        );
+        let tags = sqlx::query_as::<_, Self>(&query)
+            .bind(table)
+            .fetch_all(&mut *conn)
+            .await?;
+        Ok(tags)
     }
-}
 
-impl DbData<DBAddressParams> for DBAddress {
-    fn id(&self) -> uuid::Uuid {
-        self.id
+    /// The tags of each of the rows of `table` with `ids`, by name. Rows
+    /// without tags are left out
+    pub async fn for_entities(
+        table: &str,
+        ids: &[Uuid],
+        db: impl DbConn,
+    ) -> anyhow::Result<std::collections::HashMap<Uuid, Vec<Self>>> {
+        let mut conn = db.conn().await?;
+        let query = format!(
+            "SELECT et.entity_id, t.* FROM entity_tags et
+                JOIN {} t ON t.id = et.tag_id
+                WHERE et.entity_type = $1 AND et.entity_id = ANY($2)
+                ORDER BY t.name;",
+            Self::table_name()
+        );
+        let rows = sqlx::query(&query)
+            .bind(table)
+            .bind(ids)
+            .fetch_all(&mut *conn)
+            .await?;
+        let mut tags = std::collections::HashMap::<Uuid, Vec<Self>>::new();
+        for row in rows {
+            tags.entry(row.try_get("entity_id")?)
+                .or_default()
+                .push(Self::from_row(&row)?);
+        }
+        Ok(tags)
     }
-    fn table_name() -> String {
-        "addresses".to_string()
+}
+
+impl DBDedication {
+    pub const DEATH: Column<Self> = Column::new("death", "date");
+}
+
+impl DBPatrolLog {
+    pub const DATE: Column<Self> = Column::new("date", "date");
+    pub const HEADING: Column<Self> = Column::new("heading", "text");
+}
+
+impl DBTestimonial {
+    pub const LASTNAME: Column<Self> = Column::new("lastname", "text");
+}
+
+impl DBResource {
+    /// Stores the address, or finds the one already stored, for the row to
+    /// point at
+    async fn resolve_address(
+        params: &mut DBResourceParams,
+        conn: &mut sqlx::PgConnection,
+    ) -> anyhow::Result<()> {
+        params.address_id = match params.address.take() {
+            Some(add) => Some(DBAddress::find_or_insert(add, conn).await?),
+            None => None,
+        };
+        Ok(())
     }
-    fn fields() -> Vec<String> {
-        vec!["city", "state", "zip", "line_1", "line_2"]
-            .iter()
-            .map(|s| s.to_string())
-            .collect()
+
+    pub const NAME: Column<Self> = Column::new("name", "text");
+
+    /// The resources `list` picks with their address and images, in one query
+    pub async fn get_page_with_address_and_images(
+        list: &ListQuery<Self>,
+        db: impl DbConn,
+    ) -> anyhow::Result<Page<(Self, Option<DBAddress>, Vec<DBImage>)>> {
+        let mut conn = db.conn().await?;
+        let sql = list.sql(2, &Self::table_name(), Self::soft_deletes());
+        let query = format!(
+            "SELECT d.*,
+                    CASE WHEN a.id IS NULL THEN NULL ELSE to_json(a.*) END AS address,
+                    imgs.images,
+                    {}
+                FROM {} d
+                LEFT JOIN {} a ON a.id = d.address_id
+                {}
+                {} {} {};",
+            sql.sort_value,
+            Self::table_name(),
+            DBAddress::table_name(),
+            DBImage::images_json_join(),
+            sql.where_clause,
+            sql.order_by,
+            sql.limit,
+        );
+        let mut q = sqlx::query(&query).bind(Self::table_name());
+        for value in sql.binds {
+            q = q.bind(value);
+        }
+        let rows = q.fetch_all(&mut *conn).await?;
+
+        let mut all = Vec::with_capacity(rows.len());
+        for row in rows {
+            let address = row.try_get::<Option<Json<DBAddress>>, _>("address")?;
+            let Json(images) = row.try_get::<Json<Vec<DBImage>>, _>("images")?;
+            let res = Self::from_row(&row)?;
+            let id = res.id;
+            all.push((
+                (res, address.map(|a| a.0), images),
+                row.try_get("sort_value")?,
+                id,
+            ));
+        }
+        Ok(list.page(all))
     }
-    fn bind_tables(
-        params: DBAddressParams,
-        query: super::handles::QueryType<Self>,
-    ) -> super::handles::QueryType<Self> {
-        query
-            .bind(params.city)
-            .bind(params.state)
-            .bind(params.zip)
-            .bind(params.line_1)
-            .bind(params.line_2)
+}
+
+impl DBBoardMember {
+    pub const POSITION: Column<Self> = Column::new("position", "int");
+    pub const ACTIVE: Column<Self> = Column::new("active", "boolean");
+}
+
+impl DBLandingSlide {
+    pub const POSITION: Column<Self> = Column::new("position", "int");
+    pub const ACTIVE: Column<Self> = Column::new("active", "boolean");
+}
+
+impl DBPatrolGear {
+    pub const POSITION: Column<Self> = Column::new("position", "int");
+    pub const ACTIVE: Column<Self> = Column::new("active", "boolean");
+
+    /// Whether any of it is left, gear without variants always is
+    pub fn in_stock(&self) -> bool {
+        self.variants.is_empty() || self.variants.iter().any(|v| v.stock > 0)
     }
 }
 
-impl DbData<DBDedicationParams> for DBDedication {
-    fn id(&self) -> uuid::Uuid {
-        self.id
-    }
-    fn table_name() -> String {
-        "dedications".to_string()
-    }
-    fn fields() -> Vec<String> {
-        vec!["names", "bio", "birth", "death", "img_ids"]
-            .iter()
-            .map(|s| s.to_string())
-            .collect()
-    }
-
-    fn take_images(params: &mut DBDedicationParams) -> Option<Vec<DBImageParams>> {
-        Some(params.img_params.drain(..).collect())
-    }
-
-    fn bind_tables(
-        params: DBDedicationParams,
-        query: super::handles::QueryType<Self>,
-    ) -> super::handles::QueryType<Self> {
-        query
-            .bind(params.names)
-            .bind(params.bio)
-            .bind(params.birth)
-            .bind(params.death)
-    }
-}
-
-impl DbData<DBPatrolLogParams> for DBPatrolLog {
-    fn id(&self) -> uuid::Uuid {
-        self.id
-    }
-    fn table_name() -> String {
-        "patrol_logs".to_string()
-    }
-    fn fields() -> Vec<String> {
-        vec!["heading", "description", "date", "img_ids"]
-            .iter()
-            .map(|s| s.to_string())
-            .collect()
-    }
-    fn take_images(params: &mut DBPatrolLogParams) -> Option<Vec<DBImageParams>> {
-        Some(params.img_params.drain(..).collect())
-    }
-    fn bind_tables(
-        params: DBPatrolLogParams,
-        query: super::handles::QueryType<Self>,
-    ) -> super::handles::QueryType<Self> {
-        query
-            .bind(params.heading)
-            .bind(params.description)
-            .bind(params.date)
-    }
-}
-
-impl DbData<DBTestimonialParams> for DBTestimonial {
-    fn id(&self) -> uuid::Uuid {
-        self.id
-    }
-    fn table_name() -> String {
-        "testimonials".to_string()
-    }
-    fn fields() -> Vec<String> {
-        vec!["firstname", "lastname", "bio", "content"]
-            .iter()
-            .map(|s| s.to_string())
-            .collect()
-    }
-    fn bind_tables(
-        params: DBTestimonialParams,
-        query: super::handles::QueryType<Self>,
-    ) -> super::handles::QueryType<Self> {
-        query
-            .bind(params.firstname)
-            .bind(params.lastname)
-            .bind(params.bio)
-            .bind(params.content)
-    }
-}
-
-impl DbData<DBResourceParams> for DBResource {
-    fn id(&self) -> uuid::Uuid {
-        self.id
-    }
-    fn table_name() -> String {
-        "support_resources".to_string()
-    }
-    fn fields() -> Vec<String> {
-        vec![
-            "name",
-            "description",
-            "missions",
-            "phone",
-            "email",
-            "twitter",
-            "facebook",
-            "youtube",
-            "linkedin",
-            "threads",
-            "instagram",
-        ]
-        .iter()
-        .map(|s| s.to_string())
-        .collect()
-    }
-    fn take_images(params: &mut DBResourceParams) -> Option<Vec<DBImageParams>> {
-        Some(params.img_params.drain(..).collect())
-    }
-
-    fn bind_tables(
-        params: DBResourceParams,
-        query: super::handles::QueryType<Self>,
-    ) -> super::handles::QueryType<Self> {
-        query
-            .bind(params.name)
-            .bind(params.description)
-            .bind(params.missions)
-            .bind(params.phone)
-            .bind(params.email)
-            .bind(params.twitter)
-            .bind(params.facebook)
-            .bind(params.youtube)
-            .bind(params.linkedin)
-            .bind(params.threads)
-            .bind(params.instagram)
-    }
-
-    async fn insert_one(
-        mut params: DBResourceParams,
+impl DBAdmin {
+    pub async fn get_by_username(
         pool: &sqlx::Pool<sqlx::Postgres>,
+        username: &str,
+    ) -> anyhow::Result<Option<Self>> {
+        let query = format!("SELECT * FROM {} WHERE username = $1;", Self::table_name());
+        let admin = sqlx::query_as::<_, Self>(&query)
+            .bind(username)
+            .fetch_optional(pool)
+            .await?;
+        Ok(admin)
+    }
+
+    pub async fn get_by_email(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        email: &str,
+    ) -> anyhow::Result<Option<Self>> {
+        let query = format!(
+            "SELECT * FROM {} WHERE email = LOWER($1);",
+            Self::table_name()
+        );
+        let admin = sqlx::query_as::<_, Self>(&query)
+            .bind(email)
+            .fetch_optional(pool)
+            .await?;
+        Ok(admin)
+    }
+
+    pub async fn get_all_ordered(pool: &sqlx::Pool<sqlx::Postgres>) -> anyhow::Result<Vec<Self>> {
+        let query = format!("SELECT * FROM {} ORDER BY created_at;", Self::table_name());
+        let all = sqlx::query_as::<_, Self>(&query).fetch_all(pool).await?;
+        Ok(all)
+    }
+
+    pub async fn count_active_owners(pool: &sqlx::Pool<sqlx::Postgres>) -> anyhow::Result<i64> {
+        let query = format!(
+            "SELECT COUNT(*) FROM {} WHERE disabled = FALSE AND role = 'owner';",
+            Self::table_name()
+        );
+        let count = sqlx::query_scalar(&query).fetch_one(pool).await?;
+        Ok(count)
+    }
+
+    pub async fn set_role(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+        role: Role,
     ) -> anyhow::Result<Self> {
-        let mut id_opt = Option::<Uuid>::None;
-        if let Some(add) = params.address.as_ref() {
-            warn!("expects address: {:?}", add);
-            let query = "
-                SELECT id
-                FROM addresses
-                WHERE city = $1
-                  AND state = $2
-                  AND zip = $3
-                  AND line_1 = $4
-                  AND (line_2 IS DISTINCT FROM $5);
-            ";
-
-            let state = add.state.to_owned();
-            let city = add.city.to_owned();
-            let zip = add.zip.to_owned();
-            let line_1 = add.line_1.to_owned();
-            let line_2 = add.line_2.to_owned();
-
-            id_opt = sqlx::query_scalar(query)
-                .bind(city.clone())
-                .bind(state.clone())
-                .bind(zip.clone())
-                .bind(line_1.clone())
-                .bind(line_2.clone())
-                .fetch_optional(pool)
-                .await?;
-
-            warn!("got id opt: {:?}", id_opt);
-
-            if id_opt.is_none() {
-                let dbadd = DBAddressParams {
-                    city,
-                    state,
-                    zip,
-                    line_1,
-                    line_2,
-                };
-
-                let add = DBAddress::insert_one(dbadd, pool).await?;
-                id_opt = Some(add.id);
-            }
-        }
+        let query = format!(
+            "UPDATE {} SET role = $1 WHERE id = $2 RETURNING *;",
+            Self::table_name()
+        );
+        let admin = sqlx::query_as::<_, Self>(&query)
+            .bind(role)
+            .bind(id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or(anyhow!("no admin with id: {}", id))?;
+        Ok(admin)
+    }
 
-        let mut imgs_ids = vec![];
-        if let Some(images) = Self::take_images(&mut params) {
-            for img in images {
-                let i = DBImage::insert_one(img, pool).await?;
-                imgs_ids.push(i.id);
-            }
-        }
-        // let query = DBImage::insert_query::<Self, DBResourceParams>();
-
-        // let q = sqlx::query_as::<_, Self>(&query);
-        // Self::bind_tables(params, q)
-        //     .bind(imgs_ids)
-        //     .fetch_all(pool)
-        //     .await?;
-
-        let query = format!(
-            "INSERT INTO {} (
-                    name,
-                    img_ids,
-                    description,
-                    missions,
-                    phone,
-                    email,
-                    twitter,
-                    facebook,
-                    youtube,
-                    linkedin,
-                    threads,
-                    instagram,
-                    address_id
-        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13) RETURNING *;",
-            Self::table_name(),
+    pub async fn count_active(pool: &sqlx::Pool<sqlx::Postgres>) -> anyhow::Result<i64> {
+        let query = format!(
+            "SELECT COUNT(*) FROM {} WHERE disabled = FALSE;",
+            Self::table_name()
+        );
+        let count = sqlx::query_scalar(&query).fetch_one(pool).await?;
+        Ok(count)
+    }
+
+    pub async fn set_disabled(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+        disabled: bool,
+    ) -> anyhow::Result<Self> {
+        let query = format!(
+            "UPDATE {} SET disabled = $1 WHERE id = $2 RETURNING *;",
+            Self::table_name()
+        );
+        let admin = sqlx::query_as::<_, Self>(&query)
+            .bind(disabled)
+            .bind(id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or(anyhow!("no admin with id: {}", id))?;
+        Ok(admin)
+    }
+
+    /// Stores a freshly generated secret that stays inactive until the admin
+    /// confirms it with a code, or clears it when `secret` is `None`
+    pub async fn set_totp(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+        secret: Option<String>,
+        enabled: bool,
+    ) -> anyhow::Result<Self> {
+        let query = format!(
+            "UPDATE {} SET totp_secret = $1, totp_enabled = $2, totp_last_step = NULL WHERE id = $3 RETURNING *;",
+            Self::table_name()
+        );
+        let admin = sqlx::query_as::<_, Self>(&query)
+            .bind(secret)
+            .bind(enabled)
+            .bind(id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or(anyhow!("no admin with id: {}", id))?;
+        Ok(admin)
+    }
+
+    /// Records the time step of an accepted code, returning false if that step
+    /// (or a later one) has already been used
+    pub async fn claim_totp_step(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+        step: i64,
+    ) -> anyhow::Result<bool> {
+        let query = format!(
+            "UPDATE {} SET totp_last_step = $1 WHERE id = $2 AND (totp_last_step IS NULL OR totp_last_step < $1);",
+            Self::table_name()
+        );
+        let result = sqlx::query(&query)
+            .bind(step)
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+impl DBSession {
+    /// Returns the session if it belongs to the admin and hasn't expired, marking
+    /// it as seen
+    pub async fn touch_valid(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+        admin_id: Uuid,
+    ) -> anyhow::Result<Option<Self>> {
+        let query = format!(
+            "UPDATE {} SET last_seen_at = NOW() WHERE id = $1 AND admin_id = $2 AND expires_at > NOW() RETURNING *;",
+            Self::table_name()
+        );
+        let session = sqlx::query_as::<_, Self>(&query)
+            .bind(id)
+            .bind(admin_id)
+            .fetch_optional(pool)
+            .await?;
+        Ok(session)
+    }
+
+    pub async fn get_active_for_admin(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        admin_id: Uuid,
+    ) -> anyhow::Result<Vec<Self>> {
+        let query = format!(
+            "SELECT * FROM {} WHERE admin_id = $1 AND expires_at > NOW() ORDER BY last_seen_at DESC;",
+            Self::table_name()
+        );
+        let all = sqlx::query_as::<_, Self>(&query)
+            .bind(admin_id)
+            .fetch_all(pool)
+            .await?;
+        Ok(all)
+    }
+
+    pub async fn delete_for_admin(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+        admin_id: Uuid,
+    ) -> anyhow::Result<Option<Self>> {
+        let query = format!(
+            "DELETE FROM {} WHERE id = $1 AND admin_id = $2 RETURNING *;",
+            Self::table_name()
+        );
+        let session = sqlx::query_as::<_, Self>(&query)
+            .bind(id)
+            .bind(admin_id)
+            .fetch_optional(pool)
+            .await?;
+        Ok(session)
+    }
+
+    pub async fn delete_all_for_admin(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        admin_id: Uuid,
+    ) -> anyhow::Result<Vec<Self>> {
+        let query = format!(
+            "DELETE FROM {} WHERE admin_id = $1 RETURNING *;",
+            Self::table_name()
+        );
+        let sessions = sqlx::query_as::<_, Self>(&query)
+            .bind(admin_id)
+            .fetch_all(pool)
+            .await?;
+        Ok(sessions)
+    }
+
+    pub async fn delete_expired(pool: &sqlx::Pool<sqlx::Postgres>) -> anyhow::Result<u64> {
+        let query = format!(
+            "DELETE FROM {} WHERE expires_at <= NOW();",
+            Self::table_name()
+        );
+        let result = sqlx::query(&query).execute(pool).await?;
+        Ok(result.rows_affected())
+    }
+}
+
+impl DBRecoveryCode {
+    /// Marks the matching unused code as spent, returning it if there was one
+    pub async fn consume(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        admin_id: Uuid,
+        code_hash: &str,
+    ) -> anyhow::Result<Option<Self>> {
+        let query = format!(
+            "UPDATE {} SET used_at = NOW() WHERE admin_id = $1 AND code_hash = $2 AND used_at IS NULL RETURNING *;",
+            Self::table_name()
+        );
+        let code = sqlx::query_as::<_, Self>(&query)
+            .bind(admin_id)
+            .bind(code_hash)
+            .fetch_optional(pool)
+            .await?;
+        Ok(code)
+    }
+
+    pub async fn count_unused(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        admin_id: Uuid,
+    ) -> anyhow::Result<i64> {
+        let query = format!(
+            "SELECT COUNT(*) FROM {} WHERE admin_id = $1 AND used_at IS NULL;",
+            Self::table_name()
+        );
+        let count = sqlx::query_scalar(&query)
+            .bind(admin_id)
+            .fetch_one(pool)
+            .await?;
+        Ok(count)
+    }
+
+    pub async fn delete_all_for_admin(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        admin_id: Uuid,
+    ) -> anyhow::Result<u64> {
+        let query = format!("DELETE FROM {} WHERE admin_id = $1;", Self::table_name());
+        let result = sqlx::query(&query).bind(admin_id).execute(pool).await?;
+        Ok(result.rows_affected())
+    }
+}
+
+impl DBPasskey {
+    pub async fn get_for_admin(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        admin_id: Uuid,
+    ) -> anyhow::Result<Vec<Self>> {
+        let query = format!(
+            "SELECT * FROM {} WHERE admin_id = $1 ORDER BY created_at;",
+            Self::table_name()
         );
+        let all = sqlx::query_as::<_, Self>(&query)
+            .bind(admin_id)
+            .fetch_all(pool)
+            .await?;
+        Ok(all)
+    }
 
-        let ret = sqlx::query_as::<_, Self>(&query)
-            .bind(params.name)
-            .bind(imgs_ids)
-            .bind(params.description)
-            .bind(params.missions)
-            .bind(params.phone)
-            .bind(params.email)
-            .bind(params.twitter)
-            .bind(params.facebook)
-            .bind(params.youtube)
-            .bind(params.linkedin)
-            .bind(params.threads)
-            .bind(params.instagram)
-            .bind(id_opt)
+    pub async fn exists_with_credential_id(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        credential_id: &str,
+    ) -> anyhow::Result<bool> {
+        let query = format!(
+            "SELECT EXISTS(SELECT 1 FROM {} WHERE credential_id = $1);",
+            Self::table_name()
+        );
+        let exists = sqlx::query_scalar(&query)
+            .bind(credential_id)
             .fetch_one(pool)
             .await?;
-        Ok(ret)
+        Ok(exists)
     }
 
-    async fn update_one(
-        params: DBResourceParams,
+    /// Saves the credential's new signature counter after a successful login
+    pub async fn record_use(
         pool: &sqlx::Pool<sqlx::Postgres>,
         id: Uuid,
+        passkey: Passkey,
     ) -> anyhow::Result<()> {
-        let mut id_opt = Option::<Uuid>::None;
-        if let Some(add) = params.address {
-            let query = "
-                SELECT id
-                FROM addresses
-                WHERE city = $1
-                  AND state = $2
-                  AND zip = $3
-                  AND line_1 = $4
-                  AND (line_2 IS DISTINCT FROM $5)
-            ";
-
-            id_opt = sqlx::query_scalar(query)
-                .bind(add.city.to_owned())
-                .bind(add.state.to_owned())
-                .bind(add.zip.to_owned())
-                .bind(add.line_1.to_owned())
-                .bind(add.line_2.to_owned())
-                .fetch_one(pool)
-                .await?;
-            if id_opt.is_none() {
-                let dbadd = DBAddressParams {
-                    city: add.city,
-                    state: add.state,
-                    zip: add.zip,
-                    line_1: add.line_1,
-                    line_2: add.line_2,
-                };
-
-                let add = DBAddress::insert_one(dbadd, pool).await?;
-                id_opt = Some(add.id);
-            }
+        let query = format!(
+            "UPDATE {} SET passkey = $1, last_used_at = NOW() WHERE id = $2;",
+            Self::table_name()
+        );
+        sqlx::query(&query)
+            .bind(Json(passkey))
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_for_admin(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+        admin_id: Uuid,
+    ) -> anyhow::Result<Option<Self>> {
+        let query = format!(
+            "DELETE FROM {} WHERE id = $1 AND admin_id = $2 RETURNING *;",
+            Self::table_name()
+        );
+        let passkey = sqlx::query_as::<_, Self>(&query)
+            .bind(id)
+            .bind(admin_id)
+            .fetch_optional(pool)
+            .await?;
+        Ok(passkey)
+    }
+}
+
+impl DBWebauthnChallenge {
+    /// Removes and returns the challenge so it can only be answered once
+    pub async fn take(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+        kind: &str,
+    ) -> anyhow::Result<Option<Self>> {
+        let query = format!(
+            "DELETE FROM {} WHERE id = $1 AND kind = $2 AND expires_at > NOW() RETURNING *;",
+            Self::table_name()
+        );
+        let challenge = sqlx::query_as::<_, Self>(&query)
+            .bind(id)
+            .bind(kind)
+            .fetch_optional(pool)
+            .await?;
+        Ok(challenge)
+    }
+
+    pub async fn delete_expired(pool: &sqlx::Pool<sqlx::Postgres>) -> anyhow::Result<u64> {
+        let query = format!(
+            "DELETE FROM {} WHERE expires_at <= NOW();",
+            Self::table_name()
+        );
+        let result = sqlx::query(&query).execute(pool).await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// Which side of a login attempt failures are counted against
+#[derive(Debug, Clone, Copy)]
+pub enum AttemptKey {
+    Username,
+    Ip,
+}
+
+impl AttemptKey {
+    fn column(&self) -> &'static str {
+        match self {
+            Self::Username => "username",
+            Self::Ip => "ip",
         }
+    }
+}
 
+impl DBLoginAttempt {
+    /// Failures for the username or ip since its last successful login, looking
+    /// back at most `window_seconds`, along with when the latest one happened
+    pub async fn recent_failures(
+        db: impl DbConn,
+        key: AttemptKey,
+        value: &str,
+        window_seconds: i64,
+    ) -> anyhow::Result<(i64, Option<DateTime<Utc>>)> {
+        let column = key.column();
         let query = format!(
-            "UPDATE {} (
-            name,
-            description,
-            missions,
-            phone,
-            email,
-            twitter,
-            facebook,
-            youtube,
-            linkedin,
-            threads,
-            instagram,
-            address_id,
-) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12) RETURNING *;",
-            Self::table_name(),
+            r#"
+SELECT COUNT(*), MAX(created_at) FROM {table}
+WHERE {column} = $1
+    AND succeeded = FALSE
+    AND created_at > NOW() - make_interval(secs => $2)
+    AND created_at > COALESCE(
+        (SELECT MAX(created_at) FROM {table} WHERE {column} = $1 AND succeeded = TRUE),
+        '-infinity'
+    );"#,
+            table = Self::table_name(),
+            column = column,
         );
-        let ret = sqlx::query_as::<_, Self>(&query)
-            .bind(params.name)
-            .bind(params.description)
-            .bind(params.missions)
-            .bind(params.phone)
-            .bind(params.email)
-            .bind(params.twitter)
-            .bind(params.facebook)
-            .bind(params.youtube)
-            .bind(params.linkedin)
-            .bind(params.threads)
-            .bind(params.instagram)
-            .bind(id_opt)
-            .fetch_one(pool)
+        let mut conn = db.conn().await?;
+        let failures = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>)>(&query)
+            .bind(value)
+            .bind(window_seconds as f64)
+            .fetch_one(&mut *conn)
+            .await?;
+        Ok(failures)
+    }
+
+    /// Holds off other attempts against the same username or ip until the
+    /// surrounding transaction ends
+    pub async fn lock(db: impl DbConn, key: AttemptKey, value: &str) -> anyhow::Result<()> {
+        let lock_key = format!("{}.{}:{}", Self::table_name(), key.column(), value);
+        let mut conn = db.conn().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1));")
+            .bind(lock_key)
+            .execute(&mut *conn)
             .await?;
         Ok(())
     }
+
+    /// Settles the outcome of an attempt recorded up front as a failure
+    pub async fn finish(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: uuid::Uuid,
+        succeeded: bool,
+    ) -> anyhow::Result<()> {
+        let query = format!(
+            "UPDATE {} SET succeeded = $2, created_at = NOW() WHERE id = $1;",
+            Self::table_name()
+        );
+        sqlx::query(&query)
+            .bind(id)
+            .bind(succeeded)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_recent_failures(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        limit: i64,
+    ) -> anyhow::Result<Vec<Self>> {
+        let query = format!(
+            "SELECT * FROM {} WHERE succeeded = FALSE ORDER BY created_at DESC LIMIT $1;",
+            Self::table_name()
+        );
+        let all = sqlx::query_as::<_, Self>(&query)
+            .bind(limit)
+            .fetch_all(pool)
+            .await?;
+        Ok(all)
+    }
+
+    pub async fn delete_before(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        before: DateTime<Utc>,
+    ) -> anyhow::Result<u64> {
+        let query = format!("DELETE FROM {} WHERE created_at < $1;", Self::table_name());
+        let result = sqlx::query(&query).bind(before).execute(pool).await?;
+        Ok(result.rows_affected())
+    }
+}
+
+impl DBApiToken {
+    /// Returns the unexpired token with this hash, marking it as used
+    pub async fn touch_valid(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        token_hash: &str,
+    ) -> anyhow::Result<Option<Self>> {
+        let query = format!(
+            "UPDATE {} SET last_used_at = NOW() WHERE token_hash = $1 AND expires_at > NOW() RETURNING *;",
+            Self::table_name()
+        );
+        let token = sqlx::query_as::<_, Self>(&query)
+            .bind(token_hash)
+            .fetch_optional(pool)
+            .await?;
+        Ok(token)
+    }
+
+    pub async fn get_for_admin(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        admin_id: Uuid,
+    ) -> anyhow::Result<Vec<Self>> {
+        let query = format!(
+            "SELECT * FROM {} WHERE admin_id = $1 ORDER BY created_at DESC;",
+            Self::table_name()
+        );
+        let all = sqlx::query_as::<_, Self>(&query)
+            .bind(admin_id)
+            .fetch_all(pool)
+            .await?;
+        Ok(all)
+    }
+
+    pub async fn delete_for_admin(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        id: Uuid,
+        admin_id: Uuid,
+    ) -> anyhow::Result<Option<Self>> {
+        let query = format!(
+            "DELETE FROM {} WHERE id = $1 AND admin_id = $2 RETURNING *;",
+            Self::table_name()
+        );
+        let token = sqlx::query_as::<_, Self>(&query)
+            .bind(id)
+            .bind(admin_id)
+            .fetch_optional(pool)
+            .await?;
+        Ok(token)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+impl DBAuditEvent {
+    /// Newest first
+    pub async fn search(
+        pool: &sqlx::Pool<sqlx::Postgres>,
+        filter: &AuditFilter,
+        limit: i64,
+    ) -> anyhow::Result<Vec<Self>> {
+        let query = format!(
+            r#"
+SELECT * FROM {}
+WHERE ($1::TEXT IS NULL OR actor_name = $1)
+    AND ($2::TEXT IS NULL OR item_type = $2)
+    AND ($3::TIMESTAMPTZ IS NULL OR created_at >= $3)
+    AND ($4::TIMESTAMPTZ IS NULL OR created_at < $4)
+ORDER BY created_at DESC
+LIMIT $5;"#,
+            Self::table_name()
+        );
+        let all = sqlx::query_as::<_, Self>(&query)
+            .bind(&filter.actor_name)
+            .bind(&filter.item_type)
+            .bind(filter.from)
+            .bind(filter.until)
+            .bind(limit)
+            .fetch_all(pool)
+            .await?;
+        Ok(all)
+    }
+
+    /// Everyone that has ever shown up in the log, for the actor filter
+    pub async fn actor_names(pool: &sqlx::Pool<sqlx::Postgres>) -> anyhow::Result<Vec<String>> {
+        let query = format!(
+            "SELECT DISTINCT actor_name FROM {} ORDER BY actor_name;",
+            Self::table_name()
+        );
+        let names = sqlx::query_scalar(&query).fetch_all(pool).await?;
+        Ok(names)
+    }
 }
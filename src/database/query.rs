@@ -0,0 +1,349 @@
+use anyhow::anyhow;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use std::marker::PhantomData;
+use uuid::Uuid;
+
+/// How many rows a page shows when nothing else is asked for
+pub const DEFAULT_PAGE_SIZE: i64 = 10;
+
+/// A column of `D` that lists can be sorted and filtered by. Values are bound
+/// as text and cast to `sql_type` in the query, sort columns can't be null
+pub struct Column<D> {
+    name: &'static str,
+    sql_type: &'static str,
+    model: PhantomData<fn() -> D>,
+}
+
+impl<D> Column<D> {
+    pub const fn new(name: &'static str, sql_type: &'static str) -> Self {
+        Self {
+            name,
+            sql_type,
+            model: PhantomData,
+        }
+    }
+}
+
+impl<D> Clone for Column<D> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<D> Copy for Column<D> {}
+
+impl<D> std::fmt::Debug for Column<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}::{}", self.name, self.sql_type)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Asc,
+    Desc,
+}
+
+impl Direction {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Asc => "ASC",
+            Self::Desc => "DESC",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Filter<D> {
+    Eq(Column<D>, String),
+    Range(Column<D>, Option<String>, Option<String>),
+    /// tagged with the tag that has this slug
+    Tag(String),
+}
+
+/// Where a page ended: the sort value and id of its last row, handed to the
+/// browser as an opaque token
+#[derive(Debug, Clone, PartialEq)]
+struct Cursor {
+    value: String,
+    id: Uuid,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}\n{}", self.id, self.value))
+    }
+
+    fn decode(token: &str) -> anyhow::Result<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(token)?;
+        let text = String::from_utf8(bytes)?;
+        let (id, value) = text
+            .split_once('\n')
+            .ok_or(anyhow!("malformed cursor: {}", token))?;
+        Ok(Self {
+            value: value.to_string(),
+            id: Uuid::parse_str(id)?,
+        })
+    }
+}
+
+/// Picks, orders and pages the rows of `D`. Rows are ordered by the sort
+/// column and then by id, which is also how a cursor finds where it left off
+#[derive(Debug)]
+pub struct ListQuery<D> {
+    sort: Column<D>,
+    direction: Direction,
+    filters: Vec<Filter<D>>,
+    after: Option<Cursor>,
+    limit: Option<i64>,
+    trashed: bool,
+}
+
+/// Rows of one page and the token for the one after it, `None` on the last
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next: Option<String>,
+}
+
+/// The parts of a query [`ListQuery`] fills in, for a table aliased as `d`
+pub(super) struct ListSql {
+    pub sort_value: String,
+    pub where_clause: String,
+    pub order_by: String,
+    pub limit: String,
+    pub binds: Vec<String>,
+}
+
+impl<D> Default for ListQuery<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D> ListQuery<D> {
+    /// Every row, oldest id first
+    pub fn new() -> Self {
+        Self {
+            sort: Column::new("id", "uuid"),
+            direction: Direction::Asc,
+            filters: vec![],
+            after: None,
+            limit: None,
+            trashed: false,
+        }
+    }
+
+    pub fn sort_by(mut self, column: Column<D>, direction: Direction) -> Self {
+        self.sort = column;
+        self.direction = direction;
+        self
+    }
+
+    pub fn filter_eq(mut self, column: Column<D>, value: impl ToString) -> Self {
+        self.filters.push(Filter::Eq(column, value.to_string()));
+        self
+    }
+
+    /// Keeps rows between `from` and `to`, both included. Either side can be
+    /// left open
+    pub fn filter_range(
+        mut self,
+        column: Column<D>,
+        from: Option<impl ToString>,
+        to: Option<impl ToString>,
+    ) -> Self {
+        self.filters.push(Filter::Range(
+            column,
+            from.map(|f| f.to_string()),
+            to.map(|t| t.to_string()),
+        ));
+        self
+    }
+
+    pub fn limit(mut self, limit: i64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Only rows in the trash, which are otherwise left out on tables that
+    /// soft delete
+    pub fn trashed(mut self) -> Self {
+        self.trashed = true;
+        self
+    }
+
+    /// Only the row with `id`, if there is one
+    pub fn only(self, id: Uuid) -> Self {
+        self.filter_eq(Column::new("id", "uuid"), id)
+    }
+
+    /// Only rows visitors can see, on tables with a `status` column
+    pub fn published(self) -> Self {
+        self.filter_eq(Column::new("status", "content_status"), "published")
+    }
+
+    /// Only rows tagged with the tag that has `slug`
+    pub fn tagged(mut self, slug: impl ToString) -> Self {
+        self.filters.push(Filter::Tag(slug.to_string()));
+        self
+    }
+
+    /// Starts after the page that handed out `token`, fails on tokens this
+    /// didn't make
+    pub fn after(mut self, token: Option<&str>) -> anyhow::Result<Self> {
+        self.after = match token {
+            Some(token) => Some(Cursor::decode(token)?),
+            None => None,
+        };
+        Ok(self)
+    }
+
+    /// Binds are numbered from `first_bind`, so callers can put their own
+    /// before them. `table` and `soft_deletes` are
+    /// [`super::handles::DbData::table_name`] and
+    /// [`super::handles::DbData::soft_deletes`]
+    pub(super) fn sql(&self, first_bind: usize, table: &str, soft_deletes: bool) -> ListSql {
+        let mut binds = vec![];
+        let bind = |value: &str, sql_type: &str, binds: &mut Vec<String>| {
+            binds.push(value.to_string());
+            format!("${}::{}", first_bind + binds.len() - 1, sql_type)
+        };
+
+        let mut conditions = vec![];
+        if soft_deletes {
+            conditions.push(match self.trashed {
+                true => "d.deleted_at IS NOT NULL".to_string(),
+                false => "d.deleted_at IS NULL".to_string(),
+            });
+        }
+        for filter in self.filters.iter() {
+            match filter {
+                Filter::Eq(column, value) => {
+                    let placeholder = bind(value, column.sql_type, &mut binds);
+                    conditions.push(format!("d.{} = {}", column.name, placeholder));
+                }
+                Filter::Range(column, from, to) => {
+                    if let Some(from) = from {
+                        let placeholder = bind(from, column.sql_type, &mut binds);
+                        conditions.push(format!("d.{} >= {}", column.name, placeholder));
+                    }
+                    if let Some(to) = to {
+                        let placeholder = bind(to, column.sql_type, &mut binds);
+                        conditions.push(format!("d.{} <= {}", column.name, placeholder));
+                    }
+                }
+                Filter::Tag(slug) => {
+                    let placeholder = bind(slug, "text", &mut binds);
+                    conditions.push(format!(
+                        "EXISTS (SELECT 1 FROM entity_tags et
+                            JOIN tags t ON t.id = et.tag_id
+                            WHERE et.entity_type = '{}' AND et.entity_id = d.id
+                              AND t.slug = {})",
+                        table, placeholder
+                    ));
+                }
+            }
+        }
+        if let Some(cursor) = &self.after {
+            let value = bind(&cursor.value, self.sort.sql_type, &mut binds);
+            let id = bind(&cursor.id.to_string(), "uuid", &mut binds);
+            let op = match self.direction {
+                Direction::Asc => ">",
+                Direction::Desc => "<",
+            };
+            conditions.push(format!(
+                "(d.{}, d.id) {} ({}, {})",
+                self.sort.name, op, value, id
+            ));
+        }
+
+        let where_clause = match conditions.is_empty() {
+            true => String::new(),
+            false => format!("WHERE {}", conditions.join(" AND ")),
+        };
+        let direction = self.direction.as_sql();
+        ListSql {
+            sort_value: format!("d.{}::text AS sort_value", self.sort.name),
+            where_clause,
+            order_by: format!(
+                "ORDER BY d.{} {}, d.id {}",
+                self.sort.name, direction, direction
+            ),
+            // one extra row tells whether there's a next page
+            limit: match self.limit {
+                Some(limit) => format!("LIMIT {}", limit + 1),
+                None => String::new(),
+            },
+            binds,
+        }
+    }
+
+    /// Turns rows fetched with [`ListQuery::sql`], each with its sort value
+    /// and id, into a page
+    pub(super) fn page<T>(&self, mut rows: Vec<(T, String, Uuid)>) -> Page<T> {
+        let mut next = None;
+        if let Some(limit) = self.limit {
+            if rows.len() as i64 > limit {
+                rows.truncate(limit as usize);
+                next = rows.last().map(|(_, value, id)| {
+                    Cursor {
+                        value: value.clone(),
+                        id: *id,
+                    }
+                    .encode()
+                });
+            }
+        }
+        Page {
+            items: rows.into_iter().map(|(item, _, _)| item).collect(),
+            next,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Column, Cursor, Direction, ListQuery};
+    use uuid::Uuid;
+
+    struct Log;
+    const DATE: Column<Log> = Column::new("date", "date");
+    const HEADING: Column<Log> = Column::new("heading", "text");
+
+    #[test]
+    fn cursors_round_trip_and_reject_garbage() {
+        let cursor = Cursor {
+            value: "2024-01-02".to_string(),
+            id: Uuid::new_v4(),
+        };
+        assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert!(Cursor::decode("not a cursor").is_err());
+    }
+
+    #[test]
+    fn filters_and_cursor_bind_in_order() {
+        let first = ListQuery::<Log>::new()
+            .sort_by(DATE, Direction::Desc)
+            .limit(2)
+            .page(vec![
+                ((), "2024-03-01".to_string(), Uuid::new_v4()),
+                ((), "2024-02-01".to_string(), Uuid::new_v4()),
+                ((), "2024-01-01".to_string(), Uuid::new_v4()),
+            ]);
+        assert_eq!(first.items.len(), 2);
+
+        let sql = ListQuery::<Log>::new()
+            .sort_by(DATE, Direction::Desc)
+            .filter_eq(HEADING, "Fishing")
+            .filter_range(DATE, Some("2024-01-01"), None::<String>)
+            .after(first.next.as_deref())
+            .unwrap()
+            .sql(2, "patrol_logs", true)
+            .where_clause;
+        assert_eq!(
+            sql,
+            "WHERE d.deleted_at IS NULL AND d.heading = $2::text AND d.date >= $3::date \
+             AND (d.date, d.id) < ($4::date, $5::uuid)"
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use uuid::Uuid;
+
+use super::models::DBRevision;
+
+/// Keys of a snapshot that aren't content
+const IGNORED_FIELDS: [&str; 2] = ["id", "deleted_at"];
+
+/// A field that differs between two revisions, with both values as shown to
+/// admins
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+impl DBRevision {
+    /// What changed from `older` to this revision, field by field. The images
+    /// count as one `images` field, named through `image_names`
+    pub fn changes_since(
+        &self,
+        older: &DBRevision,
+        image_names: &HashMap<Uuid, String>,
+    ) -> Vec<FieldChange> {
+        let empty = serde_json::Map::new();
+        let before = older.snapshot.0.as_object().unwrap_or(&empty);
+        let after = self.snapshot.0.as_object().unwrap_or(&empty);
+
+        let fields: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+        let mut changes: Vec<FieldChange> = fields
+            .into_iter()
+            .filter(|field| !IGNORED_FIELDS.contains(&field.as_str()))
+            .filter_map(|field| {
+                let old = before.get(field).unwrap_or(&Value::Null);
+                let new = after.get(field).unwrap_or(&Value::Null);
+                (old != new).then(|| FieldChange {
+                    field: field.clone(),
+                    before: display(old),
+                    after: display(new),
+                })
+            })
+            .collect();
+
+        if older.image_ids != self.image_ids {
+            let names = |ids: &[Uuid]| {
+                ids.iter()
+                    .map(|id| image_names.get(id).cloned().unwrap_or(id.to_string()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            changes.push(FieldChange {
+                field: "images".to_string(),
+                before: names(&older.image_ids),
+                after: names(&self.image_ids),
+            });
+        }
+        changes
+    }
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(values) => values.iter().map(display).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use serde_json::json;
+    use sqlx::types::Json;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    use super::{DBRevision, FieldChange};
+
+    fn revision(snapshot: serde_json::Value, image_ids: Vec<Uuid>) -> DBRevision {
+        DBRevision {
+            id: Uuid::new_v4(),
+            entity_type: "dedications".to_string(),
+            entity_id: Uuid::new_v4(),
+            snapshot: Json(snapshot),
+            image_ids,
+            author_id: None,
+            author_name: "admin".to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn only_changed_fields_show_up() {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        let older = revision(
+            json!({"id": "1", "names": ["Jo"], "bio": "same", "deleted_at": null}),
+            vec![a],
+        );
+        let newer = revision(
+            json!({"id": "2", "names": ["Jo", "Sam"], "bio": "same", "deleted_at": "2024-01-01"}),
+            vec![a, b],
+        );
+        let names = HashMap::from([(a, "a.webp".to_string())]);
+
+        assert_eq!(
+            newer.changes_since(&older, &names),
+            [
+                FieldChange {
+                    field: "names".to_string(),
+                    before: "Jo".to_string(),
+                    after: "Jo, Sam".to_string(),
+                },
+                FieldChange {
+                    field: "images".to_string(),
+                    before: "a.webp".to_string(),
+                    after: format!("a.webp, {}", b),
+                },
+            ]
+        );
+        assert!(older.changes_since(&older, &names).is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::{
+    handles::{DbConn, DbData},
+    models::{DBDedication, DBPatrolLog, DBResource, DBTestimonial},
+};
+
+/// Marks the matched words in a snippet. They're stripped from the text before
+/// it's marked, so every one left can be found again after escaping
+const MATCH_START: char = '\u{1}';
+const MATCH_END: char = '\u{2}';
+
+/// A table with a generated `search` tsvector column. `TITLE` and `TEXT` are
+/// SQL on the row `d`: what a hit is called and what its snippet comes from
+pub trait Searchable {
+    const TITLE: &'static str;
+    const TEXT: &'static str;
+}
+
+impl Searchable for DBPatrolLog {
+    const TITLE: &'static str = "d.heading";
+    const TEXT: &'static str = "d.description";
+}
+
+impl Searchable for DBDedication {
+    const TITLE: &'static str = "array_to_string(d.names, ', ')";
+    const TEXT: &'static str = "d.bio";
+}
+
+impl Searchable for DBTestimonial {
+    const TITLE: &'static str = "d.firstname || ' ' || d.lastname";
+    const TEXT: &'static str = "d.content";
+}
+
+impl Searchable for DBResource {
+    const TITLE: &'static str = "d.name";
+    const TEXT: &'static str = "concat_ws(' ', d.description, array_to_string(d.missions, ', '))";
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct SearchHit {
+    pub id: Uuid,
+    pub title: String,
+    /// Both plain text with the matches between [`MATCH_START`] and
+    /// [`MATCH_END`], see [`highlight`]
+    pub marked_title: String,
+    pub snippet: String,
+    pub rank: f32,
+}
+
+/// A marked up title or snippet as html, escaped and with the matches in
+/// `<mark>`s
+pub fn highlight(marked: &str) -> String {
+    let mut html = String::with_capacity(marked.len());
+    for c in marked.chars() {
+        match c {
+            MATCH_START => html.push_str("<mark>"),
+            MATCH_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#x27;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+/// The best `limit` rows of `D` for `terms`, which take the syntax of web
+/// search boxes. Trashed rows never show up, the ones that aren't live yet
+/// only with `drafts`
+pub async fn search<D, P>(
+    terms: &str,
+    drafts: bool,
+    limit: i64,
+    db: impl DbConn,
+) -> anyhow::Result<Vec<SearchHit>>
+where
+    P: std::fmt::Debug + Serialize + for<'de> Deserialize<'de> + Send + Unpin,
+    D: DbData<P> + Searchable,
+{
+    let mut conn = db.conn().await?;
+    let status = match drafts {
+        true => "TRUE",
+        false => "d.status = 'published'",
+    };
+    let marks = format!("StartSel={}, StopSel={}", MATCH_START, MATCH_END);
+    let unmarked = |sql: &str| format!("translate({}, E'\\x01\\x02', '')", sql);
+    // uploads store line breaks as html, which shouldn't end up in snippets
+    let query = format!(
+        "SELECT d.id, {title} AS title,
+            ts_headline('english', {marked_title}, q, '{marks}, HighlightAll=true')
+                AS marked_title,
+            ts_headline(
+                'english',
+                regexp_replace(regexp_replace({text}, '<[^>]*>', ' ', 'g'), '\\s+', ' ', 'g'),
+                q,
+                '{marks}, MaxFragments=2, MaxWords=25, MinWords=10'
+            ) AS snippet,
+            ts_rank(d.search, q) AS rank
+        FROM {table} d, websearch_to_tsquery('english', $1) q
+        WHERE d.search @@ q AND {not_trashed} AND {status}
+        ORDER BY rank DESC, d.id
+        LIMIT $2;",
+        title = D::TITLE,
+        marked_title = unmarked(D::TITLE),
+        text = unmarked(D::TEXT),
+        table = D::table_name(),
+        not_trashed = D::not_trashed(),
+        status = status,
+    );
+    let hits = sqlx::query_as::<_, SearchHit>(&query)
+        .bind(terms)
+        .bind(limit)
+        .fetch_all(&mut *conn)
+        .await?;
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{highlight, MATCH_END, MATCH_START};
+
+    #[test]
+    fn snippets_are_escaped_around_the_marks() {
+        let snippet = format!(
+            "<b>Corporal</b> {}Mileo{} & \"friends\"",
+            MATCH_START, MATCH_END
+        );
+        assert_eq!(
+            highlight(&snippet),
+            "&lt;b&gt;Corporal&lt;/b&gt; <mark>Mileo</mark> &amp; &quot;friends&quot;"
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, Pool, Postgres};
+use std::path::Path;
+use tracing::info;
+
+use super::{
+    handles::DbData,
+    models::{
+        DBBoardMember, DBBoardMemberParams, DBDedication, DBDedicationParams, DBLandingSlide,
+        DBLandingSlideParams, DBPatrolGear, DBPatrolGearParams, DBPatrolLog, DBPatrolLogParams,
+        DBResource, DBResourceParams, DBTestimonial, DBTestimonialParams,
+    },
+};
+
+/// Where the seed files are. Each is a JSON array named after the table its
+/// entries go into, `patrol_logs.json` and so on
+pub const SEEDS_DIRECTORY: &str = "seeds";
+
+/// One entry of a seed file: the params of a row, and the key that tells
+/// whether it was loaded before. Keys are never reused, so changing an entry
+/// that was already loaded does nothing, a new key loads it as a new row
+#[derive(Debug, Deserialize)]
+struct Seed<P> {
+    key: String,
+    #[serde(flatten)]
+    params: P,
+}
+
+/// Loads the seed file entries that weren't loaded before, all or nothing.
+/// Rows an admin deleted since aren't brought back
+pub async fn seed(pool: &Pool<Postgres>) -> anyhow::Result<()> {
+    let mut tx = pool.begin().await?;
+    let loaded = seed_all(Path::new(SEEDS_DIRECTORY), &mut tx).await?;
+    tx.commit().await?;
+    if loaded > 0 {
+        info!("loaded {} seed entries", loaded);
+    }
+    Ok(())
+}
+
+/// Returns how many entries were loaded
+pub async fn seed_all(directory: &Path, conn: &mut PgConnection) -> anyhow::Result<usize> {
+    Ok(
+        seed_table::<DBTestimonial, DBTestimonialParams>(directory, conn).await?
+            + seed_table::<DBDedication, DBDedicationParams>(directory, conn).await?
+            + seed_table::<DBPatrolLog, DBPatrolLogParams>(directory, conn).await?
+            + seed_table::<DBResource, DBResourceParams>(directory, conn).await?
+            + seed_table::<DBBoardMember, DBBoardMemberParams>(directory, conn).await?
+            + seed_table::<DBLandingSlide, DBLandingSlideParams>(directory, conn).await?
+            + seed_table::<DBPatrolGear, DBPatrolGearParams>(directory, conn).await?,
+    )
+}
+
+fn read_seeds<P>(directory: &Path, table: &str) -> anyhow::Result<Vec<Seed<P>>>
+where
+    P: for<'de> Deserialize<'de>,
+{
+    let path = directory.join(format!("{}.json", table));
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let json = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&json).map_err(|err| anyhow!("{}: {}", path.display(), err))
+}
+
+async fn seed_table<D, P>(directory: &Path, conn: &mut PgConnection) -> anyhow::Result<usize>
+where
+    P: std::fmt::Debug + Serialize + for<'de> Deserialize<'de> + Send + Unpin,
+    D: DbData<P>,
+{
+    let mut loaded = 0;
+    for seed in read_seeds::<P>(directory, &D::table_name())? {
+        let seeded: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM seeds WHERE entity_type = $1 AND key = $2);",
+        )
+        .bind(D::table_name())
+        .bind(&seed.key)
+        .fetch_one(&mut *conn)
+        .await?;
+        if seeded {
+            continue;
+        }
+        let row = D::insert_one(seed.params, &mut *conn).await?;
+        sqlx::query("INSERT INTO seeds (entity_type, key, entity_id) VALUES ($1, $2, $3);")
+            .bind(D::table_name())
+            .bind(&seed.key)
+            .bind(row.id())
+            .execute(&mut *conn)
+            .await?;
+        loaded += 1;
+    }
+    Ok(loaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{read_seeds, SEEDS_DIRECTORY};
+    use crate::database::models::{
+        DBBoardMemberParams, DBDedicationParams, DBLandingSlideParams, DBPatrolGearParams,
+        DBPatrolLogParams, DBResourceParams, DBTestimonialParams,
+    };
+
+    #[test]
+    fn seed_files_parse() {
+        let dir = Path::new(SEEDS_DIRECTORY);
+        assert!(!read_seeds::<DBTestimonialParams>(dir, "testimonials")
+            .unwrap()
+            .is_empty());
+        assert!(!read_seeds::<DBDedicationParams>(dir, "dedications")
+            .unwrap()
+            .is_empty());
+        assert!(!read_seeds::<DBPatrolLogParams>(dir, "patrol_logs")
+            .unwrap()
+            .is_empty());
+        assert!(!read_seeds::<DBResourceParams>(dir, "support_resources")
+            .unwrap()
+            .is_empty());
+        assert!(!read_seeds::<DBBoardMemberParams>(dir, "board_members")
+            .unwrap()
+            .is_empty());
+        assert!(!read_seeds::<DBLandingSlideParams>(dir, "landing_slides")
+            .unwrap()
+            .is_empty());
+        assert!(!read_seeds::<DBPatrolGearParams>(dir, "patrol_gear")
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -92,6 +92,20 @@ async fn main() {
             .expect("failed to migrate database");
     }
 
+    if let Err(err) = auth::bootstrap_admin(&pool).await {
+        tracing::error!("🔥 Failed to bootstrap admin account: {:?}", err);
+    }
+
+    if let Err(err) = database::seeds::seed(&pool).await {
+        tracing::error!("🔥 Failed to load the seed files: {:?}", err);
+    }
+
+    tokio::spawn(auth::handlers::trash::purge_expired_task(
+        pool.clone(),
+        app_config.trash_retention,
+    ));
+    tokio::spawn(auth::handlers::upload::publish_scheduled_task(pool.clone()));
+
     let cors = CorsLayer::new()
         .allow_origin(allowed_origin.parse::<HeaderValue>().unwrap())
         .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE])
@@ -100,7 +114,6 @@ async fn main() {
 
     let app = routes::create_router(Arc::new(RwLock::new(AppState {
         db: pool.clone(),
-        admin_session_id: None,
         env: app_config.clone(),
     })))
     .layer(cors);
@@ -112,13 +125,18 @@ async fn main() {
                 .unwrap();
 
             tracing::debug!("listening on {listener:#?}");
-            axum::serve(listener, app).await.unwrap();
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .unwrap();
         }
         PROD_ENV => {
             let addr = SocketAddr::from(([0, 0, 0, 0], ports.https));
             tracing::debug!("listening on {}", addr);
             axum_server::bind_rustls(addr, cert_config)
-                .serve(app.into_make_service())
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
                 .await
                 .unwrap();
         }
@@ -4,11 +4,31 @@ pub mod pages;
 use crate::{
     auth::{
         handlers::{
+            admins::{
+                change_role_handler, create_admin_handler, disable_admin_handler,
+                enable_admin_handler, remove_admin_handler,
+            },
+            api_tokens::{create_api_token_handler, revoke_api_token_handler},
             delete::delete_item_handler,
+            edit::edit_item_handler,
             login_admin_handler, logout_handler,
+            oidc::{oidc_callback_handler, oidc_login_handler},
+            reorder::reorder_item_handler,
+            revisions::rollback_item_handler,
+            sessions::{logout_everywhere_handler, revoke_session_handler},
+            totp::{
+                confirm_totp_handler, disable_totp_handler, login_totp_handler,
+                regenerate_recovery_codes_handler, setup_totp_handler,
+            },
+            trash::{purge_item_handler, restore_item_handler},
             upload::{upload_form_handler, upload_multipart_handler},
+            webauthn::{
+                login_finish_handler, login_start_handler, register_finish_handler,
+                register_start_handler, remove_passkey_handler,
+            },
         },
-        middleware::{admin_auth, soft_auth},
+        middleware::{admin_auth, csrf_check, require_permission, session_only, soft_auth},
+        permissions::{Action, RequiredPermission},
     },
     state::SharedState,
 };
@@ -16,47 +36,184 @@ use axum::{
     extract::DefaultBodyLimit,
     http::StatusCode,
     middleware::{self},
-    routing::{delete, get, post},
-    Router,
+    routing::{delete, get, patch, post, MethodRouter},
+    Extension, Router,
 };
 use middlware::htmx_request_check;
 use tower_http::services::ServeDir;
 
 pub type HandlerResult<T> = Result<T, StatusCode>;
 
+/// Gates a route behind a permission, enforced by [`require_permission`] once
+/// [`admin_auth`] has identified the admin
+fn required_permission(
+    method_router: MethodRouter<SharedState>,
+    permission: RequiredPermission,
+) -> MethodRouter<SharedState> {
+    method_router
+        .route_layer(middleware::from_fn(require_permission))
+        .route_layer(Extension(permission))
+}
+
 #[tracing::instrument(name = "create app router", skip_all)]
 pub fn create_router(state: SharedState) -> Router {
     let admin_routes = Router::new()
         .route("/upload", get(pages::admin::upload))
+        .route("/edit/:item/:id", get(pages::admin::edit))
         // .route_layer(middleware::from_fn_with_state(state.clone(), admin_auth))
         .route("/status", get(pages::admin::login_logout))
+        .route("/accounts", get(pages::admin::accounts))
+        .route("/sessions", get(pages::admin::sessions))
+        .route("/security", get(pages::admin::security))
+        .route("/api_tokens", get(pages::admin::api_tokens))
+        .route("/audit", get(pages::admin::audit))
+        .route("/trash", get(pages::admin::trash))
+        .route("/landing_slides", get(pages::admin::landing_slides))
+        .route("/revisions/:item/:id", get(pages::admin::revisions))
         .route_layer(middleware::from_fn_with_state(state.clone(), soft_auth));
 
-    let data_routes = Router::new()
+    // everything that manages the admin's own account or other admins needs a
+    // browser session, API tokens only reach the content routes
+    let account_routes = Router::new()
         .route("/auth/logout", post(logout_handler))
-        .route("/auth/upload_form/:item", post(upload_form_handler))
+        .route("/auth/logout_everywhere", post(logout_everywhere_handler))
+        .route("/auth/sessions/:id", delete(revoke_session_handler))
+        .route("/auth/totp/setup", post(setup_totp_handler))
+        .route("/auth/totp/confirm", post(confirm_totp_handler))
+        .route(
+            "/auth/totp/recovery_codes",
+            post(regenerate_recovery_codes_handler),
+        )
+        .route("/auth/totp/disable", post(disable_totp_handler))
+        .route(
+            "/auth/webauthn/register/start",
+            post(register_start_handler),
+        )
+        .route(
+            "/auth/webauthn/register/finish",
+            post(register_finish_handler),
+        )
+        .route(
+            "/auth/webauthn/passkeys/:id",
+            delete(remove_passkey_handler),
+        )
+        .route(
+            "/auth/admins",
+            required_permission(post(create_admin_handler), RequiredPermission::ManageAdmins),
+        )
+        .route(
+            "/auth/admins/:id/disable",
+            required_permission(
+                post(disable_admin_handler),
+                RequiredPermission::ManageAdmins,
+            ),
+        )
+        .route(
+            "/auth/admins/:id/enable",
+            required_permission(post(enable_admin_handler), RequiredPermission::ManageAdmins),
+        )
+        .route(
+            "/auth/admins/:id/role",
+            required_permission(post(change_role_handler), RequiredPermission::ManageAdmins),
+        )
+        .route(
+            "/auth/admins/:id",
+            required_permission(
+                delete(remove_admin_handler),
+                RequiredPermission::ManageAdmins,
+            ),
+        )
+        .route("/auth/api_tokens", post(create_api_token_handler))
+        .route("/auth/api_tokens/:id", delete(revoke_api_token_handler))
+        .route_layer(middleware::from_fn(session_only));
+
+    let content_routes = Router::new()
+        .route(
+            "/auth/upload_form/:item",
+            required_permission(
+                post(upload_form_handler),
+                RequiredPermission::Content(Action::Upload),
+            ),
+        )
         .route(
             "/auth/upload_multipart/:item",
-            post(upload_multipart_handler),
+            required_permission(
+                post(upload_multipart_handler),
+                RequiredPermission::Content(Action::Upload),
+            ),
+        )
+        .route(
+            "/auth/edit/:item/:id",
+            required_permission(
+                patch(edit_item_handler),
+                RequiredPermission::Content(Action::Edit),
+            ),
         )
-        .route("/auth/delete/:item/:id", delete(delete_item_handler))
+        .route(
+            "/auth/delete/:item/:id",
+            required_permission(
+                delete(delete_item_handler),
+                RequiredPermission::Content(Action::Delete),
+            ),
+        )
+        .route(
+            "/auth/rollback/:item/:id/:revision_id",
+            required_permission(
+                post(rollback_item_handler),
+                RequiredPermission::Content(Action::Edit),
+            ),
+        )
+        .route(
+            "/auth/reorder/:item/:id",
+            required_permission(
+                post(reorder_item_handler),
+                RequiredPermission::Content(Action::Edit),
+            ),
+        )
+        .route(
+            "/auth/restore/:item/:id",
+            required_permission(
+                post(restore_item_handler),
+                RequiredPermission::Content(Action::Delete),
+            ),
+        )
+        .route(
+            "/auth/purge/:item/:id",
+            required_permission(
+                delete(purge_item_handler),
+                RequiredPermission::Content(Action::Delete),
+            ),
+        );
+
+    let data_routes = account_routes
+        .merge(content_routes)
         .route_layer(DefaultBodyLimit::disable())
+        .route_layer(middleware::from_fn(csrf_check))
         .route_layer(middleware::from_fn_with_state(state.clone(), admin_auth))
-        .route("/auth/login", post(login_admin_handler));
+        .route("/auth/login", post(login_admin_handler))
+        .route("/auth/login/totp", post(login_totp_handler))
+        .route("/auth/webauthn/login/start", post(login_start_handler))
+        .route("/auth/webauthn/login/finish", post(login_finish_handler))
+        .route("/auth/oidc/login", get(oidc_login_handler))
+        .route("/auth/oidc/callback", get(oidc_callback_handler));
 
-    Router::new()
+    // soft_auth looks up the session, so it runs once over all of these
+    let page_routes = Router::new()
         .route("/", get(index::index))
         .route("/landing", get(pages::landing::landing))
         .route("/about_us", get(pages::about_us::about_us))
         .route("/support", get(pages::support::support))
-        .layer(middleware::from_fn_with_state(state.clone(), soft_auth))
         .route("/patrol_gear", get(pages::patrol_gear::patrol_gear))
         .route("/patrol_log", get(pages::patrol_log::logs::patrol_log))
-        .layer(middleware::from_fn_with_state(state.clone(), soft_auth))
         .route("/dedications", get(pages::dedications::dedications))
-        .layer(middleware::from_fn_with_state(state.clone(), soft_auth))
         .route("/debriefs", get(pages::debriefs::debriefs))
-        .layer(middleware::from_fn_with_state(state.clone(), soft_auth))
+        .route("/search", get(pages::search::search))
+        .route("/search/results", get(pages::search::search_results))
+        .route("/tags/:slug", get(pages::tags::tag))
+        .route_layer(middleware::from_fn_with_state(state.clone(), soft_auth));
+
+    Router::new()
+        .merge(page_routes)
         .route("/videos", get(pages::patrol_log::videos::videos))
         .nest("/admin", admin_routes)
         .layer(middleware::from_fn(htmx_request_check))
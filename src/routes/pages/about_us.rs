@@ -1,56 +1,82 @@
 use askama::Template;
-use axum::response::Html;
+use axum::{extract::State, response::Html, Extension};
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    auth::middleware::SoftAuthExtension,
+    database::{
+        models::{DBBoardMember, DBBoardMemberParams, DBImage},
+        query::{Direction, ListQuery},
+    },
+    state::SharedState,
+};
+
+pub const BOARD_MEMBERS: &str = "board_members";
 
 #[derive(Debug)]
 struct BoardMember {
-    image_url: String,
+    id: uuid::Uuid,
+    /// `None` until a photo is uploaded
+    image_url: Option<String>,
     name: String,
     role: String,
     description: String,
+    /// Set on members visitors don't see
+    badge: Option<String>,
+}
+
+impl From<(DBBoardMember, Vec<DBImage>)> for BoardMember {
+    fn from((member, images): (DBBoardMember, Vec<DBImage>)) -> Self {
+        Self {
+            id: member.id,
+            image_url: images.into_iter().next().map(|img| img.path),
+            badge: (!member.active).then(|| "Hidden".to_string()),
+            name: member.name,
+            role: member.role,
+            description: member.bio,
+        }
+    }
 }
 
-fn board_members() -> Vec<BoardMember> {
-    vec![
-        BoardMember {
-            name: "Jamie Guajardo".to_string(),
-            role: "Founder & Board Member".to_string(),
-            description: r#"
-            In 2015, after battling Post Traumatic Stress Disorder & Traumatic Brain Injury for twelve years without seeking help he finally walked through the doors of a Veterans Administration Hospital for the first time.
-            <br/>
-            <br/>
-            In 2019, the severity of his PTSD and TBI eventually resulted in his stay at a residential Veterans Administration PTSD Hospital in Colorado where he worked with the best doctors in the nation.
-            To this day Jamie continues to struggle daily, but through years of gathering knowledge he has made a decision to save his life, and as many other Veterans as he possibly can.
-            <br/>
-            <br/>
-            During his stay at the residential treatment hospital in Colorado, Jamie learned how to tie fishing flys as a form of “grounding.” He enjoyed this project and vowed to himself
-            that when he got out he was going to create a platform called Semper Flies and use it to raise awareness for Veterans that struggle with PTSD & TBI, and that’s exactly what he did.
-            "#.to_string(),
-            image_url: "public/assets/images/board_members/jamie.webp".to_string(),
-        },
-        BoardMember {
-            name: "Beverly L. Martin-Ornelas".to_string(),
-            role: "Board Member".to_string(),
-            description: "This is a board member".to_string(),
-            image_url: "public/assets/images/board_members/beverly.webp".to_string(),
-        },
-        BoardMember {
-            name: "Dan Ornelas".to_string(),
-            role: "Board Member".to_string(),
-            description: "This is a board member".to_string(),
-            image_url: "public/assets/images/board_members/dan.webp".to_string(),
-        },
-    ]
+/// Inactive members are only listed for admins
+async fn board_members(pool: &Pool<Postgres>, admin: bool) -> anyhow::Result<Vec<BoardMember>> {
+    let mut list = ListQuery::new().sort_by(DBBoardMember::POSITION, Direction::Asc);
+    if !admin {
+        list = list.filter_eq(DBBoardMember::ACTIVE, true);
+    }
+    let page =
+        DBImage::get_page_with_images::<DBBoardMember, DBBoardMemberParams>(&list, pool).await?;
+    Ok(page.items.into_iter().map(BoardMember::from).collect())
 }
 
 #[derive(Template, Debug)]
 #[template(path = "pages/about_us.html")]
 pub struct AboutUsTemplate {
     board_members: Vec<BoardMember>,
+    admin: bool,
+    csrf_token: String,
 }
 
-pub async fn about_us() -> Html<String> {
+impl AboutUsTemplate {
+    fn is_last(&self, index: &usize) -> bool {
+        *index == self.board_members.len()
+    }
+}
+
+#[tracing::instrument(name = "about us page", skip_all)]
+pub async fn about_us(
+    State(data): State<SharedState>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+) -> Html<String> {
+    let r = data.read().await;
+    let board_members = match board_members(&r.db, soft_auth_ext.is_logged_in).await {
+        Ok(members) => members,
+        Err(err) => return Html(format!("A database error occured: {:?}", err)),
+    };
     let tmpl = AboutUsTemplate {
-        board_members: board_members(),
+        board_members,
+        admin: soft_auth_ext.is_logged_in,
+        csrf_token: soft_auth_ext.csrf_token.clone().unwrap_or_default(),
     };
     match tmpl.render() {
         Ok(r) => Html(r),
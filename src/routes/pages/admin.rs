@@ -1,19 +1,54 @@
-use askama::Template;
-use axum::{response::Html, Extension};
+use askama::{MarkupDisplay, Template};
+use axum::{
+    extract::{Path, Query, State},
+    response::Html,
+    Extension,
+};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::Deserialize;
+use sqlx::{Pool, Postgres};
+use std::collections::HashMap;
 use tracing::warn;
+use uuid::Uuid;
 
-use crate::auth::middleware::SoftAuthExtension;
+use crate::{
+    auth::{
+        audit::SESSION_ITEM,
+        handlers::upload::{GeneralItem, UploadFormItemType, UploadMultipartItemType},
+        middleware::SoftAuthExtension,
+        permissions::{Action, Permission, Role, Scope},
+    },
+    database::{
+        handles::DbData,
+        models::{
+            AuditFilter, ContentStatus, DBAddress, DBAdmin, DBApiToken, DBAuditEvent,
+            DBBoardMember, DBDedication, DBImage, DBLandingSlide, DBLandingSlideParams,
+            DBLoginAttempt, DBPasskey, DBPatrolGear, DBPatrolLog, DBRecoveryCode, DBResource,
+            DBRevision, DBSession, DBTag, DBTestimonial,
+        },
+        query::{Direction, ListQuery},
+        revisions::FieldChange,
+    },
+    state::SharedState,
+};
 
 #[derive(Template, Debug)]
 #[template(path = "admin/login_logout.html")]
 pub struct LoginLogoutTemplate {
     logged_in: bool,
+    csrf_token: String,
+    sso_enabled: bool,
 }
 
 #[tracing::instrument(name = "login logout", skip_all)]
-pub async fn login_logout(Extension(soft_auth_ext): Extension<SoftAuthExtension>) -> Html<String> {
+pub async fn login_logout(
+    State(data): State<SharedState>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+) -> Html<String> {
     let tmpl = LoginLogoutTemplate {
         logged_in: soft_auth_ext.is_logged_in,
+        csrf_token: soft_auth_ext.csrf_token.clone().unwrap_or_default(),
+        sso_enabled: data.read().await.env.oidc.is_some(),
     };
     warn!("admin is logged in: {}", tmpl.logged_in);
     match tmpl.render() {
@@ -26,11 +61,26 @@ pub async fn login_logout(Extension(soft_auth_ext): Extension<SoftAuthExtension>
 #[template(path = "admin/upload.html")]
 pub struct UploadTemplate {
     logged_in: bool,
+    csrf_token: String,
+    role: Option<Role>,
+    prefill: FormPrefill,
+}
+
+impl UploadTemplate {
+    fn can_upload(&self, item: &str) -> bool {
+        match (self.role, GeneralItem::try_from(item)) {
+            (Some(role), Ok(item)) => role.allows(&Permission::Content(Action::Upload, item)),
+            _ => false,
+        }
+    }
 }
 
 pub async fn upload(Extension(soft_auth_ext): Extension<SoftAuthExtension>) -> Html<String> {
     let tmpl = UploadTemplate {
         logged_in: soft_auth_ext.is_logged_in,
+        csrf_token: soft_auth_ext.csrf_token.clone().unwrap_or_default(),
+        role: soft_auth_ext.admin.map(|a| a.role),
+        prefill: FormPrefill::default(),
         // logged_in: true,
     };
     match tmpl.render() {
@@ -38,3 +88,911 @@ pub async fn upload(Extension(soft_auth_ext): Extension<SoftAuthExtension>) -> H
         Err(err) => Html(format!("Error rendering Layout: {}", err.to_string())),
     }
 }
+
+/// What the forms in `admin/forms` start out filled with. Empty when uploading,
+/// when editing it holds the stored item and switches the form to a PATCH
+#[derive(Debug, Default)]
+pub struct FormPrefill {
+    id: Option<Uuid>,
+    values: HashMap<&'static str, String>,
+    lists: HashMap<&'static str, Vec<String>>,
+}
+
+impl FormPrefill {
+    fn value(&self, name: &str) -> &str {
+        self.values
+            .get(name)
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    fn list(&self, name: &str) -> &[String] {
+        self.lists.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    fn set(&mut self, name: &'static str, value: Option<&str>) {
+        if let Some(value) = value {
+            // uploads store line breaks as html
+            self.values.insert(name, value.replace("<br/>", "\n"));
+        }
+    }
+
+    /// Tag names the way the tags box takes them
+    fn set_tags(&mut self, tags: &[DBTag]) {
+        let names: Vec<&str> = tags.iter().map(|tag| tag.name.as_str()).collect();
+        self.set("tags", Some(&names.join(", ")));
+    }
+
+    /// `publish_at` in the format `datetime-local` inputs take
+    fn set_publishing(&mut self, status: ContentStatus, publish_at: Option<DateTime<Utc>>) {
+        self.set("status", Some(status.as_str()));
+        self.set(
+            "publish_at",
+            publish_at
+                .map(|at| at.format("%Y-%m-%dT%H:%M").to_string())
+                .as_deref(),
+        );
+    }
+
+    fn from_testimonial(test: &DBTestimonial) -> Self {
+        let mut prefill = Self {
+            id: Some(test.id),
+            ..Default::default()
+        };
+        prefill.set("firstname", Some(&test.firstname));
+        prefill.set("lastname", Some(&test.lastname));
+        prefill.set("bio", test.bio.as_deref());
+        prefill.set("content", Some(&test.content));
+        prefill.set_publishing(test.status, test.publish_at);
+        prefill
+    }
+
+    fn from_patrol_log(log: &DBPatrolLog, tags: &[DBTag]) -> Self {
+        let mut prefill = Self {
+            id: Some(log.id),
+            ..Default::default()
+        };
+        prefill.set("heading", Some(&log.heading));
+        prefill.set("date", Some(&log.date.to_string()));
+        prefill.set("description", Some(&log.description));
+        prefill.set_publishing(log.status, log.publish_at);
+        prefill.set_tags(tags);
+        prefill
+    }
+
+    fn from_dedication(ded: &DBDedication) -> Self {
+        let mut prefill = Self {
+            id: Some(ded.id),
+            ..Default::default()
+        };
+        prefill.lists.insert("names", ded.names.clone());
+        prefill.set("birth", Some(&ded.birth.to_string()));
+        prefill.set("death", Some(&ded.death.to_string()));
+        prefill.set("bio", Some(&ded.bio));
+        prefill.set_publishing(ded.status, ded.publish_at);
+        prefill
+    }
+
+    fn from_resource(res: &DBResource, address: Option<&DBAddress>, tags: &[DBTag]) -> Self {
+        let mut prefill = Self {
+            id: Some(res.id),
+            ..Default::default()
+        };
+        prefill.lists.insert("missions", res.missions.clone());
+        prefill.set("name", Some(&res.name));
+        prefill.set("description", Some(&res.description));
+        prefill.set("phone", res.phone.as_deref());
+        prefill.set("email", res.email.as_deref());
+        prefill.set("website", res.website_url.as_deref());
+        prefill.set("instagram", res.instagram.as_deref());
+        prefill.set("facebook", res.facebook.as_deref());
+        prefill.set("youtube", res.youtube.as_deref());
+        prefill.set("linkedin", res.linkedin.as_deref());
+        prefill.set("threads", res.threads.as_deref());
+        prefill.set("twitter", res.twitter.as_deref());
+        prefill.set_publishing(res.status, res.publish_at);
+        prefill.set_tags(tags);
+        if let Some(add) = address {
+            prefill.set("city", Some(&add.city));
+            prefill.set("state", Some(&add.state));
+            prefill.set("zip", Some(&add.zip));
+            prefill.set("line1", Some(&add.line_1));
+            prefill.set("line2", add.line_2.as_deref());
+        }
+        prefill
+    }
+
+    fn from_board_member(member: &DBBoardMember) -> Self {
+        let mut prefill = Self {
+            id: Some(member.id),
+            ..Default::default()
+        };
+        prefill.set("name", Some(&member.name));
+        prefill.set("role", Some(&member.role));
+        prefill.set("bio", Some(&member.bio));
+        // the form ticks the checkbox when there's a value
+        prefill.set("active", member.active.then_some("on"));
+        prefill
+    }
+
+    fn from_landing_slide(slide: &DBLandingSlide) -> Self {
+        let mut prefill = Self {
+            id: Some(slide.id),
+            ..Default::default()
+        };
+        prefill.set("alt", Some(&slide.alt));
+        prefill.set("subtitle", Some(&slide.subtitle));
+        prefill.set("active", slide.active.then_some("on"));
+        prefill
+    }
+
+    fn from_patrol_gear(gear: &DBPatrolGear) -> Self {
+        let mut prefill = Self {
+            id: Some(gear.id),
+            ..Default::default()
+        };
+        prefill.set("name", Some(&gear.name));
+        prefill.set("category", Some(gear.category.as_str()));
+        prefill.set("price", Some(&gear.price.to_string()));
+        prefill.set("description", Some(&gear.description));
+        prefill.set("active", gear.active.then_some("on"));
+        // the variants list of the form starts from these
+        prefill.set(
+            "variants",
+            serde_json::to_string(&gear.variants.0).ok().as_deref(),
+        );
+        prefill
+    }
+}
+
+#[derive(Template, Debug)]
+#[template(path = "admin/edit.html")]
+pub struct EditTemplate {
+    logged_in: bool,
+    csrf_token: String,
+    can_edit: bool,
+    item: String,
+    /// `None` when nothing with the id exists
+    prefill: Option<FormPrefill>,
+}
+
+async fn load_prefill(
+    pool: &Pool<Postgres>,
+    item: GeneralItem,
+    id: Uuid,
+) -> anyhow::Result<Option<FormPrefill>> {
+    let prefill = match item {
+        GeneralItem::Form(UploadFormItemType::Debriefs) => DBTestimonial::get_single_by(pool, id)
+            .await?
+            .map(|test| FormPrefill::from_testimonial(&test)),
+        GeneralItem::Multi(UploadMultipartItemType::PatrolLog) => {
+            match DBPatrolLog::get_single_by(pool, id).await? {
+                Some(log) => {
+                    let tags = DBPatrolLog::tags(id, pool).await?;
+                    Some(FormPrefill::from_patrol_log(&log, &tags))
+                }
+                None => None,
+            }
+        }
+        GeneralItem::Multi(UploadMultipartItemType::Dedications) => {
+            DBDedication::get_single_by(pool, id)
+                .await?
+                .map(|ded| FormPrefill::from_dedication(&ded))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::Support) => {
+            match DBResource::get_single_by(pool, id).await? {
+                Some(res) => {
+                    let address = match res.address_id {
+                        Some(address_id) => DBAddress::get_single_by(pool, address_id).await?,
+                        None => None,
+                    };
+                    let tags = DBResource::tags(id, pool).await?;
+                    Some(FormPrefill::from_resource(&res, address.as_ref(), &tags))
+                }
+                None => None,
+            }
+        }
+        GeneralItem::Multi(UploadMultipartItemType::BoardMembers) => {
+            DBBoardMember::get_single_by(pool, id)
+                .await?
+                .map(|member| FormPrefill::from_board_member(&member))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::LandingSlides) => {
+            DBLandingSlide::get_single_by(pool, id)
+                .await?
+                .map(|slide| FormPrefill::from_landing_slide(&slide))
+        }
+        GeneralItem::Multi(UploadMultipartItemType::PatrolGear) => {
+            DBPatrolGear::get_single_by(pool, id)
+                .await?
+                .map(|gear| FormPrefill::from_patrol_gear(&gear))
+        }
+    };
+    Ok(prefill)
+}
+
+#[tracing::instrument(name = "admin edit", skip(data, soft_auth_ext))]
+pub async fn edit(
+    State(data): State<SharedState>,
+    Path((item_str, id)): Path<(String, Uuid)>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+) -> Html<String> {
+    let item = match GeneralItem::try_from(item_str.as_str()) {
+        Ok(item) => item,
+        // the error repeats the item straight from the url
+        Err(err) => return Html(MarkupDisplay::new_unsafe(err, askama::Html).to_string()),
+    };
+    let can_edit = soft_auth_ext
+        .admin
+        .as_ref()
+        .is_some_and(|a| a.role.allows(&Permission::Content(Action::Edit, item)));
+    let prefill = match can_edit {
+        true => match load_prefill(&data.read().await.db, item, id).await {
+            Ok(prefill) => prefill,
+            Err(err) => return Html(format!("A database error occured: {:?}", err)),
+        },
+        false => None,
+    };
+    let tmpl = EditTemplate {
+        logged_in: soft_auth_ext.is_logged_in,
+        csrf_token: soft_auth_ext.csrf_token.clone().unwrap_or_default(),
+        can_edit,
+        item: item.as_str().to_string(),
+        prefill,
+    };
+    match tmpl.render() {
+        Ok(r) => Html(r),
+        Err(err) => Html(format!("Error rendering Layout: {}", err)),
+    }
+}
+
+/// How many failed logins the accounts page shows owners
+const RECENT_FAILURES: i64 = 50;
+
+#[derive(Template, Debug)]
+#[template(path = "admin/accounts.html")]
+pub struct AccountsTemplate {
+    logged_in: bool,
+    csrf_token: String,
+    current_admin_id: Option<uuid::Uuid>,
+    can_manage: bool,
+    roles: Vec<Role>,
+    admins: Vec<DBAdmin>,
+    failed_logins: Vec<DBLoginAttempt>,
+}
+
+impl AccountsTemplate {
+    fn is_current(&self, id: &uuid::Uuid) -> bool {
+        self.current_admin_id.as_ref() == Some(id)
+    }
+}
+
+#[tracing::instrument(name = "admin accounts", skip_all)]
+pub async fn accounts(
+    State(data): State<SharedState>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+) -> Html<String> {
+    let admins = match soft_auth_ext.is_logged_in {
+        true => match DBAdmin::get_all_ordered(&data.read().await.db).await {
+            Ok(admins) => admins,
+            Err(err) => return Html(format!("A database error occured: {:?}", err)),
+        },
+        false => vec![],
+    };
+    let can_manage = soft_auth_ext
+        .admin
+        .as_ref()
+        .is_some_and(|a| a.role.allows(&Permission::ManageAdmins));
+    let failed_logins = match can_manage {
+        true => match DBLoginAttempt::get_recent_failures(&data.read().await.db, RECENT_FAILURES)
+            .await
+        {
+            Ok(attempts) => attempts,
+            Err(err) => return Html(format!("A database error occured: {:?}", err)),
+        },
+        false => vec![],
+    };
+    let tmpl = AccountsTemplate {
+        logged_in: soft_auth_ext.is_logged_in,
+        csrf_token: soft_auth_ext.csrf_token.clone().unwrap_or_default(),
+        can_manage,
+        roles: Role::all(),
+        current_admin_id: soft_auth_ext.admin.map(|a| a.id),
+        admins,
+        failed_logins,
+    };
+    match tmpl.render() {
+        Ok(r) => Html(r),
+        Err(err) => Html(format!("Error rendering Layout: {}", err)),
+    }
+}
+
+#[derive(Template, Debug)]
+#[template(path = "admin/sessions.html")]
+pub struct SessionsTemplate {
+    logged_in: bool,
+    csrf_token: String,
+    current_session_id: Option<uuid::Uuid>,
+    sessions: Vec<DBSession>,
+}
+
+impl SessionsTemplate {
+    fn is_current(&self, id: &uuid::Uuid) -> bool {
+        self.current_session_id.as_ref() == Some(id)
+    }
+}
+
+#[tracing::instrument(name = "admin sessions", skip_all)]
+pub async fn sessions(
+    State(data): State<SharedState>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+) -> Html<String> {
+    let sessions = match soft_auth_ext.admin.as_ref() {
+        Some(admin) => match DBSession::get_active_for_admin(&data.read().await.db, admin.id).await
+        {
+            Ok(sessions) => sessions,
+            Err(err) => return Html(format!("A database error occured: {:?}", err)),
+        },
+        None => vec![],
+    };
+    let tmpl = SessionsTemplate {
+        logged_in: soft_auth_ext.is_logged_in,
+        csrf_token: soft_auth_ext.csrf_token.clone().unwrap_or_default(),
+        current_session_id: soft_auth_ext.session_id,
+        sessions,
+    };
+    match tmpl.render() {
+        Ok(r) => Html(r),
+        Err(err) => Html(format!("Error rendering Layout: {}", err)),
+    }
+}
+
+#[derive(Template, Debug)]
+#[template(path = "admin/security.html")]
+pub struct SecurityTemplate {
+    logged_in: bool,
+    csrf_token: String,
+    totp_enabled: bool,
+    recovery_codes_left: i64,
+    passkeys: Vec<DBPasskey>,
+}
+
+#[tracing::instrument(name = "admin security", skip_all)]
+pub async fn security(
+    State(data): State<SharedState>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+) -> Html<String> {
+    let totp_enabled = soft_auth_ext.admin.as_ref().is_some_and(|a| a.totp_enabled);
+    let recovery_codes_left = match soft_auth_ext.admin.as_ref() {
+        Some(admin) if totp_enabled => {
+            match DBRecoveryCode::count_unused(&data.read().await.db, admin.id).await {
+                Ok(count) => count,
+                Err(err) => return Html(format!("A database error occured: {:?}", err)),
+            }
+        }
+        _ => 0,
+    };
+    let passkeys = match soft_auth_ext.admin.as_ref() {
+        Some(admin) => match DBPasskey::get_for_admin(&data.read().await.db, admin.id).await {
+            Ok(passkeys) => passkeys,
+            Err(err) => return Html(format!("A database error occured: {:?}", err)),
+        },
+        None => vec![],
+    };
+    let tmpl = SecurityTemplate {
+        logged_in: soft_auth_ext.is_logged_in,
+        csrf_token: soft_auth_ext.csrf_token.clone().unwrap_or_default(),
+        totp_enabled,
+        recovery_codes_left,
+        passkeys,
+    };
+    match tmpl.render() {
+        Ok(r) => Html(r),
+        Err(err) => Html(format!("Error rendering Layout: {}", err)),
+    }
+}
+
+#[derive(Template, Debug)]
+#[template(path = "admin/api_tokens.html")]
+pub struct ApiTokensTemplate {
+    logged_in: bool,
+    csrf_token: String,
+    /// every `action:item` scope the admin's role allows
+    available_scopes: Vec<String>,
+    tokens: Vec<DBApiToken>,
+}
+
+#[tracing::instrument(name = "admin api tokens", skip_all)]
+pub async fn api_tokens(
+    State(data): State<SharedState>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+) -> Html<String> {
+    let tokens = match soft_auth_ext.admin.as_ref() {
+        Some(admin) => match DBApiToken::get_for_admin(&data.read().await.db, admin.id).await {
+            Ok(tokens) => tokens,
+            Err(err) => return Html(format!("A database error occured: {:?}", err)),
+        },
+        None => vec![],
+    };
+    let available_scopes = match soft_auth_ext.admin.as_ref() {
+        Some(admin) => Action::all()
+            .into_iter()
+            .flat_map(|action| {
+                GeneralItem::all()
+                    .into_iter()
+                    .filter(move |item| admin.role.allows(&Permission::Content(action, *item)))
+                    .map(move |item| {
+                        Scope {
+                            action,
+                            item: Some(item),
+                        }
+                        .to_string()
+                    })
+            })
+            .collect(),
+        None => vec![],
+    };
+    let tmpl = ApiTokensTemplate {
+        logged_in: soft_auth_ext.is_logged_in,
+        csrf_token: soft_auth_ext.csrf_token.clone().unwrap_or_default(),
+        available_scopes,
+        tokens,
+    };
+    match tmpl.render() {
+        Ok(r) => Html(r),
+        Err(err) => Html(format!("Error rendering Layout: {}", err)),
+    }
+}
+
+/// How many events the audit page shows at once
+const AUDIT_PAGE_SIZE: i64 = 200;
+
+/// Filters from the audit page's form, blank fields come through as empty strings
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    #[serde(default)]
+    actor: String,
+    #[serde(default)]
+    item_type: String,
+    /// `YYYY-MM-DD`, inclusive
+    #[serde(default)]
+    from: String,
+    /// `YYYY-MM-DD`, inclusive
+    #[serde(default)]
+    to: String,
+}
+
+impl AuditQuery {
+    fn date(value: &str) -> Option<NaiveDate> {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+    }
+
+    fn filter(&self) -> AuditFilter {
+        let non_empty = |s: &str| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+        let midnight = |d: NaiveDate| Utc.from_utc_datetime(&d.and_hms_opt(0, 0, 0).unwrap());
+        AuditFilter {
+            actor_name: non_empty(&self.actor),
+            item_type: non_empty(&self.item_type),
+            from: Self::date(&self.from).map(midnight),
+            until: Self::date(&self.to)
+                .and_then(|d| d.succ_opt())
+                .map(midnight),
+        }
+    }
+}
+
+#[derive(Template, Debug)]
+#[template(path = "admin/audit.html")]
+pub struct AuditTemplate {
+    logged_in: bool,
+    can_view: bool,
+    query: AuditQuery,
+    actors: Vec<String>,
+    item_types: Vec<String>,
+    events: Vec<DBAuditEvent>,
+}
+
+impl AuditTemplate {
+    fn pretty_snapshot(&self, event: &DBAuditEvent) -> String {
+        event
+            .snapshot
+            .as_ref()
+            .and_then(|s| serde_json::to_string_pretty(&s.0).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[tracing::instrument(name = "admin audit", skip_all)]
+pub async fn audit(
+    State(data): State<SharedState>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+    query: Option<Query<AuditQuery>>,
+) -> Html<String> {
+    let query = query.map(|Query(q)| q).unwrap_or_default();
+    let can_view = soft_auth_ext
+        .admin
+        .as_ref()
+        .is_some_and(|a| a.role.allows(&Permission::ManageAdmins));
+    let (actors, events) = match can_view {
+        true => {
+            let r = data.read().await;
+            let actors = DBAuditEvent::actor_names(&r.db).await;
+            let events = DBAuditEvent::search(&r.db, &query.filter(), AUDIT_PAGE_SIZE).await;
+            match (actors, events) {
+                (Ok(actors), Ok(events)) => (actors, events),
+                (Err(err), _) | (_, Err(err)) => {
+                    return Html(format!("A database error occured: {:?}", err))
+                }
+            }
+        }
+        false => (vec![], vec![]),
+    };
+    let item_types = GeneralItem::all()
+        .iter()
+        .map(|i| i.as_str())
+        .chain([SESSION_ITEM])
+        .map(|i| i.to_string())
+        .collect();
+    let tmpl = AuditTemplate {
+        logged_in: soft_auth_ext.is_logged_in,
+        can_view,
+        query,
+        actors,
+        item_types,
+        events,
+    };
+    match tmpl.render() {
+        Ok(r) => Html(r),
+        Err(err) => Html(format!("Error rendering Layout: {}", err)),
+    }
+}
+
+/// An item in the trash as the trash page lists it
+#[derive(Debug)]
+pub struct TrashEntry {
+    item: &'static str,
+    id: Uuid,
+    title: String,
+    deleted_at: DateTime<Utc>,
+    /// when the purge task deletes it for good
+    purge_at: DateTime<Utc>,
+}
+
+#[derive(Template, Debug)]
+#[template(path = "admin/trash.html")]
+pub struct TrashTemplate {
+    logged_in: bool,
+    csrf_token: String,
+    retention_days: i64,
+    entries: Vec<TrashEntry>,
+}
+
+/// Trashed items of the types `role` can delete, most recently deleted first
+async fn load_trash(
+    pool: &Pool<Postgres>,
+    role: Role,
+    retention: chrono::Duration,
+) -> anyhow::Result<Vec<TrashEntry>> {
+    let mut entries = vec![];
+    for item in GeneralItem::all() {
+        if !role.allows(&Permission::Content(Action::Delete, item)) {
+            continue;
+        }
+        let rows: Vec<(Uuid, String, Option<DateTime<Utc>>)> = match item {
+            GeneralItem::Form(UploadFormItemType::Debriefs) => {
+                DBTestimonial::get_page(&ListQuery::new().trashed(), pool)
+                    .await?
+                    .items
+                    .into_iter()
+                    .map(|t| {
+                        (
+                            t.id,
+                            format!("{} {}", t.firstname, t.lastname),
+                            t.deleted_at,
+                        )
+                    })
+                    .collect()
+            }
+            GeneralItem::Multi(UploadMultipartItemType::PatrolLog) => {
+                DBPatrolLog::get_page(&ListQuery::new().trashed(), pool)
+                    .await?
+                    .items
+                    .into_iter()
+                    .map(|l| (l.id, l.heading, l.deleted_at))
+                    .collect()
+            }
+            GeneralItem::Multi(UploadMultipartItemType::Dedications) => {
+                DBDedication::get_page(&ListQuery::new().trashed(), pool)
+                    .await?
+                    .items
+                    .into_iter()
+                    .map(|d| (d.id, d.names.join(", "), d.deleted_at))
+                    .collect()
+            }
+            GeneralItem::Multi(UploadMultipartItemType::Support) => {
+                DBResource::get_page(&ListQuery::new().trashed(), pool)
+                    .await?
+                    .items
+                    .into_iter()
+                    .map(|r| (r.id, r.name, r.deleted_at))
+                    .collect()
+            }
+            GeneralItem::Multi(UploadMultipartItemType::BoardMembers) => {
+                DBBoardMember::get_page(&ListQuery::new().trashed(), pool)
+                    .await?
+                    .items
+                    .into_iter()
+                    .map(|m| (m.id, m.name, m.deleted_at))
+                    .collect()
+            }
+            GeneralItem::Multi(UploadMultipartItemType::LandingSlides) => {
+                DBLandingSlide::get_page(&ListQuery::new().trashed(), pool)
+                    .await?
+                    .items
+                    .into_iter()
+                    .map(|s| (s.id, s.alt, s.deleted_at))
+                    .collect()
+            }
+            GeneralItem::Multi(UploadMultipartItemType::PatrolGear) => {
+                DBPatrolGear::get_page(&ListQuery::new().trashed(), pool)
+                    .await?
+                    .items
+                    .into_iter()
+                    .map(|g| (g.id, g.name, g.deleted_at))
+                    .collect()
+            }
+        };
+        entries.extend(rows.into_iter().filter_map(|(id, title, deleted_at)| {
+            let deleted_at = deleted_at?;
+            Some(TrashEntry {
+                item: item.as_str(),
+                id,
+                title,
+                deleted_at,
+                purge_at: deleted_at + retention,
+            })
+        }));
+    }
+    entries.sort_by_key(|e| std::cmp::Reverse(e.deleted_at));
+    Ok(entries)
+}
+
+#[tracing::instrument(name = "admin trash", skip_all)]
+pub async fn trash(
+    State(data): State<SharedState>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+) -> Html<String> {
+    let r = data.read().await;
+    let entries = match soft_auth_ext.admin.as_ref() {
+        Some(admin) => match load_trash(&r.db, admin.role, r.env.trash_retention).await {
+            Ok(entries) => entries,
+            Err(err) => return Html(format!("A database error occured: {:?}", err)),
+        },
+        None => vec![],
+    };
+    let tmpl = TrashTemplate {
+        logged_in: soft_auth_ext.is_logged_in,
+        csrf_token: soft_auth_ext.csrf_token.clone().unwrap_or_default(),
+        retention_days: r.env.trash_retention.num_days(),
+        entries,
+    };
+    match tmpl.render() {
+        Ok(r) => Html(r),
+        Err(err) => Html(format!("Error rendering Layout: {}", err)),
+    }
+}
+
+#[derive(Debug)]
+pub struct SlideEntry {
+    id: Uuid,
+    /// `None` when the slide has no image, the landing page skips it
+    src: Option<String>,
+    alt: String,
+    subtitle: String,
+    active: bool,
+}
+
+impl From<(DBLandingSlide, Vec<DBImage>)> for SlideEntry {
+    fn from((slide, images): (DBLandingSlide, Vec<DBImage>)) -> Self {
+        Self {
+            id: slide.id,
+            src: images.into_iter().next().map(|img| img.path),
+            alt: slide.alt,
+            subtitle: slide.subtitle,
+            active: slide.active,
+        }
+    }
+}
+
+#[derive(Template, Debug)]
+#[template(path = "admin/landing_slides.html")]
+pub struct LandingSlidesTemplate {
+    logged_in: bool,
+    csrf_token: String,
+    /// whether the slides can be dragged around, edited and deleted
+    can_edit: bool,
+    slides: Vec<SlideEntry>,
+}
+
+#[tracing::instrument(name = "admin landing slides", skip_all)]
+pub async fn landing_slides(
+    State(data): State<SharedState>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+) -> Html<String> {
+    let slides = match soft_auth_ext.is_logged_in {
+        true => {
+            let list = ListQuery::new().sort_by(DBLandingSlide::POSITION, Direction::Asc);
+            match DBImage::get_page_with_images::<DBLandingSlide, DBLandingSlideParams>(
+                &list,
+                &data.read().await.db,
+            )
+            .await
+            {
+                Ok(page) => page.items.into_iter().map(SlideEntry::from).collect(),
+                Err(err) => return Html(format!("A database error occured: {:?}", err)),
+            }
+        }
+        false => vec![],
+    };
+    let can_edit = soft_auth_ext.admin.as_ref().is_some_and(|admin| {
+        admin.role.allows(&Permission::Content(
+            Action::Edit,
+            GeneralItem::Multi(UploadMultipartItemType::LandingSlides),
+        ))
+    });
+    let tmpl = LandingSlidesTemplate {
+        logged_in: soft_auth_ext.is_logged_in,
+        csrf_token: soft_auth_ext.csrf_token.clone().unwrap_or_default(),
+        can_edit,
+        slides,
+    };
+    match tmpl.render() {
+        Ok(r) => Html(r),
+        Err(err) => Html(format!("Error rendering Layout: {}", err)),
+    }
+}
+
+/// Two revisions of the revisions page to compare, newest against the one
+/// before it when left out
+#[derive(Debug, Deserialize)]
+pub struct RevisionsQuery {
+    from: Option<Uuid>,
+    to: Option<Uuid>,
+}
+
+#[derive(Template, Debug)]
+#[template(path = "admin/revisions.html")]
+pub struct RevisionsTemplate {
+    logged_in: bool,
+    csrf_token: String,
+    can_edit: bool,
+    item: String,
+    id: Uuid,
+    /// newest first
+    revisions: Vec<DBRevision>,
+    from: Option<Uuid>,
+    to: Option<Uuid>,
+    changes: Vec<FieldChange>,
+}
+
+impl RevisionsTemplate {
+    /// The revision older than the one at `loop.index`, which counts from 1
+    fn previous(&self, index: &usize) -> Option<&DBRevision> {
+        self.revisions.get(*index)
+    }
+
+    fn is_from(&self, id: &Uuid) -> bool {
+        self.from.as_ref() == Some(id)
+    }
+
+    fn is_to(&self, id: &Uuid) -> bool {
+        self.to.as_ref() == Some(id)
+    }
+}
+
+async fn load_revisions(
+    pool: &Pool<Postgres>,
+    item: GeneralItem,
+    id: Uuid,
+) -> anyhow::Result<Vec<DBRevision>> {
+    match item {
+        GeneralItem::Form(UploadFormItemType::Debriefs) => DBTestimonial::revisions(id, pool).await,
+        GeneralItem::Multi(UploadMultipartItemType::PatrolLog) => {
+            DBPatrolLog::revisions(id, pool).await
+        }
+        GeneralItem::Multi(UploadMultipartItemType::Dedications) => {
+            DBDedication::revisions(id, pool).await
+        }
+        GeneralItem::Multi(UploadMultipartItemType::Support) => {
+            DBResource::revisions(id, pool).await
+        }
+        GeneralItem::Multi(UploadMultipartItemType::BoardMembers) => {
+            DBBoardMember::revisions(id, pool).await
+        }
+        GeneralItem::Multi(UploadMultipartItemType::LandingSlides) => {
+            DBLandingSlide::revisions(id, pool).await
+        }
+        GeneralItem::Multi(UploadMultipartItemType::PatrolGear) => {
+            DBPatrolGear::revisions(id, pool).await
+        }
+    }
+}
+
+/// File names of the images the revisions show, by id
+async fn image_names(
+    pool: &Pool<Postgres>,
+    revisions: &[DBRevision],
+) -> anyhow::Result<HashMap<Uuid, String>> {
+    let ids: Vec<Uuid> = revisions
+        .iter()
+        .flat_map(|r| r.image_ids.iter().copied())
+        .collect();
+    Ok(DBImage::get_by_ids(&ids, pool)
+        .await?
+        .into_iter()
+        .map(|img| {
+            let name = std::path::Path::new(&img.path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or(img.path);
+            (img.id, name)
+        })
+        .collect())
+}
+
+#[tracing::instrument(name = "admin revisions", skip(data, soft_auth_ext))]
+pub async fn revisions(
+    State(data): State<SharedState>,
+    Path((item_str, id)): Path<(String, Uuid)>,
+    Query(query): Query<RevisionsQuery>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+) -> Html<String> {
+    let item = match GeneralItem::try_from(item_str.as_str()) {
+        Ok(item) => item,
+        // the error repeats the item straight from the url
+        Err(err) => return Html(MarkupDisplay::new_unsafe(err, askama::Html).to_string()),
+    };
+    let can_edit = soft_auth_ext
+        .admin
+        .as_ref()
+        .is_some_and(|a| a.role.allows(&Permission::Content(Action::Edit, item)));
+    let r = data.read().await;
+    let revisions = match can_edit {
+        true => match load_revisions(&r.db, item, id).await {
+            Ok(revisions) => revisions,
+            Err(err) => return Html(format!("A database error occured: {:?}", err)),
+        },
+        false => vec![],
+    };
+
+    let from = query.from.or(revisions.get(1).map(|r| r.id));
+    let to = query.to.or(revisions.first().map(|r| r.id));
+    let find = |id: Option<Uuid>| revisions.iter().find(|r| Some(r.id) == id);
+    let changes = match (find(from), find(to)) {
+        (Some(from), Some(to)) => match image_names(&r.db, &revisions).await {
+            Ok(names) => to.changes_since(from, &names),
+            Err(err) => return Html(format!("A database error occured: {:?}", err)),
+        },
+        _ => vec![],
+    };
+
+    let tmpl = RevisionsTemplate {
+        logged_in: soft_auth_ext.is_logged_in,
+        csrf_token: soft_auth_ext.csrf_token.clone().unwrap_or_default(),
+        can_edit,
+        item: item.as_str().to_string(),
+        id,
+        revisions,
+        from,
+        to,
+        changes,
+    };
+    match tmpl.render() {
+        Ok(r) => Html(r),
+        Err(err) => Html(format!("Error rendering Layout: {}", err)),
+    }
+}
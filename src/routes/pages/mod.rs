@@ -5,4 +5,20 @@ pub mod dedications;
 pub mod landing;
 pub mod patrol_gear;
 pub mod patrol_log;
+pub mod search;
 pub mod support;
+pub mod tags;
+
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Query of the list pages. `after` is set by their "load more" button, which
+/// only wants the next items back. `id` narrows the page down to one item,
+/// for links straight to it, `tag` to the items with that tag on pages whose
+/// items have tags
+#[derive(Debug, Default, Deserialize)]
+pub struct PageQuery {
+    pub after: Option<String>,
+    pub id: Option<Uuid>,
+    pub tag: Option<String>,
+}
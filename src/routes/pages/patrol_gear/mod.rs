@@ -1,208 +1,133 @@
-use std::collections::HashMap;
-
 use askama::Template;
-use axum::response::Html;
-use tracing::warn;
+use axum::{extract::State, response::Html, Extension};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::{
+    auth::middleware::SoftAuthExtension,
+    components::carousel::{CarouselTemplate, HasCarousel, Image},
+    database::{
+        models::{DBImage, DBPatrolGear, DBPatrolGearParams, GearCategory, GearVariant},
+        query::{Direction, ListQuery},
+    },
+    state::SharedState,
+};
 
-use crate::components::carousel::{CarouselTemplate, HasCarousel, Image};
+pub const PATROL_GEAR: &str = "patrol_gear";
 
-use crate::util::all_images_in_directory;
+/// The tabs of the page, the first one is open when it loads
+const TABS: [GearCategory; 3] = [GearCategory::Hats, GearCategory::Misc, GearCategory::Tops];
 
 #[derive(Template, Debug)]
 #[template(path = "pages/patrol_gear.html")]
 pub struct PatrolGearTemplate {
-    gear: HashMap<String, Vec<Gear>>,
+    tabs: Vec<(GearCategory, Vec<Gear>)>,
+    admin: bool,
+    csrf_token: String,
 }
 
-pub async fn patrol_gear() -> Html<String> {
+impl HasCarousel for PatrolGearTemplate {}
+
+#[tracing::instrument(name = "patrol gear page", skip_all)]
+pub async fn patrol_gear(
+    State(data): State<SharedState>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+) -> Html<String> {
+    let tabs = match catalog(&data.read().await.db, soft_auth_ext.is_logged_in).await {
+        Ok(tabs) => tabs,
+        Err(err) => return Html(format!("A database error occured: {:?}", err)),
+    };
     let template = PatrolGearTemplate {
-        gear: builtin_gear(),
+        tabs,
+        admin: soft_auth_ext.is_logged_in,
+        csrf_token: soft_auth_ext.csrf_token.clone().unwrap_or_default(),
     };
-    warn!("got gear template: {:?}", template);
     match template.render() {
         Ok(r) => Html(r),
         Err(err) => Html(format!("Error rendering Layout: {}", err.to_string())),
     }
 }
-impl HasCarousel for PatrolGearTemplate {}
 
 #[derive(Debug)]
 pub struct Gear {
-    id: u32,
-    price: u32,
+    id: Uuid,
+    name: String,
+    price: i32,
+    description: String,
+    variants: Vec<GearVariant>,
+    in_stock: bool,
+    /// Set on gear visitors don't see
+    badge: Option<String>,
+    /// Places of the gear before and after it in its tab among all gear, for
+    /// the reorder buttons
+    up: Option<usize>,
+    down: Option<usize>,
     carousel: CarouselTemplate,
 }
 
-impl PatrolGearTemplate {
-    fn tops(&self) -> &Vec<Gear> {
-        self.gear.get(TOPS).unwrap()
-    }
-    fn hats(&self) -> &Vec<Gear> {
-        self.gear.get(HATS).unwrap()
-    }
-    fn misc(&self) -> &Vec<Gear> {
-        self.gear.get(MISC).unwrap()
+impl Gear {
+    fn new(
+        gear: DBPatrolGear,
+        images: Vec<DBImage>,
+        up: Option<usize>,
+        down: Option<usize>,
+    ) -> Self {
+        let images: Vec<Image> = images.into_iter().map(Image::from).collect();
+        Self {
+            id: gear.id,
+            in_stock: gear.in_stock(),
+            badge: (!gear.active).then(|| "Hidden".to_string()),
+            name: gear.name,
+            price: gear.price,
+            description: gear.description,
+            variants: gear.variants.0,
+            up,
+            down,
+            carousel: CarouselTemplate {
+                show_subtitles: images.iter().any(|img| !img.subtitle.is_empty()),
+                images,
+                auto_scroll: false,
+            },
+        }
     }
 }
 
-pub const TOPS: &str = "Tops";
-pub const HATS: &str = "Hats";
-pub const MISC: &str = "Miscellaneous";
-
-fn builtin_gear() -> HashMap<String, Vec<Gear>> {
-    let merch_path = "public/assets/images/merchandise";
-    let hats_path = format!("{}/hats", merch_path);
-    let misc_path = format!("{}/misc", merch_path);
-    let tops_path = format!("{}/tops", merch_path);
-
-    let all_misc_imgs: Vec<String> = all_images_in_directory(&misc_path)
-        .unwrap()
-        .iter()
-        .map(|p| p.to_str().unwrap().to_string())
-        .collect();
-
-    let mut all_misc = vec![];
-    for src in all_misc_imgs {
-        if src.contains("sticker") {
-            let images = vec![Image {
-                src,
-                alt: String::new(),
-                subtitle: String::from("2 inches Tall and 3 ¾ Wide"),
-            }];
-            all_misc.push(Gear {
-                id: 1,
-                price: 5,
-                carousel: CarouselTemplate {
-                    images,
-                    auto_scroll: false,
-                    show_subtitles: true,
-                },
-            })
-        } else if src.contains("patch") {
-            let images = vec![Image {
-                src,
-                alt: String::new(),
-                subtitle: String::from("2 ¾ inches round"),
-            }];
-            all_misc.push(Gear {
-                id: 2,
-                price: 10,
-                carousel: CarouselTemplate {
-                    images,
-                    auto_scroll: false,
-                    show_subtitles: true,
-                },
-            })
-        } else {
-            let images = vec![Image {
-                src,
-                alt: String::new(),
-                subtitle: String::new(),
-            }];
-            all_misc.push(Gear {
-                id: 3,
-                price: 22,
-                carousel: CarouselTemplate {
-                    images,
-                    auto_scroll: false,
-                    show_subtitles: false,
-                },
-            })
-        };
-        all_misc.sort_by(|a, b| b.id.cmp(&a.id))
+/// Gear by tab in the order it's shown. Inactive gear is only listed for admins
+async fn catalog(
+    pool: &Pool<Postgres>,
+    admin: bool,
+) -> anyhow::Result<Vec<(GearCategory, Vec<Gear>)>> {
+    let mut list = ListQuery::new().sort_by(DBPatrolGear::POSITION, Direction::Asc);
+    if !admin {
+        list = list.filter_eq(DBPatrolGear::ACTIVE, true);
     }
+    let page =
+        DBImage::get_page_with_images::<DBPatrolGear, DBPatrolGearParams>(&list, pool).await?;
 
-    let all_tops_imgs: Vec<String> = all_images_in_directory(&tops_path)
-        .unwrap()
-        .iter()
-        .map(|p| p.to_str().unwrap().to_string())
-        .collect();
-
-    let mut all_tops = vec![];
-    let (hoodie_imgs, t_imgs): (Vec<String>, Vec<String>) = all_tops_imgs
-        .into_iter()
-        .partition(|src| src.contains("hoodie"));
-    let images = hoodie_imgs
+    // places count from 1 across every tab, the way the reorder handler takes them
+    let mut placed: Vec<_> = page
+        .items
         .into_iter()
-        .map(|src| Image {
-            src,
-            alt: String::new(),
-            subtitle: String::new(),
-        })
+        .enumerate()
+        .map(|(idx, (gear, images))| (idx + 1, gear, images))
         .collect();
-    all_tops.push(Gear {
-        id: 1,
-        price: 60,
-        carousel: CarouselTemplate {
-            images,
-            auto_scroll: false,
-            show_subtitles: false,
-        },
-    });
-    let images = t_imgs
+    Ok(TABS
         .into_iter()
-        .map(|src| Image {
-            src,
-            alt: String::new(),
-            subtitle: String::new(),
+        .map(|category| {
+            let (tab, rest): (Vec<_>, Vec<_>) = placed
+                .drain(..)
+                .partition(|(_, gear, _)| gear.category == category);
+            placed = rest;
+            let places: Vec<usize> = tab.iter().map(|(place, _, _)| *place).collect();
+            let gear = tab
+                .into_iter()
+                .enumerate()
+                .map(|(i, (_, gear, images))| {
+                    let up = i.checked_sub(1).map(|before| places[before]);
+                    Gear::new(gear, images, up, places.get(i + 1).copied())
+                })
+                .collect();
+            (category, gear)
         })
-        .collect();
-
-    all_tops.push(Gear {
-        id: 2,
-        price: 40,
-        carousel: CarouselTemplate {
-            images,
-            auto_scroll: false,
-            show_subtitles: false,
-        },
-    });
-
-    let all_hats_imgs: Vec<String> = all_images_in_directory(&hats_path)
-        .unwrap()
-        .iter()
-        .map(|p| p.to_str().unwrap().to_string())
-        .collect();
-
-    let mut all_hats = vec![];
-    for src in all_hats_imgs {
-        let price = 40;
-        let id = match src.rsplit_once("/").unwrap().1 {
-            "black-baseball.webp" => 1,
-            "black-baseball2.webp" => 2,
-            "black-beanie.webp" => 3,
-            "camo-baseball.webp" => 4,
-            "camo-flatbill.webp" => 5,
-            "camo-flatbill2.webp" => 6,
-            "camo-trucker.webp" => 7,
-            "grey-flatbill.webp" => 8,
-            "grey-flatbill2.webp" => 9,
-            "grey-red-flatbill.webp" => 10,
-            "white-beanie.webp" => 11,
-            other => panic!("encountered unexpected hat img: {}", other),
-        };
-
-        let images = vec![Image {
-            src,
-            alt: String::new(),
-            subtitle: String::new(),
-        }];
-        all_hats.push(Gear {
-            id,
-            price,
-            carousel: CarouselTemplate {
-                images,
-                auto_scroll: false,
-                show_subtitles: false,
-            },
-        });
-    }
-
-    all_hats.sort_by(|a, b| a.id.cmp(&b.id));
-
-    let mut map = HashMap::new();
-    map.insert(TOPS.to_string(), all_tops);
-    map.insert(HATS.to_string(), all_hats);
-    map.insert(MISC.to_string(), all_misc);
-    map
+        .collect())
 }
@@ -5,26 +5,49 @@ use axum::{
     Extension,
 };
 use chrono::NaiveDate;
-use rand::Rng;
 use serde::Deserialize;
 use sqlx::{Pool, Postgres};
-use std::collections::HashMap;
 use tracing::warn;
 use uuid::Uuid;
 
 use crate::{
     auth::middleware::SoftAuthExtension,
-    components::carousel::{CarouselTemplate, HasCarousel, Image},
-    database::models::{DBImage, DBPatrolLog, DBPatrolLogParams},
+    components::{
+        carousel::{CarouselTemplate, HasCarousel, Image},
+        tags::TagFilterTemplate,
+    },
+    database::{
+        handles::DbData,
+        models::{DBImage, DBPatrolLog, DBPatrolLogParams, DBTag},
+        query::{Direction, ListQuery, DEFAULT_PAGE_SIZE},
+    },
+    routes::pages::search::url_encode,
     state::SharedState,
-    util,
 };
 
 #[derive(Template, Debug)]
 #[template(path = "pages/patrol_log.html")]
 pub struct PatrolLogTemplate {
-    logs: Vec<Log>,
+    items: PatrolLogItemsTemplate,
+    tag_filter: TagFilterTemplate,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    tag: Option<String>,
     admin: bool,
+    csrf_token: String,
+}
+
+#[derive(Template, Debug)]
+#[template(path = "components/patrol_log_items.html")]
+pub struct PatrolLogItemsTemplate {
+    logs: Vec<Log>,
+    more_url: Option<String>,
+}
+
+impl PatrolLogItemsTemplate {
+    pub fn is_empty(&self) -> bool {
+        self.logs.is_empty()
+    }
 }
 
 #[derive(Template, Debug)]
@@ -32,6 +55,7 @@ pub struct PatrolLogTemplate {
 pub struct SinglePatrolLogTemplate {
     log: Log,
     admin: bool,
+    csrf_token: String,
 }
 
 pub const PATROL_LOG: &str = "patrol_log";
@@ -43,21 +67,16 @@ pub struct Log {
     pub description: String,
     pub date: NaiveDate,
     pub carousel: CarouselTemplate,
+    /// Set on logs that aren't live yet, see
+    /// [`crate::database::models::ContentStatus::badge`]
+    pub badge: Option<String>,
+    pub tags: Vec<DBTag>,
 }
-impl HasCarousel for PatrolLogTemplate {}
+impl HasCarousel for PatrolLogItemsTemplate {}
 
 impl From<(DBPatrolLog, Vec<DBImage>)> for Log {
     fn from((log, images): (DBPatrolLog, Vec<DBImage>)) -> Self {
-        let images: Vec<Image> = images
-            .into_iter()
-            .filter_map(|i| {
-                if log.img_ids.contains(&i.id) {
-                    Some(i.into())
-                } else {
-                    None
-                }
-            })
-            .collect();
+        let images: Vec<Image> = images.into_iter().map(|i| i.into()).collect();
 
         let carousel = CarouselTemplate {
             show_subtitles: false,
@@ -66,149 +85,198 @@ impl From<(DBPatrolLog, Vec<DBImage>)> for Log {
         };
         Self {
             id: log.id,
+            badge: log.status.badge(&log.publish_at),
             heading: log.heading,
             description: log.description,
             date: log.date,
             carousel,
+            tags: vec![],
         }
     }
 }
 
-async fn get_logs(pool: &Pool<Postgres>) -> anyhow::Result<Vec<Log>> {
-    let all_logs_and_imgs =
-        DBImage::get_multiple_with_images::<DBPatrolLog, DBPatrolLogParams>(&pool).await?;
-    let mut all_logs = vec![];
-    for (log, imgs) in all_logs_and_imgs {
-        all_logs.push(Log::from((log, imgs)));
-    }
-    Ok(all_logs)
-}
-
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct PatrolLogQuery {
     heading: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    from: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    to: Option<NaiveDate>,
+    tag: Option<String>,
+    after: Option<String>,
 }
 
-#[tracing::instrument(name = "patrol log template rendering", skip_all)]
-pub async fn patrol_log(
-    State(data): State<SharedState>,
-    queries: Option<Query<PatrolLogQuery>>,
-    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
-) -> Html<String> {
-    let r = data.read().await;
-    let mut logs_map = HashMap::new();
-
-    for l in get_logs(&r.db).await.unwrap() {
-        logs_map.insert(l.heading.to_owned(), l);
+/// The date inputs of the filter form send an empty string when cleared
+fn empty_as_none<'de, D>(deserializer: D) -> Result<Option<NaiveDate>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Option::<String>::deserialize(deserializer)?.as_deref() {
+        None | Some("") => Ok(None),
+        Some(date) => date.parse().map(Some).map_err(serde::de::Error::custom),
     }
+}
 
-    for l in generate_activities(5) {
-        logs_map.insert(l.heading.to_owned(), l);
+impl PatrolLogQuery {
+    /// The first page of logs with the tag that has `slug`
+    pub(crate) fn tagged(slug: &str) -> Self {
+        Self {
+            tag: Some(slug.to_string()),
+            ..Default::default()
+        }
     }
 
-    for l in builtin_logs() {
-        logs_map.insert(l.heading.to_owned(), l);
+    fn more_url(&self, next: String) -> String {
+        let mut url = format!("/patrol_log?after={}", next);
+        if let Some(from) = self.from {
+            url.push_str(&format!("&from={}", from));
+        }
+        if let Some(to) = self.to {
+            url.push_str(&format!("&to={}", to));
+        }
+        if let Some(tag) = &self.tag {
+            url.push_str(&format!("&tag={}", url_encode(tag)));
+        }
+        url
     }
 
-    if let Some(q) = queries {
-        warn!("got query: {:?}", q);
-        if let Some(heading) = q.0.heading {
-            match logs_map.get(&heading) {
-                Some(log) => {
-                    warn!("building template for log: {:?}", log);
-                    let template = Some(SinglePatrolLogTemplate {
-                        log: log.clone(),
-                        admin: soft_auth_ext.is_logged_in,
-                    });
-
-                    return match template.unwrap().render() {
-                        Ok(r) => Html(r),
-                        Err(err) => Html(format!("Error rendering Layout: {}", err.to_string())),
-                    };
-                }
-                None => return Html(format!("{} is not a valid log heading", heading)),
-            }
+    /// The page with the dates it's filtered by, for the tag chips to add to
+    fn tag_filter_url(&self) -> String {
+        let mut url = "/patrol_log?".to_string();
+        if let Some(from) = self.from {
+            url.push_str(&format!("from={}&", from));
+        }
+        if let Some(to) = self.to {
+            url.push_str(&format!("to={}&", to));
         }
+        url
     }
+}
 
-    let logs = logs_map.into_values().collect();
-    let template = Some(PatrolLogTemplate {
+/// Newest logs first. Drafts and scheduled logs are only listed for admins
+pub(crate) async fn get_logs(
+    pool: &Pool<Postgres>,
+    query: &PatrolLogQuery,
+    admin: bool,
+) -> anyhow::Result<PatrolLogItemsTemplate> {
+    let mut list = ListQuery::new()
+        .sort_by(DBPatrolLog::DATE, Direction::Desc)
+        .filter_range(DBPatrolLog::DATE, query.from, query.to)
+        .limit(DEFAULT_PAGE_SIZE)
+        .after(query.after.as_deref())?;
+    if let Some(tag) = &query.tag {
+        list = list.tagged(tag);
+    }
+    if !admin {
+        list = list.published();
+    }
+    let page = DBImage::get_page_with_images::<DBPatrolLog, DBPatrolLogParams>(&list, pool).await?;
+    let ids: Vec<Uuid> = page.items.iter().map(|(log, _)| log.id).collect();
+    let mut tags = DBTag::for_entities(&DBPatrolLog::table_name(), &ids, pool).await?;
+    let logs: Vec<Log> = page
+        .items
+        .into_iter()
+        .map(|item| {
+            let mut log = Log::from(item);
+            log.tags = tags.remove(&log.id).unwrap_or_default();
+            log
+        })
+        .collect();
+    Ok(PatrolLogItemsTemplate {
         logs,
-        admin: soft_auth_ext.is_logged_in,
-    });
+        more_url: page.next.map(|next| query.more_url(next)),
+    })
+}
 
-    match template.unwrap().render() {
-        Ok(r) => Html(r),
-        Err(err) => Html(format!("Error rendering Layout: {}", err.to_string())),
+async fn get_log(pool: &Pool<Postgres>, heading: &str, admin: bool) -> anyhow::Result<Option<Log>> {
+    let mut list = ListQuery::new()
+        .filter_eq(DBPatrolLog::HEADING, heading)
+        .limit(1);
+    if !admin {
+        list = list.published();
+    }
+    let page = DBImage::get_page_with_images::<DBPatrolLog, DBPatrolLogParams>(&list, pool).await?;
+    match page.items.into_iter().next() {
+        Some(item) => {
+            let mut log = Log::from(item);
+            log.tags = DBPatrolLog::tags(log.id, pool).await?;
+            Ok(Some(log))
+        }
+        None => Ok(None),
     }
 }
 
-fn builtin_logs() -> Vec<Log> {
-    let images =
-        util::all_images_in_directory("public/assets/images/patrol_log/fishing_trip").unwrap();
-    let images = images
-        .into_iter()
-        .map(|path| Image {
-            src: path.to_str().unwrap().to_string(),
-            alt: String::new(),
-            subtitle: String::new(),
-        })
-        .collect();
+#[tracing::instrument(name = "patrol log template rendering", skip_all)]
+pub async fn patrol_log(
+    State(data): State<SharedState>,
+    queries: Option<Query<PatrolLogQuery>>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+) -> Html<String> {
+    let r = data.read().await;
+    let query = queries.map(|q| q.0).unwrap_or_default();
+    warn!("got query: {:?}", query);
+
+    if let Some(heading) = &query.heading {
+        return match get_log(&r.db, heading, soft_auth_ext.is_logged_in).await {
+            Ok(Some(log)) => {
+                warn!("building template for log: {:?}", log);
+                let template = SinglePatrolLogTemplate {
+                    log,
+                    admin: soft_auth_ext.is_logged_in,
+                    csrf_token: soft_auth_ext.csrf_token.clone().unwrap_or_default(),
+                };
+                match template.render() {
+                    Ok(r) => Html(r),
+                    Err(err) => Html(format!("Error rendering Layout: {}", err)),
+                }
+            }
+            Ok(None) => Html(format!("{} is not a valid log heading", heading)),
+            Err(err) => Html(format!("A database error occured: {:?}", err)),
+        };
+    }
 
-    let carousel = CarouselTemplate {
-        show_subtitles: false,
-        images,
-        auto_scroll: false,
+    let admin = soft_auth_ext.is_logged_in;
+    let items = match get_logs(&r.db, &query, admin).await {
+        Ok(items) => items,
+        Err(err) => return Html(format!("A database error occured: {:?}", err)),
     };
-    let fishing_trip = Log {
-        id: Uuid::new_v4(),
-        heading: "Semperflies Fishing Trip".to_string(),
-        description: "Semper Flies Foundation & Tahoe Fly Fishing Outfitters teamed up to send (2) Combat Veterans on a fly fishing trip they would remember for the rest of their lives.".to_string(),
-        date: NaiveDate::from_ymd_opt(2023, 06, 21).unwrap(),
-        carousel,
+    let rendered = match query.after {
+        Some(_) => items.render(),
+        None => PatrolLogTemplate {
+            items,
+            tag_filter: TagFilterTemplate {
+                tags: match DBTag::used_by(&DBPatrolLog::table_name(), admin, &r.db).await {
+                    Ok(tags) => tags,
+                    Err(err) => return Html(format!("A database error occured: {:?}", err)),
+                },
+                url: query.tag_filter_url(),
+                active: query.tag.clone(),
+                target: "#log-content",
+            },
+            from: query.from,
+            to: query.to,
+            tag: query.tag.clone(),
+            admin: soft_auth_ext.is_logged_in,
+            csrf_token: soft_auth_ext.csrf_token.clone().unwrap_or_default(),
+        }
+        .render(),
     };
-    vec![fishing_trip]
+    match rendered {
+        Ok(r) => Html(r),
+        Err(err) => Html(format!("Error rendering Layout: {}", err)),
+    }
 }
 
-fn generate_activities(amt: i32) -> Vec<Log> {
-    let mut rng = rand::thread_rng();
-    let image_urls = vec![
-        "public/assets/images/board_members/business.webp".to_string(),
-        "public/assets/images/board_members/business2.webp".to_string(),
-        "public/assets/images/board_members/old.webp".to_string(),
-    ];
-
-    let mut activities = Vec::new();
-    for _ in 0..amt {
-        let heading = format!("Heading for activity {}", activities.len() + 1);
-        let description = format!("Description of activity {}", activities.len() + 1);
-        let year = rng.gen_range(1950..1990);
-        let month = rng.gen_range(1..13);
-        let day = rng.gen_range(1..29);
-        let date = NaiveDate::from_ymd_opt(year, month, day).unwrap();
-
-        let amt_imgs = rng.gen_range(0..=3);
-        let mut images = vec![];
-        for i in 0..amt_imgs {
-            images.push(Image {
-                src: image_urls[i].to_owned(),
-                alt: String::new(),
-                subtitle: String::new(),
-            })
-        }
-        let carousel = CarouselTemplate {
-            show_subtitles: false,
-            images,
-            auto_scroll: false,
-        };
+#[cfg(test)]
+mod tests {
+    use super::PatrolLogQuery;
 
-        activities.push(Log {
-            id: uuid::Uuid::new_v4(),
-            heading,
-            description,
-            date,
-            carousel,
-        });
+    #[test]
+    fn tags_survive_the_more_url() {
+        let query = PatrolLogQuery::tagged("fish & chips #1");
+        assert_eq!(
+            query.more_url("next".to_string()),
+            "/patrol_log?after=next&tag=fish%20%26%20chips%20%231"
+        );
     }
-    activities
 }
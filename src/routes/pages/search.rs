@@ -0,0 +1,204 @@
+use askama::Template;
+use axum::{
+    extract::{Query, State},
+    response::Html,
+    Extension,
+};
+use serde::Deserialize;
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    auth::middleware::SoftAuthExtension,
+    database::{
+        models::{
+            DBDedication, DBDedicationParams, DBPatrolLog, DBPatrolLogParams, DBResource,
+            DBResourceParams, DBTestimonial, DBTestimonialParams,
+        },
+        query::DEFAULT_PAGE_SIZE,
+        search::{highlight, search as search_table, SearchHit},
+    },
+    routes::pages::{
+        debriefs::DEBRIEFS, dedications::DEDICATIONS, patrol_log::logs::PATROL_LOG,
+        support::SUPPORT,
+    },
+    state::SharedState,
+};
+
+#[derive(Template, Debug)]
+#[template(path = "pages/search.html")]
+pub struct SearchTemplate {
+    terms: String,
+    results: SearchResultsTemplate,
+}
+
+#[derive(Template, Debug)]
+#[template(path = "components/search_results.html")]
+pub struct SearchResultsTemplate {
+    searched: bool,
+    groups: Vec<SearchGroup>,
+}
+
+/// The hits of one content type, under the name the site gives it
+#[derive(Debug)]
+pub struct SearchGroup {
+    label: &'static str,
+    results: Vec<SearchResult>,
+}
+
+#[derive(Debug)]
+pub struct SearchResult {
+    link: String,
+    /// Both escaped html, only the marks are tags
+    title: String,
+    snippet: String,
+}
+
+impl SearchResult {
+    fn new(hit: SearchHit, link: String) -> Self {
+        Self {
+            link,
+            title: highlight(&hit.marked_title),
+            snippet: highlight(&hit.snippet),
+        }
+    }
+
+    /// Links by id to a list page narrowed down to the one item
+    fn listed(item: &str, hit: SearchHit) -> Self {
+        let link = format!("/{}?id={}", item, hit.id);
+        Self::new(hit, link)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchQuery {
+    q: Option<String>,
+}
+
+impl SearchQuery {
+    fn terms(&self) -> &str {
+        self.q.as_deref().map(str::trim).unwrap_or_default()
+    }
+}
+
+/// Best matches first within each content type. Drafts and scheduled items
+/// are only found by admins
+async fn get_results(
+    pool: &Pool<Postgres>,
+    terms: &str,
+    admin: bool,
+) -> anyhow::Result<SearchResultsTemplate> {
+    if terms.is_empty() {
+        return Ok(SearchResultsTemplate {
+            searched: false,
+            groups: vec![],
+        });
+    }
+    let limit = DEFAULT_PAGE_SIZE;
+    let groups = vec![
+        SearchGroup {
+            label: "Speak their names",
+            results: search_table::<DBDedication, DBDedicationParams>(terms, admin, limit, pool)
+                .await?
+                .into_iter()
+                .map(|hit| SearchResult::listed(DEDICATIONS, hit))
+                .collect(),
+        },
+        SearchGroup {
+            label: "Patrol Log",
+            // logs already have a page of their own
+            results: search_table::<DBPatrolLog, DBPatrolLogParams>(terms, admin, limit, pool)
+                .await?
+                .into_iter()
+                .map(|hit| {
+                    let link = format!("/{}?heading={}", PATROL_LOG, url_encode(&hit.title));
+                    SearchResult::new(hit, link)
+                })
+                .collect(),
+        },
+        SearchGroup {
+            label: "Debriefs",
+            results: search_table::<DBTestimonial, DBTestimonialParams>(terms, admin, limit, pool)
+                .await?
+                .into_iter()
+                .map(|hit| SearchResult::listed(DEBRIEFS, hit))
+                .collect(),
+        },
+        SearchGroup {
+            label: "Support",
+            results: search_table::<DBResource, DBResourceParams>(terms, admin, limit, pool)
+                .await?
+                .into_iter()
+                .map(|hit| SearchResult::listed(SUPPORT, hit))
+                .collect(),
+        },
+    ];
+    Ok(SearchResultsTemplate {
+        searched: true,
+        groups: groups
+            .into_iter()
+            .filter(|g| !g.results.is_empty())
+            .collect(),
+    })
+}
+
+/// Percent encodes everything but unreserved characters, for query values
+pub(crate) fn url_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            other => format!("%{:02X}", other),
+        })
+        .collect()
+}
+
+#[tracing::instrument(name = "search page", skip_all)]
+pub async fn search(
+    State(data): State<SharedState>,
+    Query(query): Query<SearchQuery>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+) -> Html<String> {
+    let r = data.read().await;
+    let results = match get_results(&r.db, query.terms(), soft_auth_ext.is_logged_in).await {
+        Ok(results) => results,
+        Err(err) => return Html(format!("A database error occured: {:?}", err)),
+    };
+    let tmpl = SearchTemplate {
+        terms: query.terms().to_string(),
+        results,
+    };
+    match tmpl.render() {
+        Ok(r) => Html(r),
+        Err(err) => Html(format!("Error rendering Layout: {}", err)),
+    }
+}
+
+/// Just the results, for the search box to swap in as it's typed into
+#[tracing::instrument(name = "search results", skip_all)]
+pub async fn search_results(
+    State(data): State<SharedState>,
+    Query(query): Query<SearchQuery>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+) -> Html<String> {
+    let r = data.read().await;
+    match get_results(&r.db, query.terms(), soft_auth_ext.is_logged_in).await {
+        Ok(results) => match results.render() {
+            Ok(r) => Html(r),
+            Err(err) => Html(format!("Error rendering Layout: {}", err)),
+        },
+        Err(err) => Html(format!("A database error occured: {:?}", err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::url_encode;
+
+    #[test]
+    fn headings_survive_the_query_string() {
+        assert_eq!(url_encode("Fishing & Fun #2"), "Fishing%20%26%20Fun%20%232");
+        assert_eq!(url_encode("Año"), "A%C3%B1o");
+    }
+}
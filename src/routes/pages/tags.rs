@@ -0,0 +1,81 @@
+use askama::{MarkupDisplay, Template};
+use axum::{
+    extract::{Path, State},
+    response::Html,
+    Extension,
+};
+use sqlx::{Pool, Postgres};
+
+use crate::{
+    auth::middleware::SoftAuthExtension,
+    database::models::DBTag,
+    routes::pages::{
+        patrol_log::logs::{get_logs, PatrolLogItemsTemplate, PatrolLogQuery},
+        support::{get_resources, SupportItemsTemplate},
+        PageQuery,
+    },
+    state::SharedState,
+};
+
+/// Everything with one tag, the patrol logs first
+#[derive(Template, Debug)]
+#[template(path = "pages/tag.html")]
+pub struct TagTemplate {
+    tag: DBTag,
+    logs: PatrolLogItemsTemplate,
+    resources: SupportItemsTemplate,
+    admin: bool,
+    csrf_token: String,
+}
+
+impl TagTemplate {
+    fn is_empty(&self) -> bool {
+        self.logs.is_empty() && self.resources.is_empty()
+    }
+}
+
+async fn get_tagged(
+    pool: &Pool<Postgres>,
+    slug: &str,
+    admin: bool,
+    csrf_token: String,
+) -> anyhow::Result<Option<TagTemplate>> {
+    let Some(tag) = DBTag::get_by_slug(slug, pool).await? else {
+        return Ok(None);
+    };
+    let logs = get_logs(pool, &PatrolLogQuery::tagged(slug), admin).await?;
+    let resource_query = PageQuery {
+        tag: Some(slug.to_string()),
+        ..Default::default()
+    };
+    let resources = get_resources(pool, &resource_query, admin).await?;
+    Ok(Some(TagTemplate {
+        tag,
+        logs,
+        resources,
+        admin,
+        csrf_token,
+    }))
+}
+
+#[tracing::instrument(name = "tag page", skip(data, soft_auth_ext))]
+pub async fn tag(
+    State(data): State<SharedState>,
+    Path(slug): Path<String>,
+    Extension(soft_auth_ext): Extension<SoftAuthExtension>,
+) -> Html<String> {
+    let r = data.read().await;
+    let csrf_token = soft_auth_ext.csrf_token.clone().unwrap_or_default();
+    match get_tagged(&r.db, &slug, soft_auth_ext.is_logged_in, csrf_token).await {
+        Ok(Some(tmpl)) => match tmpl.render() {
+            Ok(r) => Html(r),
+            Err(err) => Html(format!("Error rendering Layout: {}", err)),
+        },
+        // the slug comes straight from the url
+        Ok(None) => Html(format!(
+            "{} is not a valid tag",
+            MarkupDisplay::new_unsafe(&slug, askama::Html)
+        )),
+        Err(err) => Html(format!("A database error occured: {:?}", err)),
+    }
+}
@@ -1,5 +1,5 @@
 use dotenv::dotenv;
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc};
 use tokio::sync::RwLock;
 
 use sqlx::{Pool, Postgres};
@@ -8,15 +8,23 @@ pub type SharedState = Arc<RwLock<AppState>>;
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub db: Pool<Postgres>,
-    // shuld be a salted stirgn
-    pub admin_session_id: Option<uuid::Uuid>,
     pub env: Config,
 }
 
+const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub database_url: String,
     pub jwt_secret: String,
+    /// origin passkeys are bound to, its host is used as the relying party id
+    pub webauthn_origin: String,
+    /// single sign-on is only offered when `OIDC_ISSUER` is set
+    pub oidc: Option<OidcConfig>,
+    /// how long deleted items stay in the trash, `TRASH_RETENTION_DAYS`
+    pub trash_retention: chrono::Duration,
+    /// reverse proxies whose `X-Forwarded-For` is believed, `TRUSTED_PROXIES`
+    pub trusted_proxies: Vec<IpAddr>,
     // pub jwt_expires_in: String,
     // pub jwt_maxage: i32,
 }
@@ -25,9 +33,67 @@ impl Config {
     pub fn init() -> Config {
         let database_url = std::env::var("DB_URL").expect("DATABASE_URL must be set");
         let jwt_secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
+        let webauthn_origin = std::env::var("WEBAUTHN_ORIGIN")
+            .or_else(|_| std::env::var("ALLOWED_ORIGIN"))
+            .unwrap_or_else(|_| "http://localhost:3000".to_string());
+        let trash_retention_days = std::env::var("TRASH_RETENTION_DAYS")
+            .ok()
+            .map(|days| days.parse().expect("TRASH_RETENTION_DAYS must be a number"))
+            .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS);
+        let trusted_proxies = std::env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                v.parse()
+                    .expect("TRUSTED_PROXIES must be a list of ip addresses")
+            })
+            .collect();
         Config {
             database_url,
             jwt_secret,
+            webauthn_origin,
+            oidc: OidcConfig::init(),
+            trash_retention: chrono::Duration::days(trash_retention_days),
+            trusted_proxies,
         }
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct OidcConfig {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// must point at `/data/auth/oidc/callback` and be registered with the provider
+    pub redirect_url: String,
+    /// anyone with a verified email on these domains gets a volunteer account
+    pub allowed_domains: Vec<String>,
+    /// individual addresses allowed in the same way as `allowed_domains`
+    pub allowed_emails: Vec<String>,
+}
+
+impl OidcConfig {
+    fn init() -> Option<OidcConfig> {
+        let issuer = std::env::var("OIDC_ISSUER").ok()?;
+        let list = |name: &str| -> Vec<String> {
+            std::env::var(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(|v| v.trim().to_lowercase())
+                .filter(|v| !v.is_empty())
+                .collect()
+        };
+        Some(OidcConfig {
+            issuer: issuer.trim_end_matches('/').to_string(),
+            client_id: std::env::var("OIDC_CLIENT_ID").expect("OIDC_CLIENT_ID must be set"),
+            client_secret: std::env::var("OIDC_CLIENT_SECRET")
+                .expect("OIDC_CLIENT_SECRET must be set"),
+            redirect_url: std::env::var("OIDC_REDIRECT_URL")
+                .expect("OIDC_REDIRECT_URL must be set"),
+            allowed_domains: list("OIDC_ALLOWED_DOMAINS"),
+            allowed_emails: list("OIDC_ALLOWED_EMAILS"),
+        })
+    }
+}